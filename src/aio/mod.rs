@@ -0,0 +1,349 @@
+//! Async counterparts on top of tokio.
+
+#[cfg(test)]
+pub(crate) mod tests;
+
+mod multiplexed;
+mod stream;
+
+pub use multiplexed::{MultiplexedConnection, MultiplexedOptions, Pushes};
+pub use stream::Stream;
+
+use std::{collections::VecDeque, error::Error, fmt, io, time::Duration};
+
+use anyhow::{anyhow, bail, Context};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::{TcpStream, ToSocketAddrs},
+    time::{timeout, timeout_at, Instant},
+};
+
+#[cfg(unix)]
+use tokio::net::UnixStream;
+
+use crate::{
+    cmd::Cmd,
+    config::{Addr, ConnectionInfo},
+    protocol::{ClientProtocol, Hello, RequestId},
+    value::{DecodeOptions, Value},
+};
+
+/// An async client connection driving [`ClientProtocol`] over any tokio stream,
+/// a [`TcpStream`] by default.
+#[derive(Debug)]
+pub struct Connection<S = TcpStream> {
+    stream: S,
+    protocol: ClientProtocol,
+    response_timeout: Option<Duration>,
+}
+
+/// Found in the chain of errors returned while sending when not a single byte of the
+/// request was written, so the server cannot have run any of its commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotSent;
+
+impl Connection {
+    pub async fn connect(addr: impl ToSocketAddrs) -> anyhow::Result<Self> {
+        let stream = TcpStream::connect(addr)
+            .await
+            .context("Connection (TcpStream::connect)")?;
+        stream
+            .set_nodelay(true)
+            .context("Connection (TcpStream::set_nodelay)")?;
+
+        Ok(Self::new(stream))
+    }
+}
+
+impl Connection<Stream> {
+    /// Connects as described, performing the handshake and selecting the database if needed.
+    pub async fn open(info: &ConnectionInfo) -> anyhow::Result<Self> {
+        let connect = async {
+            let stream = match &info.addr {
+                Addr::Tcp { host, port } => {
+                    let stream = TcpStream::connect((host.as_str(), *port))
+                        .await
+                        .context("Connection (TcpStream::connect)")?;
+                    stream
+                        .set_nodelay(true)
+                        .context("Connection (TcpStream::set_nodelay)")?;
+
+                    match info.tls.as_ref() {
+                        None => Stream::Tcp(stream),
+                        #[cfg(feature = "tls")]
+                        Some(tls) => {
+                            Stream::Tls(Box::new(crate::tls::connect(stream, host, tls).await?))
+                        }
+                        #[cfg(not(feature = "tls"))]
+                        Some(_) => bail!("Connection (TLS needs the tls feature)"),
+                    }
+                }
+                Addr::Unix(_) if info.tls.is_some() => {
+                    bail!("Connection (TLS over unix sockets is not supported)")
+                }
+                #[cfg(unix)]
+                Addr::Unix(path) => Stream::Unix(
+                    UnixStream::connect(path)
+                        .await
+                        .context("Connection (UnixStream::connect)")?,
+                ),
+                #[cfg(not(unix))]
+                Addr::Unix(_) => bail!("Connection (unix sockets are not supported)"),
+            };
+
+            anyhow::Ok(stream)
+        };
+
+        let stream = match info.connect_timeout {
+            Some(duration) => timeout(duration, connect)
+                .await
+                .map_err(|_| anyhow!("Connection (timed out connecting to {})", info.addr))??,
+            None => connect.await?,
+        };
+
+        let mut connection = Self::new(stream);
+        connection.set_response_timeout(info.response_timeout);
+
+        if let Some(hello) = info.hello() {
+            connection.hello(&hello).await?;
+        }
+
+        if info.db != 0 {
+            let select = Cmd::new("SELECT").arg(info.db.to_string());
+            connection
+                .send(&select)
+                .await?
+                .into_result()
+                .context("Connection (SELECT)")?;
+        }
+
+        Ok(connection)
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Connection<S> {
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream,
+            protocol: ClientProtocol::new(),
+            response_timeout: None,
+        }
+    }
+
+    pub fn protocol(&self) -> &ClientProtocol {
+        &self.protocol
+    }
+
+    pub fn set_decode_options(&mut self, options: DecodeOptions) {
+        self.protocol.set_decode_options(options);
+    }
+
+    /// Fails waiting for a reply longer than this, no limit by default. The connection
+    /// is broken after a timeout, as the reply may still arrive.
+    pub fn set_response_timeout(&mut self, duration: Option<Duration>) {
+        self.response_timeout = duration;
+    }
+
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
+    /// Performs the `HELLO` handshake, failing if the server refuses it.
+    pub async fn hello(&mut self, hello: &Hello) -> anyhow::Result<()> {
+        self.protocol.hello(hello);
+        self.transmit().await?;
+
+        let deadline = self.deadline();
+        loop {
+            if let Some(result) = self.protocol.poll_hello() {
+                return result;
+            }
+
+            self.receive(deadline).await?;
+        }
+    }
+
+    /// Sends a command and waits for its reply. Server errors are returned as values.
+    pub async fn send(&mut self, cmd: &Cmd) -> anyhow::Result<Value> {
+        let mut replies = self.pipeline(std::slice::from_ref(cmd)).await?;
+        Ok(replies.remove(0))
+    }
+
+    /// Sends all commands in one write and returns their replies in order.
+    pub async fn pipeline(&mut self, cmds: &[Cmd]) -> anyhow::Result<Vec<Value>> {
+        if !cmds.iter().all(ClientProtocol::expects_reply) {
+            bail!("Connection (commands without replies have to be sent with Connection::write)");
+        }
+
+        let mut ids = cmds
+            .iter()
+            .map(|cmd| self.protocol.send(cmd))
+            .collect::<VecDeque<_>>();
+
+        self.transmit().await?;
+
+        let mut replies = Vec::with_capacity(ids.len());
+        while let Some(expected) = ids.pop_front() {
+            replies.push(self.reply(expected).await?);
+        }
+
+        Ok(replies)
+    }
+
+    /// Sends a command confirmed with push messages, e.g. `SUBSCRIBE`.
+    pub async fn write(&mut self, cmd: &Cmd) -> anyhow::Result<RequestId> {
+        if ClientProtocol::expects_reply(cmd) {
+            bail!("Connection (commands with replies have to be sent with Connection::send)");
+        }
+
+        let id = self.protocol.send(cmd);
+        self.transmit().await?;
+
+        Ok(id)
+    }
+
+    /// Undoes what the commands sent so far left behind: unsubscribes from every channel,
+    /// discards a transaction in progress and selects database 0.
+    /// Push messages not read yet are dropped.
+    pub async fn reset(&mut self) -> anyhow::Result<()> {
+        let mut cmds = vec![];
+
+        if self.protocol.is_subscribed() {
+            for name in ["UNSUBSCRIBE", "PUNSUBSCRIBE", "SUNSUBSCRIBE"] {
+                self.protocol.send(&Cmd::new(name));
+            }
+
+            // replied to after every confirmation
+            cmds.push(Cmd::new("PING"));
+        }
+
+        if self.protocol.in_transaction() {
+            cmds.push(Cmd::new("DISCARD"));
+        }
+
+        if self.protocol.db() != 0 {
+            cmds.push(Cmd::new("SELECT").arg("0"));
+        }
+
+        if !cmds.is_empty() {
+            for reply in self.pipeline(&cmds).await? {
+                reply.into_result().context("Connection::reset")?;
+            }
+        }
+
+        self.protocol.clear_pushes();
+        Ok(())
+    }
+
+    /// Waits for the next push message.
+    pub async fn next_push(&mut self) -> anyhow::Result<Value> {
+        loop {
+            if let Some(result) = self.protocol.poll_push() {
+                return result;
+            }
+
+            self.receive(None).await?;
+        }
+    }
+
+    async fn reply(&mut self, expected: RequestId) -> anyhow::Result<Value> {
+        let deadline = self.deadline();
+
+        loop {
+            if let Some(result) = self.protocol.poll_reply(expected) {
+                return result;
+            }
+
+            self.receive(deadline).await?;
+        }
+    }
+
+    // When the whole of the reply being waited for has to be there, pushes may take any time.
+    fn deadline(&self) -> Option<Instant> {
+        self.response_timeout
+            .filter(|_| self.protocol.in_flight() > 0)
+            .map(|duration| Instant::now() + duration)
+    }
+
+    async fn transmit(&mut self) -> anyhow::Result<()> {
+        if self.protocol.is_broken() {
+            return Err(anyhow::Error::new(NotSent).context("Connection (broken)"));
+        }
+
+        let mut sent = false;
+
+        while let Some(bytes) = self.protocol.poll_transmit() {
+            let mut bytes = &bytes[..];
+
+            while !bytes.is_empty() {
+                let err = match self.stream.write(bytes).await {
+                    Ok(0) => io::Error::from(io::ErrorKind::WriteZero),
+                    Ok(len) => {
+                        bytes = &bytes[len..];
+                        sent = true;
+                        continue;
+                    }
+                    Err(err) => err,
+                };
+
+                // the requests queued behind would get the replies meant for these
+                self.protocol.abort();
+
+                let err = match sent {
+                    true => anyhow::Error::new(err),
+                    false => anyhow::Error::new(err).context(NotSent),
+                };
+                return Err(err.context("Connection (stream::write)"));
+            }
+        }
+
+        if let Err(err) = self.stream.flush().await {
+            self.protocol.abort();
+            return Err(err).context("Connection (stream::flush)");
+        }
+
+        Ok(())
+    }
+
+    // Reads what the server sent so far into the protocol.
+    async fn receive(&mut self, deadline: Option<Instant>) -> anyhow::Result<()> {
+        if self.protocol.is_broken() {
+            bail!("Connection (broken)");
+        }
+
+        let mut buf = [0; 16 * 1024];
+        let read = self.stream.read(&mut buf);
+        let len = match deadline {
+            Some(deadline) => match timeout_at(deadline, read).await {
+                Ok(len) => len,
+                Err(_) => {
+                    self.protocol.abort();
+                    bail!("Connection (timed out waiting for the server)");
+                }
+            },
+            None => read.await,
+        };
+        let len = match len {
+            Ok(0) => {
+                self.protocol.abort();
+                bail!("Connection (closed by server)");
+            }
+            Ok(len) => len,
+            Err(err) => {
+                self.protocol.abort();
+                return Err(err).context("Connection (stream::read)");
+            }
+        };
+
+        self.protocol.receive(&buf[..len]);
+        Ok(())
+    }
+}
+
+impl fmt::Display for NotSent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Connection (nothing was sent)")
+    }
+}
+
+impl Error for NotSent {}
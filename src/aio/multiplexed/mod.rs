@@ -0,0 +1,303 @@
+//! One connection shared by many tasks.
+
+#[cfg(test)]
+pub(crate) mod tests;
+
+use std::collections::VecDeque;
+
+use anyhow::{anyhow, bail, Context};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf},
+    net::ToSocketAddrs,
+    sync::{mpsc, oneshot},
+};
+
+use super::Connection;
+use crate::{
+    cmd::Cmd,
+    protocol::{ClientProtocol, Event, Hello, RequestId},
+    value::Value,
+};
+
+/// Parameters of [`MultiplexedConnection::connect_with`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MultiplexedOptions {
+    /// Sent right after connecting.
+    pub hello: Option<Hello>,
+    /// Whether commands issued in the same tick go out in one write.
+    pub auto_pipeline: bool,
+}
+
+/// Push messages received by a [`MultiplexedConnection`], in order.
+pub type Pushes = mpsc::UnboundedReceiver<Value>;
+
+/// A connection which can be cloned across tasks, every clone sending over the same socket.
+///
+/// A background task writes the commands in the order they are issued and hands
+/// the replies back in the same order. Push messages go to the [`Pushes`] channel
+/// returned alongside the connection, and are dropped if it is.
+/// The task stops when the last clone is dropped or the connection is lost,
+/// failing every command still waiting.
+#[derive(Debug, Clone)]
+pub struct MultiplexedConnection {
+    requests: mpsc::UnboundedSender<Request>,
+}
+
+#[derive(Debug)]
+struct Request {
+    cmds: Vec<Cmd>,
+    reply: oneshot::Sender<anyhow::Result<Vec<Value>>>,
+}
+
+// A request written out, waiting for the replies to its commands.
+#[derive(Debug)]
+struct Waiting {
+    ids: VecDeque<RequestId>,
+    values: Vec<Value>,
+    reply: oneshot::Sender<anyhow::Result<Vec<Value>>>,
+    written: bool,
+}
+
+impl MultiplexedConnection {
+    pub async fn connect(addr: impl ToSocketAddrs) -> anyhow::Result<(Self, Pushes)> {
+        Self::connect_with(addr, &MultiplexedOptions::default()).await
+    }
+
+    pub async fn connect_with(
+        addr: impl ToSocketAddrs,
+        options: &MultiplexedOptions,
+    ) -> anyhow::Result<(Self, Pushes)> {
+        let mut connection = Connection::connect(addr).await?;
+
+        if let Some(hello) = options.hello.as_ref() {
+            connection.hello(hello).await?;
+        }
+
+        Ok(Self::new(connection, options.auto_pipeline))
+    }
+
+    /// Takes over a connection (handshake done, if any) and starts the background task.
+    pub fn new<S>(connection: Connection<S>, auto_pipeline: bool) -> (Self, Pushes)
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let (requests, receiver) = mpsc::unbounded_channel();
+        let (pushes, push_receiver) = mpsc::unbounded_channel();
+
+        let Connection {
+            stream, protocol, ..
+        } = connection;
+
+        let (reader, writer) = tokio::io::split(stream);
+        let driver = Driver {
+            reader,
+            writer,
+            out: vec![],
+            protocol,
+            requests: receiver,
+            waiting: VecDeque::new(),
+            pushes,
+            auto_pipeline,
+        };
+        tokio::spawn(driver.run());
+
+        (Self { requests }, push_receiver)
+    }
+
+    /// Sends a command and waits for its reply. Server errors are returned as values.
+    pub async fn send(&self, cmd: &Cmd) -> anyhow::Result<Value> {
+        let mut replies = self.pipeline(std::slice::from_ref(cmd)).await?;
+        Ok(replies.remove(0))
+    }
+
+    /// Sends the commands back to back, no other clone's commands in between,
+    /// and returns their replies in order.
+    pub async fn pipeline(&self, cmds: &[Cmd]) -> anyhow::Result<Vec<Value>> {
+        if !cmds.iter().all(ClientProtocol::expects_reply) {
+            bail!("MultiplexedConnection (commands without replies have to be sent with MultiplexedConnection::write)");
+        }
+
+        self.request(cmds.to_vec()).await
+    }
+
+    /// Sends a command confirmed with push messages, e.g. `SUBSCRIBE`,
+    /// returning once it is written.
+    pub async fn write(&self, cmd: &Cmd) -> anyhow::Result<()> {
+        if ClientProtocol::expects_reply(cmd) {
+            bail!("MultiplexedConnection (commands with replies have to be sent with MultiplexedConnection::send)");
+        }
+
+        self.request(vec![cmd.clone()]).await.map(|_| ())
+    }
+
+    /// Whether the background task is gone, along with the connection.
+    pub fn is_closed(&self) -> bool {
+        self.requests.is_closed()
+    }
+
+    async fn request(&self, cmds: Vec<Cmd>) -> anyhow::Result<Vec<Value>> {
+        let (reply, receiver) = oneshot::channel();
+
+        self.requests
+            .send(Request { cmds, reply })
+            .map_err(|_| anyhow!("MultiplexedConnection (connection closed)"))?;
+
+        receiver
+            .await
+            .map_err(|_| anyhow!("MultiplexedConnection (connection closed)"))?
+    }
+}
+
+// The background task: owns the stream and the protocol state.
+struct Driver<S> {
+    reader: ReadHalf<S>,
+    writer: WriteHalf<S>,
+    // bytes still to be written, see `drive`
+    out: Vec<u8>,
+    protocol: ClientProtocol,
+    requests: mpsc::UnboundedReceiver<Request>,
+    waiting: VecDeque<Waiting>,
+    pushes: mpsc::UnboundedSender<Value>,
+    auto_pipeline: bool,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Driver<S> {
+    async fn run(mut self) {
+        if let Err(err) = self.drive().await {
+            for waiting in self.waiting.drain(..) {
+                let err = anyhow!("MultiplexedConnection (connection lost: {err:#})");
+                let _ = waiting.reply.send(Err(err));
+            }
+        }
+    }
+
+    // Returns once every clone is gone, or with the error which broke the connection.
+    //
+    // Reading goes on while writing: a server whose replies are not read stops reading
+    // commands once its output buffer is full, and a large pipeline would never get through.
+    async fn drive(&mut self) -> anyhow::Result<()> {
+        let mut buf = vec![0; 16 * 1024];
+
+        // push messages received before, e.g. along with the handshake
+        self.dispatch()?;
+
+        loop {
+            tokio::select! {
+                request = self.requests.recv() => {
+                    let Some(request) = request else {
+                        return Ok(());
+                    };
+
+                    self.queue(request);
+
+                    if self.auto_pipeline {
+                        // let the other tasks of this tick issue their commands
+                        tokio::task::yield_now().await;
+
+                        while let Ok(request) = self.requests.try_recv() {
+                            self.queue(request);
+                        }
+                    }
+
+                    while let Some(bytes) = self.protocol.poll_transmit() {
+                        self.out.extend_from_slice(&bytes);
+                    }
+                }
+                len = self.writer.write(&self.out), if !self.out.is_empty() => {
+                    match len.context("MultiplexedConnection (stream::write)")? {
+                        0 => bail!("MultiplexedConnection (stream::write wrote nothing)"),
+                        len => self.out.drain(..len),
+                    };
+
+                    if self.out.is_empty() {
+                        self.flush().await?;
+                    }
+                }
+                len = self.reader.read(&mut buf) => {
+                    let len = len.context("MultiplexedConnection (stream::read)")?;
+                    if len == 0 {
+                        bail!("MultiplexedConnection (closed by server)");
+                    }
+
+                    self.protocol.receive(&buf[..len]);
+                    self.dispatch()?;
+                }
+            }
+        }
+    }
+
+    fn queue(&mut self, request: Request) {
+        let ids = request
+            .cmds
+            .iter()
+            .map(|cmd| (ClientProtocol::expects_reply(cmd), self.protocol.send(cmd)))
+            .filter(|(expects_reply, _)| *expects_reply)
+            .map(|(_, id)| id)
+            .collect::<VecDeque<_>>();
+
+        let waiting = Waiting {
+            ids,
+            values: vec![],
+            reply: request.reply,
+            written: false,
+        };
+
+        // requests without replies complete once written, see `flush`
+        self.waiting.push_back(waiting);
+    }
+
+    // Everything queued so far is out once flushed.
+    async fn flush(&mut self) -> anyhow::Result<()> {
+        self.writer
+            .flush()
+            .await
+            .context("MultiplexedConnection (stream::flush)")?;
+
+        for waiting in self.waiting.iter_mut() {
+            waiting.written = true;
+        }
+
+        self.complete();
+        Ok(())
+    }
+
+    fn dispatch(&mut self) -> anyhow::Result<()> {
+        while let Some(event) = self.protocol.poll_event() {
+            match event {
+                Event::Reply(id, value) => {
+                    let waiting = self
+                        .waiting
+                        .iter_mut()
+                        .find(|waiting| !waiting.ids.is_empty())
+                        .filter(|waiting| waiting.ids.front() == Some(&id))
+                        .context("MultiplexedConnection (reply out of order)")?;
+
+                    waiting.ids.pop_front();
+                    waiting.values.push(value);
+                }
+                Event::Push(value) => {
+                    let _ = self.pushes.send(value);
+                }
+                Event::Error(err) => return Err(err),
+                Event::ProtocolSwitched(_) => {}
+            }
+        }
+
+        self.complete();
+        Ok(())
+    }
+
+    // Hands the replies over to requests which got all of theirs, in order.
+    fn complete(&mut self) {
+        while self
+            .waiting
+            .front()
+            .is_some_and(|waiting| waiting.written && waiting.ids.is_empty())
+        {
+            let waiting = self.waiting.pop_front().expect("front was just checked");
+
+            // the caller may have given up waiting
+            let _ = waiting.reply.send(Ok(waiting.values));
+        }
+    }
+}
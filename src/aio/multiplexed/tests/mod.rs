@@ -0,0 +1,159 @@
+use std::time::Duration;
+
+use bytes::Bytes;
+use tokio::net::TcpListener;
+
+use super::*;
+use crate::{aio::tests::serve, resp, value::BlobString};
+
+async fn server() -> String {
+    serve(|| {
+        |args: &[Bytes]| match &args[0][..] {
+            b"HELLO" => vec![resp!({"proto" => 3})],
+            b"ECHO" => vec![resp!(BlobString::from(args[1].clone()))],
+            b"GET" => vec![
+                resp!(>["invalidate", [(BlobString::from(args[1].clone()))]]),
+                resp!(nil),
+            ],
+            b"SUBSCRIBE" => vec![resp!(>["subscribe", (BlobString::from(args[1].clone())), 1])],
+            _ => vec![resp!(-"ERR unknown command")],
+        }
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_multiplexed() {
+    let options = MultiplexedOptions {
+        hello: Some(Hello::default()),
+        ..MultiplexedOptions::default()
+    };
+    let (connection, mut pushes) = MultiplexedConnection::connect_with(server().await, &options)
+        .await
+        .unwrap();
+
+    let mut tasks = vec![];
+    for i in 0..20 {
+        let connection = connection.clone();
+        tasks.push(tokio::spawn(async move {
+            let msg = format!("msg {i}");
+            let reply = connection
+                .send(&Cmd::new("ECHO").arg(msg.clone()))
+                .await
+                .unwrap();
+            assert_eq!(reply, resp!(BlobString::from(msg.into_bytes())));
+        }));
+    }
+    for task in tasks {
+        task.await.unwrap();
+    }
+
+    // pushes go to their own channel, replies to the caller
+    let replies = connection
+        .pipeline(&[Cmd::new("GET").arg("a"), Cmd::new("ECHO").arg("b")])
+        .await
+        .unwrap();
+    assert_eq!(replies, [resp!(nil), resp!("b")]);
+    assert_eq!(pushes.recv().await.unwrap(), resp!(>["invalidate", ["a"]]));
+
+    connection
+        .write(&Cmd::new("SUBSCRIBE").arg("ch"))
+        .await
+        .unwrap();
+    assert_eq!(pushes.recv().await.unwrap(), resp!(>["subscribe", "ch", 1]));
+
+    // server errors are values
+    let reply = connection.send(&Cmd::new("NOPE")).await.unwrap();
+    assert!(reply.into_result().is_err());
+
+    assert!(connection
+        .send(&Cmd::new("SUBSCRIBE").arg("ch"))
+        .await
+        .is_err());
+    assert!(connection.write(&Cmd::new("ECHO").arg("a")).await.is_err());
+}
+
+#[tokio::test]
+async fn test_auto_pipeline() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // counts the commands of the first write
+    let server = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut buf = vec![0; 4096];
+        let len = stream.read(&mut buf).await.unwrap();
+
+        let mut frames = 0;
+        let mut pos = 0;
+        while let Some((_, used)) = Value::decode(&buf[pos..len]).unwrap() {
+            frames += 1;
+            pos += used;
+        }
+
+        stream.write_all(&b"+OK\r\n".repeat(frames)).await.unwrap();
+        frames
+    });
+
+    let options = MultiplexedOptions {
+        auto_pipeline: true,
+        ..MultiplexedOptions::default()
+    };
+    let (connection, _) = MultiplexedConnection::connect_with(addr, &options)
+        .await
+        .unwrap();
+
+    let ping = Cmd::new("PING");
+    let replies = tokio::join!(
+        connection.send(&ping),
+        connection.send(&ping),
+        connection.send(&ping),
+    );
+    for reply in [replies.0, replies.1, replies.2] {
+        assert_eq!(reply.unwrap(), resp!(+"OK"));
+    }
+
+    assert_eq!(server.await.unwrap(), 3);
+}
+
+#[tokio::test]
+async fn test_closed() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // hangs up after reading the first command
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut buf = vec![0; 4096];
+        let _ = stream.read(&mut buf).await;
+    });
+
+    let (connection, mut pushes) = MultiplexedConnection::connect(addr).await.unwrap();
+    let other = connection.clone();
+
+    assert!(connection.send(&Cmd::new("PING")).await.is_err());
+    assert!(pushes.recv().await.is_none());
+    assert!(other.is_closed());
+    assert!(other.send(&Cmd::new("PING")).await.is_err());
+}
+
+#[tokio::test]
+async fn test_large_pipeline() {
+    let (connection, _) = MultiplexedConnection::connect(server().await)
+        .await
+        .unwrap();
+
+    // far more than the socket buffers hold both ways, the server only reads
+    // on once its replies are read
+    let msg = "x".repeat(16 * 1024);
+    let cmds = vec![Cmd::new("ECHO").arg(msg.clone()); 1000];
+    let replies = tokio::time::timeout(Duration::from_secs(20), connection.pipeline(&cmds))
+        .await
+        .expect("pipeline stalled")
+        .unwrap();
+
+    assert_eq!(replies.len(), 1000);
+    assert!(replies
+        .iter()
+        .all(|reply| *reply == resp!(BlobString::from(msg.clone().into_bytes()))));
+}
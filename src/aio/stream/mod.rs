@@ -0,0 +1,74 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+#[cfg(unix)]
+use tokio::net::UnixStream;
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    net::TcpStream,
+};
+
+/// The streams [`Connection::open`](super::Connection::open) connects with.
+#[derive(Debug)]
+pub enum Stream {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+    #[cfg(feature = "tls")]
+    Tls(Box<tokio_rustls::client::TlsStream<TcpStream>>),
+}
+
+impl AsyncRead for Stream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(inner) => Pin::new(inner).poll_read(cx, buf),
+            #[cfg(unix)]
+            Self::Unix(inner) => Pin::new(inner).poll_read(cx, buf),
+            #[cfg(feature = "tls")]
+            Self::Tls(inner) => Pin::new(inner).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for Stream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Self::Tcp(inner) => Pin::new(inner).poll_write(cx, buf),
+            #[cfg(unix)]
+            Self::Unix(inner) => Pin::new(inner).poll_write(cx, buf),
+            #[cfg(feature = "tls")]
+            Self::Tls(inner) => Pin::new(inner).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(inner) => Pin::new(inner).poll_flush(cx),
+            #[cfg(unix)]
+            Self::Unix(inner) => Pin::new(inner).poll_flush(cx),
+            #[cfg(feature = "tls")]
+            Self::Tls(inner) => Pin::new(inner).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(inner) => Pin::new(inner).poll_shutdown(cx),
+            #[cfg(unix)]
+            Self::Unix(inner) => Pin::new(inner).poll_shutdown(cx),
+            #[cfg(feature = "tls")]
+            Self::Tls(inner) => Pin::new(inner).poll_shutdown(cx),
+        }
+    }
+}
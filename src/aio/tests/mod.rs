@@ -0,0 +1,275 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+
+use bytes::Bytes;
+use tokio::net::TcpListener;
+#[cfg(unix)]
+use tokio::net::UnixListener;
+
+use super::*;
+use crate::{config::ConnectionInfo, protocol::Version, resp, value::BlobString};
+
+/// Starts a server answering every command with the frames returned by a handler,
+/// each connection getting a fresh handler from `handler`. Returns its address.
+pub async fn serve<F, H>(handler: F) -> String
+where
+    F: Fn() -> H + Send + 'static,
+    H: FnMut(&[Bytes]) -> Vec<Value> + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(session(stream, handler()));
+        }
+    });
+
+    addr
+}
+
+/// The name of every command a [`serve_logged`] server received, uppercased,
+/// with the number of the connection it came on.
+pub type Log = Arc<Mutex<Vec<(usize, String)>>>;
+
+/// Like [`serve`], logging every command. Connections are numbered from 1,
+/// the handler of each one is made by `handler` from its number.
+pub async fn serve_logged<F, H>(handler: F) -> (String, Log)
+where
+    F: Fn(usize) -> H + Send + 'static,
+    H: FnMut(&[Bytes]) -> Vec<Value> + Send + 'static,
+{
+    let log = Log::default();
+    let count = AtomicUsize::new(0);
+
+    let state = log.clone();
+    let addr = serve(move || {
+        let id = count.fetch_add(1, Ordering::SeqCst) + 1;
+        let log = state.clone();
+        let mut handler = handler(id);
+
+        move |args: &[Bytes]| {
+            let name = String::from_utf8_lossy(&args[0]).to_uppercase();
+            log.lock().unwrap().push((id, name));
+
+            handler(args)
+        }
+    })
+    .await;
+
+    (addr, log)
+}
+
+/// Empties the log, returning the commands received on any connection.
+pub fn take_log(log: &Log) -> Vec<String> {
+    let log = std::mem::take(&mut *log.lock().unwrap());
+    log.into_iter().map(|(_, name)| name).collect()
+}
+
+/// Empties the log, returning the commands received on connection `id`.
+pub fn take_log_of(log: &Log, id: usize) -> Vec<String> {
+    let log = std::mem::take(&mut *log.lock().unwrap());
+    log.into_iter()
+        .filter(|(from, _)| *from == id)
+        .map(|(_, name)| name)
+        .collect()
+}
+
+async fn session(
+    mut stream: impl AsyncRead + AsyncWrite + Unpin,
+    mut handler: impl FnMut(&[Bytes]) -> Vec<Value>,
+) {
+    let mut buf = vec![];
+    let mut chunk = [0; 4096];
+
+    loop {
+        while let Some((value, len)) = Value::decode(&buf).unwrap() {
+            buf.drain(..len);
+
+            for reply in handler(&args(value)) {
+                let bytes = Bytes::try_from(reply).unwrap();
+                if stream.write_all(&bytes).await.is_err() {
+                    return;
+                }
+            }
+        }
+
+        match stream.read(&mut chunk).await {
+            Ok(0) | Err(_) => return,
+            Ok(len) => buf.extend_from_slice(&chunk[..len]),
+        }
+    }
+}
+
+fn args(value: Value) -> Vec<Bytes> {
+    match value {
+        Value::Array(args) => args
+            .into_iter()
+            .map(|arg| match arg {
+                Value::BlobString(arg) => arg.val().clone(),
+                arg => panic!("unexpected argument: {arg:?}"),
+            })
+            .collect(),
+        value => panic!("unexpected command: {value:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_connection() {
+    let addr = serve(|| {
+        |args: &[Bytes]| match &args[0][..] {
+            b"HELLO" => vec![resp!({"proto" => 3})],
+            b"GET" => vec![
+                resp!(>["invalidate", [(BlobString::from(args[1].clone()))]]),
+                resp!("val"),
+            ],
+            b"INCR" => vec![resp!(1)],
+            _ => vec![resp!(-"ERR unknown command")],
+        }
+    })
+    .await;
+
+    let mut connection = Connection::connect(addr).await.unwrap();
+    connection.hello(&Hello::default()).await.unwrap();
+    assert_eq!(connection.protocol().version(), Version::Resp3);
+
+    assert_eq!(
+        connection.send(&Cmd::new("GET").arg("key")).await.unwrap(),
+        resp!("val")
+    );
+    assert_eq!(
+        connection
+            .pipeline(&[Cmd::new("INCR").arg("n"), Cmd::new("PING")])
+            .await
+            .unwrap(),
+        vec![resp!(1), resp!(-"ERR unknown command")]
+    );
+
+    assert_eq!(
+        connection.next_push().await.unwrap(),
+        resp!(>["invalidate", ["key"]])
+    );
+
+    assert!(connection.write(&Cmd::new("GET").arg("key")).await.is_err());
+}
+
+#[tokio::test]
+async fn test_connection_closed() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        drop(stream);
+    });
+
+    let mut connection = Connection::connect(addr).await.unwrap();
+    assert!(connection.send(&Cmd::new("PING")).await.is_err());
+}
+
+#[tokio::test]
+async fn test_write_failure() {
+    let (client, server) = tokio::io::duplex(64);
+    drop(server);
+
+    let mut connection = Connection::new(client);
+    let err = connection.send(&Cmd::new("PING")).await.unwrap_err();
+    assert!(err.downcast_ref::<NotSent>().is_some());
+
+    // nothing is left waiting for a reply which would go to the next command
+    assert!(connection.protocol().is_broken());
+    assert_eq!(connection.protocol().in_flight(), 0);
+
+    let err = connection.send(&Cmd::new("PING")).await.unwrap_err();
+    assert_eq!(err.to_string(), "Connection (broken)");
+}
+
+// Answers HELLO and SELECT, echoes the database selected on `DB`, never replies to `WAIT`.
+fn handler() -> impl FnMut(&[Bytes]) -> Vec<Value> {
+    let mut db = Bytes::from("0");
+
+    move |args: &[Bytes]| match &args[0][..] {
+        b"HELLO" => vec![resp!({"proto" => 3})],
+        b"SELECT" => {
+            db = args[1].clone();
+            vec![resp!(+"OK")]
+        }
+        b"DB" => vec![resp!(BlobString::from(db.clone()))],
+        b"WAIT" => vec![],
+        _ => vec![resp!(-"ERR unknown command")],
+    }
+}
+
+#[tokio::test]
+async fn test_open() {
+    let addr = serve(handler).await;
+    let info = ConnectionInfo::from_url(&format!("redis://{addr}/4?protocol=3")).unwrap();
+
+    let mut connection = Connection::open(&info).await.unwrap();
+    assert!(matches!(connection.get_ref(), Stream::Tcp(_)));
+    assert_eq!(connection.protocol().version(), Version::Resp3);
+    assert_eq!(connection.protocol().db(), 4);
+    assert_eq!(connection.send(&Cmd::new("DB")).await.unwrap(), resp!("4"));
+
+    connection.set_response_timeout(Some(std::time::Duration::from_millis(50)));
+    let err = connection.send(&Cmd::new("WAIT")).await.unwrap_err();
+    assert_eq!(
+        format!("{err}"),
+        "Connection (timed out waiting for the server)"
+    );
+
+    // the late reply cannot be taken for the next one's
+    assert!(connection.protocol().is_broken());
+    let err = connection.send(&Cmd::new("DB")).await.unwrap_err();
+    assert_eq!(format!("{err}"), "Connection (broken)");
+
+    let info = info.with_tls(Default::default());
+    assert!(Connection::open(&info).await.is_err());
+}
+
+#[tokio::test]
+async fn test_slow_reply() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // a byte every 20ms, each read comes well within the timeout but the reply does not
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut buf = [0; 64];
+        let _ = stream.read(&mut buf).await;
+
+        for byte in b"+PONG\r\n" {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            if stream.write_all(&[*byte]).await.is_err() {
+                return;
+            }
+        }
+    });
+
+    let mut connection = Connection::connect(addr).await.unwrap();
+    connection.set_response_timeout(Some(std::time::Duration::from_millis(50)));
+    assert!(connection.send(&Cmd::new("PING")).await.is_err());
+    assert!(connection.protocol().is_broken());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_open_unix() {
+    let path = std::env::temp_dir().join(format!("resp3-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path).unwrap();
+
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        session(stream, handler()).await;
+    });
+
+    let info = ConnectionInfo::unix(&path).with_db(2);
+    let mut connection = Connection::open(&info).await.unwrap();
+    assert!(matches!(connection.get_ref(), Stream::Unix(_)));
+    assert_eq!(connection.send(&Cmd::new("DB")).await.unwrap(), resp!("2"));
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,135 @@
+#[cfg(test)]
+mod tests;
+
+use std::{
+    fs,
+    io::{self, Read},
+};
+
+use anyhow::{bail, Context};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Raw,
+    Hex,
+    Escaped,
+}
+
+impl Format {
+    pub fn from_arg(arg: &str) -> anyhow::Result<Self> {
+        match arg {
+            "raw" => Ok(Self::Raw),
+            "hex" => Ok(Self::Hex),
+            "escaped" => Ok(Self::Escaped),
+            arg => bail!("unknown format {arg:?} (expected raw, hex or escaped)"),
+        }
+    }
+
+    pub fn decode(self, input: &[u8]) -> anyhow::Result<Vec<u8>> {
+        match self {
+            Self::Raw => Ok(input.to_vec()),
+            Self::Hex => unhex(input),
+            Self::Escaped => unescape(input),
+        }
+    }
+
+    pub fn encode(self, input: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Raw => input.to_vec(),
+            Self::Hex => input
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<String>()
+                .into_bytes(),
+            Self::Escaped => escape(input).into_bytes(),
+        }
+    }
+}
+
+/// Reads the whole of `path`, or stdin if it is `None` or `-`.
+pub fn read(path: Option<&str>) -> anyhow::Result<Vec<u8>> {
+    let mut buf = vec![];
+
+    match path {
+        None | Some("-") => {
+            io::stdin()
+                .read_to_end(&mut buf)
+                .context("failed to read stdin")?;
+        }
+        Some(path) => {
+            buf = fs::read(path).with_context(|| format!("failed to read {path}"))?;
+        }
+    }
+
+    Ok(buf)
+}
+
+fn unhex(input: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let digits = input
+        .iter()
+        .filter(|b| !b.is_ascii_whitespace())
+        .map(|b| match (*b as char).to_digit(16) {
+            Some(digit) => Ok(digit as u8),
+            None => bail!("invalid hex digit {:?}", *b as char),
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    if digits.len() % 2 != 0 {
+        bail!("odd number of hex digits");
+    }
+
+    Ok(digits
+        .chunks(2)
+        .map(|pair| pair[0] << 4 | pair[1])
+        .collect())
+}
+
+// Literal line breaks are ignored: the frame's own delimiters must be written as `\r\n`.
+fn unescape(input: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut buf = vec![];
+    let mut bytes = input.iter().copied().filter(|b| *b != b'\r' && *b != b'\n');
+
+    while let Some(byte) = bytes.next() {
+        if byte != b'\\' {
+            buf.push(byte);
+            continue;
+        }
+
+        match bytes.next() {
+            Some(b'r') => buf.push(b'\r'),
+            Some(b'n') => buf.push(b'\n'),
+            Some(b't') => buf.push(b'\t'),
+            Some(b'0') => buf.push(0),
+            Some(b'\\') => buf.push(b'\\'),
+            Some(b'"') => buf.push(b'"'),
+            Some(b'x') => {
+                let hex = [bytes.next(), bytes.next()];
+                match hex {
+                    [Some(hi), Some(lo)] => buf.extend(unhex(&[hi, lo])?),
+                    _ => bail!("truncated \\x escape"),
+                }
+            }
+            Some(other) => bail!("unknown escape \\{}", other as char),
+            None => bail!("trailing backslash"),
+        }
+    }
+
+    Ok(buf)
+}
+
+fn escape(input: &[u8]) -> String {
+    let mut buf = String::new();
+
+    for &byte in input {
+        match byte {
+            b'\r' => buf.push_str("\\r"),
+            b'\n' => buf.push_str("\\n"),
+            b'\t' => buf.push_str("\\t"),
+            b'\\' => buf.push_str("\\\\"),
+            0x20..=0x7e => buf.push(byte as char),
+            _ => buf.push_str(&format!("\\x{byte:02x}")),
+        }
+    }
+
+    buf
+}
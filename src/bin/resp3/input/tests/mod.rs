@@ -0,0 +1,38 @@
+use super::*;
+
+#[test]
+fn test_hex() {
+    assert_eq!(
+        Format::Hex.decode(b"2b 4f 4B\n0d0a").unwrap(),
+        b"+OK\r\n".to_vec()
+    );
+    assert_eq!(Format::Hex.encode(b"+OK\r\n"), b"2b4f4b0d0a".to_vec());
+
+    assert!(Format::Hex.decode(b"2b4").is_err());
+    assert!(Format::Hex.decode(b"zz").is_err());
+}
+
+#[test]
+fn test_escaped() {
+    assert_eq!(
+        Format::Escaped
+            .decode(b"*1\\r\\n\n$4\\r\\nP\\x49NG\\r\\n")
+            .unwrap(),
+        b"*1\r\n$4\r\nPING\r\n".to_vec()
+    );
+    assert_eq!(
+        Format::Escaped.encode(b"$2\r\n\xff\\\r\n"),
+        b"$2\\r\\n\\xff\\\\\\r\\n".to_vec()
+    );
+
+    assert!(Format::Escaped.decode(b"\\q").is_err());
+    assert!(Format::Escaped.decode(b"\\x4").is_err());
+}
+
+#[test]
+fn test_escaped_roundtrip() {
+    let raw = (0..=255).collect::<Vec<u8>>();
+    let escaped = Format::Escaped.encode(&raw);
+
+    assert_eq!(Format::Escaped.decode(&escaped).unwrap(), raw);
+}
@@ -0,0 +1,190 @@
+mod input;
+
+use std::{
+    env,
+    io::{self, Write},
+    process::ExitCode,
+};
+
+use anyhow::{bail, Context};
+use bytes::Bytes;
+use resp3::{cmd::Cmd, value::Value};
+use serde_json::Value as Json;
+
+use input::Format;
+
+static USAGE: &str = "\
+usage:
+    resp3 decode [--input raw|hex|escaped] [--output human|raw|json|tagged|debug] [FILE]
+    resp3 encode [--from command|json|tagged] [--output raw|hex|escaped] [--file FILE] [ARG...]
+
+decode reads RESP frames from FILE (or stdin) and prints every frame.
+encode reads commands (one per line, redis-cli syntax) or JSON values from FILE (or stdin)
+and writes them as RESP frames; trailing ARGs are encoded as a single command instead.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Output {
+    Human,
+    Raw,
+    Json,
+    Tagged,
+    Debug,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    Command,
+    Json,
+    Tagged,
+}
+
+fn main() -> ExitCode {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+
+    let result = match args.first().map(String::as_str) {
+        Some("decode") => decode(&args[1..]),
+        Some("encode") => encode(&args[1..]),
+        Some("-h" | "--help" | "help") => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("resp3: {err:#}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn decode(args: &[String]) -> anyhow::Result<()> {
+    let mut format = Format::Raw;
+    let mut output = Output::Human;
+    let mut path = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--input" => format = Format::from_arg(value(&mut args, arg)?)?,
+            "--output" => {
+                output = match value(&mut args, arg)? {
+                    "human" => Output::Human,
+                    "raw" => Output::Raw,
+                    "json" => Output::Json,
+                    "tagged" => Output::Tagged,
+                    "debug" => Output::Debug,
+                    other => bail!("unknown output {other:?}"),
+                }
+            }
+            arg if arg.starts_with("--") => bail!("unknown option {arg:?}\n\n{USAGE}"),
+            arg if path.is_none() => path = Some(arg),
+            arg => bail!("unexpected argument {arg:?}\n\n{USAGE}"),
+        }
+    }
+
+    let input = format.decode(&input::read(path)?)?;
+    let mut stdout = io::stdout().lock();
+    let mut offset = 0;
+
+    while offset < input.len() {
+        let (value, len) = match Value::decode(&input[offset..]) {
+            Ok(Some(frame)) => frame,
+            Ok(None) => bail!("incomplete frame at byte offset {offset}"),
+            Err(err) => bail!("malformed frame at byte offset {offset}: {err:#}"),
+        };
+
+        match output {
+            Output::Human => writeln!(stdout, "{value}"),
+            Output::Raw => writeln!(stdout, "{}", value.raw()),
+            Output::Json => writeln!(stdout, "{}", value.to_json()),
+            Output::Tagged => writeln!(stdout, "{}", value.to_tagged_json()),
+            Output::Debug => writeln!(stdout, "{value:#?}"),
+        }
+        .context("failed to write stdout")?;
+
+        offset += len;
+    }
+
+    Ok(())
+}
+
+fn encode(args: &[String]) -> anyhow::Result<()> {
+    let mut source = Source::Command;
+    let mut format = Format::Raw;
+    let mut path = None;
+    let mut words = vec![];
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--from" => {
+                source = match value(&mut args, arg)? {
+                    "command" => Source::Command,
+                    "json" => Source::Json,
+                    "tagged" => Source::Tagged,
+                    other => bail!("unknown source {other:?}"),
+                }
+            }
+            "--output" => format = Format::from_arg(value(&mut args, arg)?)?,
+            "--file" => path = Some(value(&mut args, arg)?),
+            "--" => words.extend(args.by_ref().cloned()),
+            arg if arg.starts_with("--") && words.is_empty() => {
+                bail!("unknown option {arg:?}\n\n{USAGE}")
+            }
+            arg => words.push(arg.to_string()),
+        }
+    }
+
+    let frames = match words.is_empty() {
+        false => vec![Bytes::try_from(Cmd::from_iter(words))?],
+        true => {
+            let input = input::read(path)?;
+
+            match source {
+                Source::Command => String::from_utf8(input)
+                    .context("commands must be UTF-8")?
+                    .lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(|line| Cmd::from_line(line).and_then(Bytes::try_from))
+                    .collect::<anyhow::Result<Vec<_>>>()?,
+                Source::Json | Source::Tagged => serde_json::Deserializer::from_slice(&input)
+                    .into_iter::<Json>()
+                    .map(|json| {
+                        let json = json.context("invalid JSON")?;
+                        let value = match source {
+                            Source::Tagged => Value::from_tagged_json(&json)?,
+                            _ => Value::from_json(&json)?,
+                        };
+
+                        Bytes::try_from(value)
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?,
+            }
+        }
+    };
+
+    let mut stdout = io::stdout().lock();
+    for frame in frames {
+        stdout
+            .write_all(&format.encode(&frame))
+            .and_then(|_| match format {
+                Format::Raw => Ok(()),
+                _ => stdout.write_all(b"\n"),
+            })
+            .context("failed to write stdout")?;
+    }
+
+    stdout.flush().context("failed to flush stdout")
+}
+
+fn value<'a>(args: &mut impl Iterator<Item = &'a String>, name: &str) -> anyhow::Result<&'a str> {
+    args.next()
+        .map(String::as_str)
+        .with_context(|| format!("{name} requires a value"))
+}
@@ -0,0 +1,308 @@
+//! Blocking `std::io` counterparts for scripts and command line tools.
+
+#[cfg(test)]
+pub(crate) mod tests;
+
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead, BufWriter, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use anyhow::{bail, Context};
+use bytes::Bytes;
+
+use crate::{
+    cmd::Cmd,
+    protocol::{ClientProtocol, Hello, RequestId},
+    value::{DecodeOptions, FrameScanner, Value},
+};
+
+/// Reads values one frame at a time.
+///
+/// Only the bytes of the frame being returned are consumed from the underlying reader,
+/// so it is positioned right after that frame.
+#[derive(Debug)]
+pub struct Resp3Reader<R> {
+    reader: R,
+    buf: Vec<u8>,
+    scanner: FrameScanner,
+    options: DecodeOptions,
+}
+
+impl<R: BufRead> Resp3Reader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: vec![],
+            scanner: FrameScanner::new(),
+            options: DecodeOptions::default(),
+        }
+    }
+
+    pub fn with_options(mut self, options: DecodeOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Returns `None` on a clean EOF (between frames) and an error on EOF mid-frame.
+    pub fn read_value(&mut self) -> anyhow::Result<Option<Value>> {
+        loop {
+            let available = match self.reader.fill_buf() {
+                Ok(available) => available,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err).context("Resp3Reader (reader::fill_buf)"),
+            };
+
+            if available.is_empty() {
+                match self.buf.is_empty() {
+                    true => return Ok(None),
+                    false => bail!("Resp3Reader (unexpected EOF mid-frame)"),
+                }
+            }
+
+            let buffered = self.buf.len();
+            self.buf.extend_from_slice(available);
+
+            // the frame is only parsed once all of it is there
+            match self.scanner.scan(&self.buf) {
+                Ok(Some(len)) => {
+                    self.reader.consume(len - buffered);
+                    self.buf.truncate(len);
+
+                    let value = Value::decode_with(&self.buf, &self.options)
+                        .context("Resp3Reader (Value::decode_with)")
+                        .map(|value| value.map(|(value, _)| value));
+                    self.buf.clear();
+
+                    return value;
+                }
+                Ok(None) => {
+                    let len = self.buf.len() - buffered;
+                    self.reader.consume(len);
+                }
+                // the malformed bytes are dropped rather than scanned again on the next call
+                Err(err) => {
+                    let len = self.buf.len() - buffered;
+                    self.reader.consume(len);
+                    self.buf.clear();
+                    self.scanner = FrameScanner::new();
+
+                    return Err(err).context("Resp3Reader (FrameScanner::scan)");
+                }
+            }
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: BufRead> Iterator for Resp3Reader<R> {
+    type Item = anyhow::Result<Value>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_value().transpose()
+    }
+}
+
+/// Buffers encoded values until [`Resp3Writer::flush`] is called (or the buffer fills up).
+#[derive(Debug)]
+pub struct Resp3Writer<W: Write> {
+    writer: BufWriter<W>,
+}
+
+impl<W: Write> Resp3Writer<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: BufWriter::new(writer),
+        }
+    }
+
+    pub fn write_value(&mut self, value: &Value) -> anyhow::Result<()> {
+        let bytes = Bytes::try_from(value).context("Resp3Writer (Bytes::try_from)")?;
+        self.writer
+            .write_all(&bytes)
+            .context("Resp3Writer (writer::write_all)")
+    }
+
+    pub fn write_cmd(&mut self, cmd: &Cmd) -> anyhow::Result<()> {
+        let bytes = Bytes::try_from(cmd).context("Resp3Writer (Bytes::try_from)")?;
+        self.writer
+            .write_all(&bytes)
+            .context("Resp3Writer (writer::write_all)")
+    }
+
+    pub fn flush(&mut self) -> anyhow::Result<()> {
+        self.writer.flush().context("Resp3Writer (writer::flush)")
+    }
+
+    pub fn get_ref(&self) -> &W {
+        self.writer.get_ref()
+    }
+
+    pub fn into_inner(self) -> anyhow::Result<W> {
+        self.writer
+            .into_inner()
+            .map_err(|err| err.into_error())
+            .context("Resp3Writer (writer::into_inner)")
+    }
+}
+
+/// A blocking client connection driving [`ClientProtocol`] over a [`TcpStream`].
+#[derive(Debug)]
+pub struct Connection {
+    stream: TcpStream,
+    protocol: ClientProtocol,
+}
+
+impl Connection {
+    pub fn connect(addr: impl ToSocketAddrs) -> anyhow::Result<Self> {
+        let stream = TcpStream::connect(addr).context("Connection (TcpStream::connect)")?;
+        stream
+            .set_nodelay(true)
+            .context("Connection (TcpStream::set_nodelay)")?;
+
+        Ok(Self {
+            stream,
+            protocol: ClientProtocol::new(),
+        })
+    }
+
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> anyhow::Result<()> {
+        self.stream
+            .set_read_timeout(timeout)
+            .context("Connection (TcpStream::set_read_timeout)")
+    }
+
+    pub fn protocol(&self) -> &ClientProtocol {
+        &self.protocol
+    }
+
+    pub fn set_decode_options(&mut self, options: DecodeOptions) {
+        self.protocol.set_decode_options(options);
+    }
+
+    /// Performs the `HELLO` handshake, failing if the server refuses it.
+    pub fn hello(&mut self, hello: &Hello) -> anyhow::Result<()> {
+        self.protocol.hello(hello);
+        self.transmit()?;
+
+        loop {
+            if let Some(result) = self.protocol.poll_hello() {
+                return result;
+            }
+
+            self.receive()?;
+        }
+    }
+
+    /// Sends a command and waits for its reply. Server errors are returned as values.
+    pub fn send(&mut self, cmd: &Cmd) -> anyhow::Result<Value> {
+        let mut replies = self.pipeline(std::slice::from_ref(cmd))?;
+        Ok(replies.remove(0))
+    }
+
+    /// Sends all commands in one write and returns their replies in order.
+    pub fn pipeline(&mut self, cmds: &[Cmd]) -> anyhow::Result<Vec<Value>> {
+        if !cmds.iter().all(ClientProtocol::expects_reply) {
+            bail!("Connection (commands without replies have to be sent with Connection::write)");
+        }
+
+        let mut ids = cmds
+            .iter()
+            .map(|cmd| self.protocol.send(cmd))
+            .collect::<VecDeque<_>>();
+
+        self.transmit()?;
+
+        let mut replies = Vec::with_capacity(ids.len());
+        while let Some(expected) = ids.pop_front() {
+            replies.push(self.reply(expected)?);
+        }
+
+        Ok(replies)
+    }
+
+    /// Sends a command confirmed with push messages, e.g. `SUBSCRIBE`.
+    pub fn write(&mut self, cmd: &Cmd) -> anyhow::Result<RequestId> {
+        if ClientProtocol::expects_reply(cmd) {
+            bail!("Connection (commands with replies have to be sent with Connection::send)");
+        }
+
+        let id = self.protocol.send(cmd);
+        self.transmit()?;
+
+        Ok(id)
+    }
+
+    /// Blocks until the next push message arrives.
+    pub fn next_push(&mut self) -> anyhow::Result<Value> {
+        loop {
+            if let Some(result) = self.protocol.poll_push() {
+                return result;
+            }
+
+            self.receive()?;
+        }
+    }
+
+    fn reply(&mut self, expected: RequestId) -> anyhow::Result<Value> {
+        loop {
+            if let Some(result) = self.protocol.poll_reply(expected) {
+                return result;
+            }
+
+            self.receive()?;
+        }
+    }
+
+    fn transmit(&mut self) -> anyhow::Result<()> {
+        if self.protocol.is_broken() {
+            bail!("Connection (broken)");
+        }
+
+        while let Some(bytes) = self.protocol.poll_transmit() {
+            if let Err(err) = self.stream.write_all(&bytes) {
+                // part of it may have been sent, what comes back can't be matched anymore
+                self.protocol.abort();
+                return Err(err).context("Connection (TcpStream::write_all)");
+            }
+        }
+
+        Ok(())
+    }
+
+    // Reads what the server sent so far into the protocol. A failed read, timeouts
+    // included, leaves replies behind which would be taken for later requests' ones.
+    fn receive(&mut self) -> anyhow::Result<()> {
+        if self.protocol.is_broken() {
+            bail!("Connection (broken)");
+        }
+
+        let mut buf = [0; 16 * 1024];
+        let len = loop {
+            match self.stream.read(&mut buf) {
+                Ok(0) => {
+                    self.protocol.abort();
+                    bail!("Connection (closed by server)");
+                }
+                Ok(len) => break len,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => {
+                    self.protocol.abort();
+                    return Err(err).context("Connection (TcpStream::read)");
+                }
+            }
+        };
+
+        self.protocol.receive(&buf[..len]);
+        Ok(())
+    }
+}
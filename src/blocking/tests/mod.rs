@@ -0,0 +1,212 @@
+use std::{
+    io::{BufReader, Cursor},
+    net::TcpListener,
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use super::*;
+use crate::{
+    protocol::Version,
+    value::{BlobString, Number, SimpleString},
+};
+
+// Reads exactly the expected request and answers with the canned reply, in order.
+fn serve(script: Vec<(Vec<u8>, &'static [u8])>) -> (String, JoinHandle<()>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+
+    let handle = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+
+        for (request, reply) in script {
+            let mut buf = vec![0; request.len()];
+            stream.read_exact(&mut buf).unwrap();
+            assert_eq!(buf, request);
+
+            stream.write_all(reply).unwrap();
+        }
+    });
+
+    (addr, handle)
+}
+
+fn encode(cmd: Cmd) -> Vec<u8> {
+    Bytes::try_from(cmd).unwrap().to_vec()
+}
+
+#[test]
+fn test_reader() {
+    let input = b"+OK\r\n:1\r\n*2\r\n$1\r\na\r\n_\r\n";
+    let mut reader = Resp3Reader::new(BufReader::with_capacity(3, &input[..]));
+
+    assert_eq!(
+        reader.read_value().unwrap(),
+        Some(Value::from(SimpleString::new("OK").unwrap()))
+    );
+    assert_eq!(
+        reader.read_value().unwrap(),
+        Some(Value::from(Number::from(1)))
+    );
+    assert!(matches!(
+        reader.read_value().unwrap(),
+        Some(Value::Array(_))
+    ));
+    assert_eq!(reader.read_value().unwrap(), None);
+}
+
+#[test]
+fn test_reader_exact() {
+    let mut input = Cursor::new(b"+OK\r\ntrailing".to_vec());
+    let mut reader = Resp3Reader::new(&mut input);
+
+    assert!(reader.read_value().unwrap().is_some());
+    assert_eq!(input.position(), 5);
+}
+
+#[test]
+fn test_reader_eof() {
+    let mut reader = Resp3Reader::new(&b"$5\r\nab"[..]);
+    assert!(reader.read_value().is_err());
+
+    let mut reader = Resp3Reader::new(&b"?\r\n"[..]);
+    assert!(reader.read_value().is_err());
+}
+
+#[test]
+fn test_reader_malformed() {
+    let input = b"?\r\n+OK\r\n";
+    let mut reader = Resp3Reader::new(BufReader::with_capacity(3, &input[..]));
+
+    // the bad bytes are gone, reading goes on after them
+    assert!(reader.read_value().is_err());
+    assert_eq!(
+        reader.read_value().unwrap(),
+        Some(Value::from(SimpleString::new("OK").unwrap()))
+    );
+    assert_eq!(reader.read_value().unwrap(), None);
+}
+
+#[test]
+fn test_writer() {
+    let mut writer = Resp3Writer::new(vec![]);
+
+    writer
+        .write_value(&Value::from(SimpleString::new("OK").unwrap()))
+        .unwrap();
+    writer.write_cmd(&Cmd::new("PING")).unwrap();
+    assert!(writer.get_ref().is_empty());
+
+    writer.flush().unwrap();
+    assert_eq!(writer.into_inner().unwrap(), b"+OK\r\n*1\r\n$4\r\nPING\r\n");
+}
+
+#[test]
+fn test_connection() {
+    let (addr, server) = serve(vec![
+        (
+            encode(Cmd::new("HELLO").arg("3")),
+            b"%1\r\n$5\r\nproto\r\n:3\r\n",
+        ),
+        (
+            encode(Cmd::new("GET").arg("key")),
+            b">2\r\n$10\r\ninvalidate\r\n*1\r\n$3\r\nkey\r\n$3\r\nval\r\n",
+        ),
+        (
+            [encode(Cmd::new("INCR").arg("n")), encode(Cmd::new("PING"))].concat(),
+            b":1\r\n+PONG\r\n",
+        ),
+    ]);
+
+    let mut connection = Connection::connect(addr).unwrap();
+    connection.hello(&Hello::default()).unwrap();
+    assert_eq!(connection.protocol().version(), Version::Resp3);
+
+    assert_eq!(
+        connection.send(&Cmd::new("GET").arg("key")).unwrap(),
+        Value::from(BlobString::from("val"))
+    );
+
+    assert_eq!(
+        connection
+            .pipeline(&[Cmd::new("INCR").arg("n"), Cmd::new("PING")])
+            .unwrap(),
+        vec![
+            Value::from(Number::from(1)),
+            Value::from(SimpleString::new("PONG").unwrap())
+        ]
+    );
+
+    match connection.next_push().unwrap() {
+        Value::Push(push) => assert_eq!(push.kind(), Some(&b"invalidate"[..])),
+        value => panic!("unexpected value: {value:?}"),
+    }
+
+    server.join().unwrap();
+    assert!(connection.send(&Cmd::new("PING")).is_err());
+}
+
+#[test]
+fn test_connection_subscribe() {
+    let (addr, server) = serve(vec![(
+        encode(Cmd::new("SUBSCRIBE").arg("ch")),
+        b"*3\r\n$9\r\nsubscribe\r\n$2\r\nch\r\n:1\r\n*3\r\n$7\r\nmessage\r\n$2\r\nch\r\n$2\r\nhi\r\n",
+    )]);
+
+    let mut connection = Connection::connect(addr).unwrap();
+
+    assert!(connection.send(&Cmd::new("SUBSCRIBE").arg("ch")).is_err());
+    assert!(connection.write(&Cmd::new("GET").arg("key")).is_err());
+
+    connection.write(&Cmd::new("SUBSCRIBE").arg("ch")).unwrap();
+
+    let kinds = (0..2)
+        .map(|_| match connection.next_push().unwrap() {
+            Value::Push(push) => push.kind().unwrap().to_vec(),
+            value => panic!("unexpected value: {value:?}"),
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(kinds, vec![b"subscribe".to_vec(), b"message".to_vec()]);
+    server.join().unwrap();
+}
+
+#[test]
+fn test_connection_timeout() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // answers late, then takes whatever comes next
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let request = encode(Cmd::new("GET").arg("key"));
+        let mut buf = vec![0; request.len()];
+        stream.read_exact(&mut buf).unwrap();
+
+        thread::sleep(Duration::from_millis(100));
+        stream.write_all(b"$3\r\nval\r\n").unwrap();
+
+        // the unread reply makes the client reset the connection when closing it
+        let mut rest = vec![];
+        let _ = stream.read_to_end(&mut rest);
+        rest
+    });
+
+    let mut connection = Connection::connect(addr).unwrap();
+    connection
+        .set_read_timeout(Some(Duration::from_millis(20)))
+        .unwrap();
+    assert!(connection.send(&Cmd::new("GET").arg("key")).is_err());
+    assert!(connection.protocol().is_broken());
+    assert_eq!(connection.protocol().in_flight(), 0);
+
+    // the late reply is never taken for another command's
+    thread::sleep(Duration::from_millis(150));
+    for _ in 0..2 {
+        let err = connection.send(&Cmd::new("PING")).unwrap_err();
+        assert_eq!(err.to_string(), "Connection (broken)");
+    }
+
+    drop(connection);
+    assert!(server.join().unwrap().is_empty());
+}
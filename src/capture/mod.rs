@@ -0,0 +1,356 @@
+//! On-disk traffic captures.
+//!
+//! A capture is a header (`RESP3CAP` followed by a version byte) and a sequence of records:
+//!
+//! ```text
+//! timestamp   u64 LE  microseconds since the UNIX epoch
+//! connection  u64 LE
+//! direction   u8      0 - client to server, 1 - server to client
+//! length      u32 LE
+//! frame       [u8; length]
+//! ```
+//!
+//! Every record holds exactly one complete RESP frame.
+
+#[cfg(test)]
+pub(crate) mod tests;
+
+use std::{
+    collections::HashMap,
+    io::{self, Read, Write},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{bail, Context};
+use bytes::Bytes;
+
+use crate::value::{FrameScanner, Value};
+
+static MAGIC: &[u8] = b"RESP3CAP";
+const VERSION: u8 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Direction {
+    ToServer,
+    ToClient,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub timestamp: SystemTime,
+    pub conn_id: u64,
+    pub direction: Direction,
+    pub frame: Bytes,
+}
+
+impl Record {
+    pub fn value(&self) -> anyhow::Result<Value> {
+        match Value::decode(&self.frame)? {
+            Some((value, len)) if len == self.frame.len() => Ok(value),
+            _ => bail!("Record (frame is not a single value)"),
+        }
+    }
+}
+
+/// Writes records, splitting raw traffic into frames.
+///
+/// Bytes may be fed in arbitrary chunks, exactly as a proxy sees them:
+/// partial frames are buffered per connection and direction until complete.
+#[derive(Debug)]
+pub struct Recorder<W: Write> {
+    writer: W,
+    // bytes of the frame not complete yet, per connection and direction
+    pending: HashMap<(u64, Direction), (Vec<u8>, FrameScanner)>,
+}
+
+impl<W: Write> Recorder<W> {
+    pub fn new(mut writer: W) -> anyhow::Result<Self> {
+        writer
+            .write_all(MAGIC)
+            .and_then(|_| writer.write_all(&[VERSION]))
+            .context("Recorder (writer::write_all)")?;
+
+        Ok(Self {
+            writer,
+            pending: HashMap::new(),
+        })
+    }
+
+    /// Records raw bytes and returns the number of complete frames written.
+    pub fn record(
+        &mut self,
+        conn_id: u64,
+        direction: Direction,
+        bytes: &[u8],
+    ) -> anyhow::Result<usize> {
+        self.record_at(SystemTime::now(), conn_id, direction, bytes)
+    }
+
+    pub fn record_at(
+        &mut self,
+        timestamp: SystemTime,
+        conn_id: u64,
+        direction: Direction,
+        bytes: &[u8],
+    ) -> anyhow::Result<usize> {
+        let (buf, scanner) = self.pending.entry((conn_id, direction)).or_default();
+        buf.extend_from_slice(bytes);
+
+        let mut offset = 0;
+        let mut frames = vec![];
+
+        // frames are only decoded (to check them) once complete
+        let result = loop {
+            let len = match scanner.scan(&buf[offset..]) {
+                Ok(Some(len)) => len,
+                Ok(None) => break Ok(()),
+                Err(err) => break Err(err),
+            };
+
+            let frame = &buf[offset..offset + len];
+            if let Err(err) = Value::decode(frame) {
+                break Err(err);
+            }
+
+            frames.push(Bytes::copy_from_slice(frame));
+            offset += len;
+        };
+
+        // a malformed frame poisons the stream, there is no way to resynchronize
+        match result {
+            Ok(()) => drop(buf.drain(..offset)),
+            Err(_) => drop(self.pending.remove(&(conn_id, direction))),
+        }
+
+        for frame in frames.iter() {
+            self.write(timestamp, conn_id, direction, frame)?;
+        }
+
+        result.with_context(|| format!("Recorder (malformed frame on connection {conn_id})"))?;
+        Ok(frames.len())
+    }
+
+    /// Records a value which has already been decoded (e.g. by a codec).
+    pub fn record_value(
+        &mut self,
+        conn_id: u64,
+        direction: Direction,
+        value: &Value,
+    ) -> anyhow::Result<()> {
+        let frame = Bytes::try_from(value).context("Recorder (Bytes::try_from)")?;
+        self.write(SystemTime::now(), conn_id, direction, &frame)
+    }
+
+    /// Forgets a closed connection, failing if it left a partial frame behind.
+    pub fn close(&mut self, conn_id: u64) -> anyhow::Result<()> {
+        let mut partial = false;
+
+        for direction in [Direction::ToServer, Direction::ToClient] {
+            if let Some((buf, _)) = self.pending.remove(&(conn_id, direction)) {
+                partial |= !buf.is_empty();
+            }
+        }
+
+        if partial {
+            bail!("Recorder (connection {conn_id} closed mid-frame)");
+        }
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> anyhow::Result<()> {
+        self.writer.flush().context("Recorder (writer::flush)")
+    }
+
+    pub fn into_inner(mut self) -> anyhow::Result<W> {
+        self.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write(
+        &mut self,
+        timestamp: SystemTime,
+        conn_id: u64,
+        direction: Direction,
+        frame: &[u8],
+    ) -> anyhow::Result<()> {
+        let micros = timestamp
+            .duration_since(UNIX_EPOCH)
+            .context("Recorder (timestamp before UNIX epoch)")?
+            .as_micros() as u64;
+        let len = u32::try_from(frame.len()).context("Recorder (frame too large)")?;
+        let direction = match direction {
+            Direction::ToServer => 0u8,
+            Direction::ToClient => 1u8,
+        };
+
+        self.writer
+            .write_all(&micros.to_le_bytes())
+            .and_then(|_| self.writer.write_all(&conn_id.to_le_bytes()))
+            .and_then(|_| self.writer.write_all(&[direction]))
+            .and_then(|_| self.writer.write_all(&len.to_le_bytes()))
+            .and_then(|_| self.writer.write_all(frame))
+            .context("Recorder (writer::write_all)")
+    }
+}
+
+/// Reads records back from a capture.
+#[derive(Debug)]
+pub struct Reader<R: Read> {
+    reader: R,
+}
+
+impl<R: Read> Reader<R> {
+    pub fn new(mut reader: R) -> anyhow::Result<Self> {
+        let mut header = [0; 9];
+        reader
+            .read_exact(&mut header)
+            .context("Reader (missing header)")?;
+
+        if &header[..8] != MAGIC {
+            bail!("Reader (not a capture)");
+        }
+
+        if header[8] != VERSION {
+            bail!("Reader (unsupported version {})", header[8]);
+        }
+
+        Ok(Self { reader })
+    }
+
+    fn read_record(&mut self) -> anyhow::Result<Option<Record>> {
+        let mut head = [0; 21];
+
+        match self.reader.read_exact(&mut head[..1]) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err).context("Reader (reader::read_exact)"),
+        }
+
+        self.reader
+            .read_exact(&mut head[1..])
+            .context("Reader (truncated record)")?;
+
+        let micros = u64::from_le_bytes(head[0..8].try_into()?);
+        let conn_id = u64::from_le_bytes(head[8..16].try_into()?);
+        let direction = match head[16] {
+            0 => Direction::ToServer,
+            1 => Direction::ToClient,
+            other => bail!("Reader (unknown direction {other})"),
+        };
+        let len = u32::from_le_bytes(head[17..21].try_into()?) as usize;
+
+        let mut frame = vec![0; len];
+        self.reader
+            .read_exact(&mut frame)
+            .context("Reader (truncated frame)")?;
+
+        Ok(Some(Record {
+            timestamp: UNIX_EPOCH + Duration::from_micros(micros),
+            conn_id,
+            direction,
+            frame: Bytes::from(frame),
+        }))
+    }
+}
+
+impl<R: Read> Iterator for Reader<R> {
+    type Item = anyhow::Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Speed {
+    /// Keep the original gaps between records.
+    Original,
+    /// Divide the original gaps by the given factor.
+    Accelerated(f64),
+    /// Do not wait at all.
+    Unthrottled,
+}
+
+/// Feeds recorded frames back, keeping (or scaling) the original timing.
+#[derive(Debug)]
+pub struct Replayer<I> {
+    records: I,
+    speed: Speed,
+}
+
+impl<I: Iterator<Item = anyhow::Result<Record>>> Replayer<I> {
+    pub fn new(records: I, speed: Speed) -> Self {
+        Self { records, speed }
+    }
+
+    /// Hands every record going in `direction` to `sink` at its (scaled) original time.
+    ///
+    /// Replaying [`Direction::ToServer`] drives a server, [`Direction::ToClient`] a mock client.
+    pub fn replay(
+        self,
+        direction: Direction,
+        mut sink: impl FnMut(&Record) -> anyhow::Result<()>,
+    ) -> anyhow::Result<usize> {
+        if let Speed::Accelerated(factor) = self.speed {
+            if !(factor.is_finite() && factor > 0.0) {
+                bail!("Replayer (invalid speed factor {factor})");
+            }
+        }
+
+        let started = Instant::now();
+        let mut first = None;
+        let mut count = 0;
+
+        for record in self.records {
+            let record = record?;
+            if record.direction != direction {
+                continue;
+            }
+
+            let first = *first.get_or_insert(record.timestamp);
+            let offset = record.timestamp.duration_since(first).unwrap_or_default();
+
+            let offset = match self.speed {
+                Speed::Original => Some(offset),
+                Speed::Accelerated(factor) => Some(offset.div_f64(factor)),
+                Speed::Unthrottled => None,
+            };
+
+            if let Some(wait) = offset.and_then(|offset| offset.checked_sub(started.elapsed())) {
+                thread::sleep(wait);
+            }
+
+            sink(&record)?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Replays the frames of connection `conn_id` into `writer`,
+    /// leaving out those of the other connections.
+    pub fn replay_to(
+        self,
+        conn_id: u64,
+        direction: Direction,
+        writer: &mut impl Write,
+    ) -> anyhow::Result<usize> {
+        let mut count = 0;
+
+        self.replay(direction, |record| {
+            if record.conn_id != conn_id {
+                return Ok(());
+            }
+
+            count += 1;
+            writer
+                .write_all(&record.frame)
+                .and_then(|_| writer.flush())
+                .context("Replayer (writer::write_all)")
+        })?;
+
+        Ok(count)
+    }
+}
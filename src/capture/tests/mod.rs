@@ -0,0 +1,163 @@
+use super::*;
+use crate::value::{Number, SimpleString};
+
+fn at(millis: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_millis(1_000_000 + millis)
+}
+
+fn capture() -> Vec<u8> {
+    let mut recorder = Recorder::new(vec![]).unwrap();
+
+    assert_eq!(
+        recorder
+            .record_at(at(0), 1, Direction::ToServer, b"*1\r\n$4\r\nPI")
+            .unwrap(),
+        0
+    );
+    assert_eq!(
+        recorder
+            .record_at(
+                at(10),
+                1,
+                Direction::ToServer,
+                b"NG\r\n*1\r\n$4\r\nPING\r\n"
+            )
+            .unwrap(),
+        2
+    );
+    assert_eq!(
+        recorder
+            .record_at(at(20), 2, Direction::ToClient, b"+PONG\r\n:1\r\n")
+            .unwrap(),
+        2
+    );
+
+    recorder.into_inner().unwrap()
+}
+
+#[test]
+fn test_roundtrip() {
+    let records = Reader::new(&capture()[..])
+        .unwrap()
+        .collect::<anyhow::Result<Vec<_>>>()
+        .unwrap();
+
+    assert_eq!(
+        records
+            .iter()
+            .map(|r| (r.timestamp, r.conn_id, r.direction, &r.frame[..]))
+            .collect::<Vec<_>>(),
+        vec![
+            (at(10), 1, Direction::ToServer, &b"*1\r\n$4\r\nPING\r\n"[..]),
+            (at(10), 1, Direction::ToServer, &b"*1\r\n$4\r\nPING\r\n"[..]),
+            (at(20), 2, Direction::ToClient, &b"+PONG\r\n"[..]),
+            (at(20), 2, Direction::ToClient, &b":1\r\n"[..]),
+        ]
+    );
+
+    assert_eq!(
+        records[2].value().unwrap(),
+        Value::from(SimpleString::new("PONG").unwrap())
+    );
+    assert_eq!(records[3].value().unwrap(), Value::from(Number::from(1)));
+}
+
+#[test]
+fn test_malformed() {
+    let mut recorder = Recorder::new(vec![]).unwrap();
+
+    assert!(recorder
+        .record(1, Direction::ToClient, b"+OK\r\n?\r\n")
+        .is_err());
+    assert!(recorder.record(1, Direction::ToClient, b"+OK\r\n").is_ok());
+
+    let capture = recorder.into_inner().unwrap();
+    assert_eq!(Reader::new(&capture[..]).unwrap().count(), 2);
+
+    let mut recorder = Recorder::new(vec![]).unwrap();
+
+    recorder.record(2, Direction::ToClient, b"+O").unwrap();
+    assert!(recorder.close(2).is_err());
+    assert!(recorder.close(1).is_ok());
+}
+
+#[test]
+fn test_invalid_capture() {
+    assert!(Reader::new(&b"RESP2CAP\x01"[..]).is_err());
+    assert!(Reader::new(&b"RESP3CAP\x02"[..]).is_err());
+
+    let mut capture = capture();
+    capture.pop();
+
+    let records = Reader::new(&capture[..]).unwrap().collect::<Vec<_>>();
+    assert!(records.last().unwrap().is_err());
+}
+
+#[test]
+fn test_replay() {
+    let reader = Reader::new(std::io::Cursor::new(capture())).unwrap();
+
+    let mut out = vec![];
+    let count = Replayer::new(reader, Speed::Unthrottled)
+        .replay_to(2, Direction::ToClient, &mut out)
+        .unwrap();
+
+    assert_eq!(count, 2);
+    assert_eq!(out, b"+PONG\r\n:1\r\n");
+}
+
+#[test]
+fn test_replay_connection() {
+    let mut recorder = Recorder::new(vec![]).unwrap();
+    recorder
+        .record_at(at(0), 1, Direction::ToClient, b"+PONG\r\n")
+        .unwrap();
+    recorder
+        .record_at(at(1), 2, Direction::ToClient, b"$3\r\nfoo\r\n")
+        .unwrap();
+    recorder
+        .record_at(at(2), 1, Direction::ToClient, b":1\r\n")
+        .unwrap();
+    let capture = recorder.into_inner().unwrap();
+
+    let replay = |conn_id| {
+        let reader = Reader::new(&capture[..]).unwrap();
+        let mut out = vec![];
+        let count = Replayer::new(reader, Speed::Unthrottled)
+            .replay_to(conn_id, Direction::ToClient, &mut out)
+            .unwrap();
+
+        (count, out)
+    };
+
+    assert_eq!(replay(1), (2, b"+PONG\r\n:1\r\n".to_vec()));
+    assert_eq!(replay(2), (1, b"$3\r\nfoo\r\n".to_vec()));
+    assert_eq!(replay(3), (0, vec![]));
+}
+
+#[test]
+fn test_replay_speed() {
+    let records = || {
+        [0, 200].into_iter().map(|millis| {
+            Ok(Record {
+                timestamp: at(millis),
+                conn_id: 1,
+                direction: Direction::ToServer,
+                frame: Bytes::from_static(b"_\r\n"),
+            })
+        })
+    };
+
+    let started = Instant::now();
+    Replayer::new(records(), Speed::Accelerated(4.0))
+        .replay(Direction::ToServer, |_| Ok(()))
+        .unwrap();
+
+    let elapsed = started.elapsed();
+    assert!(elapsed >= Duration::from_millis(50), "{elapsed:?}");
+    assert!(elapsed < Duration::from_millis(200), "{elapsed:?}");
+
+    assert!(Replayer::new(records(), Speed::Accelerated(0.0))
+        .replay(Direction::ToServer, |_| Ok(()))
+        .is_err());
+}
@@ -0,0 +1,496 @@
+//! Redis Cluster: key slots and a client routing every command to the node serving it.
+
+#[cfg(test)]
+pub(crate) mod tests;
+
+mod pipeline;
+mod topology;
+
+pub use topology::{Change, Health, Node, Role, Shard, Topology};
+
+use std::{collections::HashMap, ops::RangeInclusive, time::Duration};
+
+use anyhow::{anyhow, Context};
+use tokio::task::JoinSet;
+
+use pipeline::Plan;
+
+use crate::{
+    aio::{Connection, NotSent},
+    cmd::{Cmd, CommandTable},
+    error::ErrorKind,
+    protocol::Hello,
+    value::Value,
+};
+
+/// The number of hash slots keys are spread over.
+pub const SLOTS: u16 = 16384;
+
+/// The slot of a key: CRC16 of the key, or of its `{hash tag}` if it has a non empty one,
+/// modulo [`SLOTS`].
+pub fn slot(key: &[u8]) -> u16 {
+    crc16(hash_tag(key)) % SLOTS
+}
+
+fn hash_tag(key: &[u8]) -> &[u8] {
+    let Some(start) = key.iter().position(|b| *b == b'{') else {
+        return key;
+    };
+
+    match key[start + 1..].iter().position(|b| *b == b'}') {
+        Some(len) if len > 0 => &key[start + 1..start + 1 + len],
+        _ => key,
+    }
+}
+
+// CRC16-CCITT (XMODEM), as specified by the cluster spec.
+fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0, |crc, byte| {
+        (0..8).fold(crc ^ (u16::from(*byte) << 8), |crc, _| match crc & 0x8000 {
+            0 => crc << 1,
+            _ => (crc << 1) ^ 0x1021,
+        })
+    })
+}
+
+/// Which node (`host:port`) serves each slot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlotMap {
+    nodes: Vec<String>,
+    slots: Vec<Option<usize>>,
+}
+
+impl Default for SlotMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SlotMap {
+    /// A map with no slot assigned.
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![],
+            slots: vec![None; SLOTS.into()],
+        }
+    }
+
+    pub fn get(&self, slot: u16) -> Option<&str> {
+        let node = (*self.slots.get(usize::from(slot))?)?;
+        Some(&self.nodes[node])
+    }
+
+    pub fn set(&mut self, slots: RangeInclusive<u16>, addr: &str) {
+        let node = match self.nodes.iter().position(|node| node == addr) {
+            Some(node) => node,
+            None => {
+                self.nodes.push(addr.to_string());
+                self.nodes.len() - 1
+            }
+        };
+
+        let end = (*slots.end()).min(SLOTS - 1);
+        for slot in *slots.start()..=end {
+            self.slots[usize::from(slot)] = Some(node);
+        }
+    }
+
+    /// Every node serving at least one slot.
+    pub fn nodes(&self) -> impl Iterator<Item = &str> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.slots.contains(&Some(*i)))
+            .map(|(_, node)| node.as_str())
+    }
+
+    /// Builds the map from a `CLUSTER SLOTS` reply, `host` stands in for unknown hostnames.
+    pub fn from_cluster_slots(reply: &Value, host: &str) -> anyhow::Result<Self> {
+        let topology = Topology::from_cluster_slots(reply).context("SlotMap")?;
+        Ok(Self::from_topology(&topology, host))
+    }
+
+    /// Maps the slots of every shard to its primary, `host` stands in for unknown addresses.
+    /// Shards without a reachable primary are left out.
+    pub fn from_topology(topology: &Topology, host: &str) -> Self {
+        let mut map = Self::new();
+
+        for shard in topology.shards.iter() {
+            let Some(primary) = shard.primary() else {
+                continue;
+            };
+
+            let Some(port) = primary.port else {
+                continue;
+            };
+
+            let addr = match (primary.ip.as_str(), primary.hostname.as_deref()) {
+                ("", Some(hostname)) => format!("{hostname}:{port}"),
+                ("", None) => format!("{host}:{port}"),
+                (ip, _) => format!("{ip}:{port}"),
+            };
+
+            for slots in shard.slots.iter() {
+                map.set(slots.clone(), &addr);
+            }
+        }
+
+        map
+    }
+}
+
+/// Parameters of [`ClusterClient::connect_with`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClusterOptions {
+    /// Sent to every node right after connecting.
+    pub hello: Option<Hello>,
+    /// How many `MOVED`/`ASK` redirections to follow for one command.
+    pub max_redirects: usize,
+    /// How many times to retry a command after `TRYAGAIN`, `CLUSTERDOWN` or a lost node.
+    /// Commands are only sent again to another node if they were not written to the
+    /// lost one, or if they are read only.
+    pub retries: usize,
+    /// The delay before the first retry, doubled on every following one.
+    pub backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for ClusterOptions {
+    fn default() -> Self {
+        Self {
+            hello: None,
+            max_redirects: 16,
+            retries: 5,
+            backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Routes commands to the node serving the slot of their key, following redirections.
+///
+/// Keys are found with the bundled [`CommandTable`](crate::cmd::CommandTable), commands
+/// without keys go to any node.
+#[derive(Debug)]
+pub struct ClusterClient {
+    seeds: Vec<String>,
+    options: ClusterOptions,
+    slots: SlotMap,
+    connections: HashMap<String, Connection>,
+}
+
+impl ClusterClient {
+    /// Connects to the first reachable seed node and loads the slot map from it.
+    pub async fn connect(
+        seeds: impl IntoIterator<Item = impl Into<String>>,
+    ) -> anyhow::Result<Self> {
+        Self::connect_with(seeds, ClusterOptions::default()).await
+    }
+
+    pub async fn connect_with(
+        seeds: impl IntoIterator<Item = impl Into<String>>,
+        options: ClusterOptions,
+    ) -> anyhow::Result<Self> {
+        let mut client = Self {
+            seeds: seeds.into_iter().map(Into::into).collect(),
+            options,
+            slots: SlotMap::new(),
+            connections: HashMap::new(),
+        };
+
+        client.refresh_slots().await?;
+        Ok(client)
+    }
+
+    pub fn slots(&self) -> &SlotMap {
+        &self.slots
+    }
+
+    /// Reloads the slot map with `CLUSTER SLOTS`, asking the known nodes and then the seeds.
+    pub async fn refresh_slots(&mut self) -> anyhow::Result<()> {
+        let mut candidates = self.slots.nodes().map(String::from).collect::<Vec<_>>();
+        candidates.extend(self.seeds.iter().cloned());
+
+        let mut last_err = anyhow!("ClusterClient (no seed nodes)");
+
+        for addr in candidates {
+            let reply = match self
+                .send_to(&addr, &Cmd::new("CLUSTER").arg("SLOTS"), false)
+                .await
+            {
+                Ok(reply) => reply,
+                Err(err) => {
+                    last_err = err;
+                    continue;
+                }
+            };
+
+            match SlotMap::from_cluster_slots(&reply, host(&addr)) {
+                Ok(slots) => {
+                    self.slots = slots;
+                    return Ok(());
+                }
+                Err(err) => last_err = err,
+            }
+        }
+
+        Err(last_err.context("ClusterClient (refresh_slots)"))
+    }
+
+    /// Sends a command to the node serving its key and waits for the reply.
+    /// Server errors other than redirections are returned as values.
+    ///
+    /// Multi-key commands spanning slots (`MGET`, `DEL`, `UNLINK`, `EXISTS`, `TOUCH`)
+    /// are split per slot and their replies merged back. `MSET` is not, as its parts
+    /// would no longer be applied all or nothing.
+    pub async fn send(&mut self, cmd: &Cmd) -> anyhow::Result<Value> {
+        let mut replies = self.pipeline(std::slice::from_ref(cmd)).await?;
+        Ok(replies.remove(0))
+    }
+
+    /// Sends the commands in one pipeline per node, all nodes at once,
+    /// and returns the replies in the original order. Like [`ClusterClient::send`]
+    /// multi-key commands are split per slot, and redirected commands are sent again.
+    ///
+    /// Fails if the connection to a node is lost after its batch was written, as
+    /// some of its commands may have run already, unless they are all read only.
+    pub async fn pipeline(&mut self, cmds: &[Cmd]) -> anyhow::Result<Vec<Value>> {
+        let plan = Plan::new(cmds);
+
+        let mut batches = Vec::<(String, Vec<usize>)>::new();
+        for (i, cmd) in plan.subs.iter().enumerate() {
+            let addr = self.route(pipeline::key(cmd).map(slot))?;
+
+            match batches.iter_mut().find(|(a, _)| *a == addr) {
+                Some((_, batch)) => batch.push(i),
+                None => batches.push((addr, vec![i])),
+            }
+        }
+
+        let mut replies = vec![None; plan.subs.len()];
+        let mut tasks = JoinSet::new();
+
+        for (addr, batch) in batches {
+            let cmds = batch
+                .iter()
+                .map(|i| plan.subs[*i].clone())
+                .collect::<Vec<_>>();
+
+            // connections are moved into the tasks and put back once they are done
+            if self.connection(&addr).await.is_err() {
+                continue;
+            }
+            let Some(mut connection) = self.connections.remove(&addr) else {
+                continue;
+            };
+
+            tasks.spawn(async move {
+                let result = connection.pipeline(&cmds).await;
+                (addr, connection, batch, result)
+            });
+        }
+
+        let mut lost = None;
+        while let Some(task) = tasks.join_next().await {
+            let (addr, connection, batch, result) = task.context("ClusterClient (task)")?;
+
+            match result {
+                Ok(values) => {
+                    self.connections.insert(addr.clone(), connection);
+
+                    for (i, value) in batch.into_iter().zip(values) {
+                        replies[i] = Some((addr.clone(), value));
+                    }
+                }
+                // the node never got the batch, or running it twice does no harm: its
+                // commands are sent again below, as `execute` would
+                Err(err) if batch.iter().all(|i| retryable(&plan.subs[*i], &err)) => {}
+                // some commands may have run, sending them again could apply writes twice
+                Err(err) => {
+                    lost.get_or_insert(err.context(format!(
+                        "ClusterClient ({addr} was lost, {} commands may have run)",
+                        batch.len()
+                    )));
+                }
+            }
+        }
+
+        if let Some(err) = lost {
+            return Err(err);
+        }
+
+        // commands of batches that were not sent and redirected ones go one by one
+        let mut values = Vec::with_capacity(replies.len());
+        for (i, reply) in replies.into_iter().enumerate() {
+            let redirected = reply
+                .as_ref()
+                .and_then(|(_, reply)| reply.server_error())
+                .is_some_and(|err| {
+                    matches!(
+                        err.kind(),
+                        ErrorKind::Moved { .. }
+                            | ErrorKind::Ask { .. }
+                            | ErrorKind::TryAgain
+                            | ErrorKind::ClusterDown
+                    )
+                });
+
+            match reply {
+                Some((_, value)) if !redirected => values.push(value),
+                reply => values.push(self.execute(&plan.subs[i], reply).await?),
+            }
+        }
+
+        Ok(plan.merge(values))
+    }
+
+    // Sends one command, following redirections and retrying. `first` is a reply already
+    // received from the given node, handled before anything is sent.
+    async fn execute(
+        &mut self,
+        cmd: &Cmd,
+        mut first: Option<(String, Value)>,
+    ) -> anyhow::Result<Value> {
+        let slot = pipeline::key(cmd).map(slot);
+
+        let mut target = match &first {
+            Some((addr, _)) => addr.clone(),
+            None => self.route(slot)?,
+        };
+        let mut asking = false;
+        let mut redirects = 0;
+        let mut retries = 0;
+
+        loop {
+            let sent = match first.take() {
+                Some((_, reply)) => Ok(reply),
+                None => self.send_to(&target, cmd, asking).await,
+            };
+
+            let reply = match sent {
+                Ok(reply) => reply,
+                Err(err) if retries < self.options.retries && retryable(cmd, &err) => {
+                    self.backoff(&mut retries).await;
+
+                    // the node is probably gone, ask the others who took over its slots
+                    if self.refresh_slots().await.is_err() {
+                        return Err(err);
+                    }
+
+                    target = self.route(slot)?;
+                    asking = false;
+                    continue;
+                }
+                Err(err) => return Err(err),
+            };
+
+            let Some(err) = reply.server_error() else {
+                return Ok(reply);
+            };
+
+            match err.kind() {
+                ErrorKind::Moved { slot, addr } if redirects < self.options.max_redirects => {
+                    let addr = resolve(addr, &target);
+                    self.slots.set(*slot..=*slot, &addr);
+
+                    target = addr;
+                    asking = false;
+                    redirects += 1;
+                }
+                ErrorKind::Ask { addr, .. } if redirects < self.options.max_redirects => {
+                    target = resolve(addr, &target);
+                    asking = true;
+                    redirects += 1;
+                }
+                ErrorKind::TryAgain | ErrorKind::ClusterDown if retries < self.options.retries => {
+                    self.backoff(&mut retries).await;
+                    target = self.route(slot)?;
+                    asking = false;
+                }
+                _ => return Ok(reply),
+            }
+        }
+    }
+
+    fn route(&self, slot: Option<u16>) -> anyhow::Result<String> {
+        slot.and_then(|slot| self.slots.get(slot))
+            .or_else(|| self.slots.nodes().next())
+            .or_else(|| self.seeds.first().map(String::as_str))
+            .map(String::from)
+            .ok_or_else(|| anyhow!("ClusterClient (no node to send to)"))
+    }
+
+    async fn backoff(&self, retries: &mut usize) {
+        let delay = self
+            .options
+            .backoff
+            .saturating_mul(1 << (*retries).min(16))
+            .min(self.options.max_backoff);
+        *retries += 1;
+
+        tokio::time::sleep(delay).await;
+    }
+
+    async fn send_to(&mut self, addr: &str, cmd: &Cmd, asking: bool) -> anyhow::Result<Value> {
+        let connection = match self.connection(addr).await {
+            Ok(connection) => connection,
+            Err(err) => {
+                return Err(err
+                    .context(NotSent)
+                    .context(format!("ClusterClient ({addr})")))
+            }
+        };
+
+        let reply = match asking {
+            true => connection
+                .pipeline(&[Cmd::new("ASKING"), cmd.clone()])
+                .await
+                .map(|mut replies| replies.remove(1)),
+            false => connection.send(cmd).await,
+        };
+
+        if reply.is_err() {
+            self.connections.remove(addr);
+        }
+
+        reply.with_context(|| format!("ClusterClient ({addr})"))
+    }
+
+    async fn connection(&mut self, addr: &str) -> anyhow::Result<&mut Connection> {
+        if !self.connections.contains_key(addr) {
+            let mut connection = Connection::connect(addr).await?;
+
+            if let Some(hello) = self.options.hello.as_ref() {
+                connection.hello(hello).await?;
+            }
+
+            self.connections.insert(addr.to_string(), connection);
+        }
+
+        Ok(self
+            .connections
+            .get_mut(addr)
+            .expect("connection was just inserted"))
+    }
+}
+
+// Whether a command can be sent again after the connection failed: only if it did not
+// reach the node, or if running it twice does no harm.
+fn retryable(cmd: &Cmd, err: &anyhow::Error) -> bool {
+    err.downcast_ref::<NotSent>().is_some()
+        || CommandTable::bundled()
+            .lookup(cmd)
+            .is_some_and(|info| info.is_readonly())
+}
+
+fn host(addr: &str) -> &str {
+    addr.rsplit_once(':').map_or(addr, |(host, _)| host)
+}
+
+// Redirections may leave the host out (`:7001`) when it is the same as the replying node's.
+fn resolve(addr: &str, from: &str) -> String {
+    match addr.strip_prefix(':') {
+        Some(port) => format!("{}:{port}", host(from)),
+        None => addr.to_string(),
+    }
+}
@@ -0,0 +1,178 @@
+//! Splitting pipelines by slot and merging the replies back in order.
+
+#[cfg(test)]
+pub(crate) mod tests;
+
+use bytes::Bytes;
+
+use super::slot;
+use crate::{
+    cmd::{Cmd, CommandTable},
+    value::{Array, Null, Number, Value},
+};
+
+/// How the replies of a multi-key command split per slot are put back together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Merge {
+    /// One value per key, in key order (`MGET`).
+    Concat,
+    /// Counts added up (`DEL`, `EXISTS`...).
+    Sum,
+}
+
+// Multi-key commands which can be split per slot: how many arguments go with each key,
+// and how their replies merge. Only those whose parts can fail on their own without
+// breaking a promise of the command: a split `MSET` would no longer be atomic.
+fn fan_out(name: &[u8]) -> Option<(usize, Merge)> {
+    match &name.to_ascii_uppercase()[..] {
+        b"MGET" => Some((1, Merge::Concat)),
+        b"DEL" | b"UNLINK" | b"EXISTS" | b"TOUCH" => Some((1, Merge::Sum)),
+        _ => None,
+    }
+}
+
+/// The key a command is routed by: its first key according to the bundled command table,
+/// the first argument for commands the table does not know.
+pub(super) fn key(cmd: &Cmd) -> Option<&[u8]> {
+    let table = CommandTable::bundled();
+
+    match table.lookup(cmd) {
+        Some(_) => table.extract_keys(cmd).into_iter().next(),
+        None => cmd.args().get(1).map(|key| &key[..]),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    /// Sent as is, the reply of the sub-command at this index.
+    Whole(usize),
+    /// Split per slot: sub-command indexes with the positions of their keys in the original.
+    Split {
+        merge: Merge,
+        keys: usize,
+        subs: Vec<(usize, Vec<usize>)>,
+    },
+}
+
+/// A pipeline rewritten so that no command spans slots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Plan {
+    pub(super) subs: Vec<Cmd>,
+    parts: Vec<Part>,
+}
+
+impl Plan {
+    pub(super) fn new(cmds: &[Cmd]) -> Self {
+        let mut plan = Self {
+            subs: vec![],
+            parts: vec![],
+        };
+
+        for cmd in cmds {
+            let part = match fan_out(cmd.name()) {
+                Some((step, merge)) => plan.split(cmd, step, merge),
+                None => None,
+            };
+
+            let part = part.unwrap_or_else(|| {
+                plan.subs.push(cmd.clone());
+                Part::Whole(plan.subs.len() - 1)
+            });
+
+            plan.parts.push(part);
+        }
+
+        plan
+    }
+
+    // `None` if every key is in the same slot.
+    fn split(&mut self, cmd: &Cmd, step: usize, merge: Merge) -> Option<Part> {
+        let mut slots: Vec<(u16, Vec<Bytes>, Vec<usize>)> = vec![];
+        let groups = cmd.args()[1..].chunks(step);
+        let keys = groups.len();
+
+        for (i, group) in groups.enumerate() {
+            let slot = slot(&group[0]);
+
+            let at = match slots.iter().position(|(s, ..)| *s == slot) {
+                Some(at) => at,
+                None => {
+                    slots.push((slot, vec![cmd.args()[0].clone()], vec![]));
+                    slots.len() - 1
+                }
+            };
+
+            slots[at].1.extend(group.iter().cloned());
+            slots[at].2.push(i);
+        }
+
+        if slots.len() < 2 {
+            return None;
+        }
+
+        let subs = slots
+            .into_iter()
+            .map(|(_, args, positions)| {
+                self.subs.push(args.into_iter().collect());
+                (self.subs.len() - 1, positions)
+            })
+            .collect();
+
+        Some(Part::Split { merge, keys, subs })
+    }
+
+    /// Puts the replies of the sub-commands back together, one per original command.
+    pub(super) fn merge(&self, replies: Vec<Value>) -> Vec<Value> {
+        let mut replies = replies.into_iter().map(Some).collect::<Vec<_>>();
+        let mut take = |i: usize| replies[i].take().expect("every reply is taken once");
+
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Whole(i) => take(*i),
+                Part::Split { merge, keys, subs } => {
+                    let replies = subs
+                        .iter()
+                        .map(|(i, positions)| (take(*i), positions))
+                        .collect::<Vec<_>>();
+
+                    merged(*merge, *keys, replies)
+                }
+            })
+            .collect()
+    }
+}
+
+// The first unexpected reply (usually an error) stands for the whole command.
+fn merged(merge: Merge, keys: usize, replies: Vec<(Value, &Vec<usize>)>) -> Value {
+    match merge {
+        Merge::Concat => {
+            let mut values = vec![Value::from(Null::default()); keys];
+
+            for (reply, positions) in replies {
+                match reply {
+                    Value::Array(array) if array.len() == positions.len() => {
+                        for (value, at) in array.into_iter().zip(positions) {
+                            values[*at] = value;
+                        }
+                    }
+                    reply => return reply,
+                }
+            }
+
+            Value::from(Array::from(values))
+        }
+        Merge::Sum => {
+            let mut sum = 0i64;
+
+            for (reply, _) in replies {
+                match reply {
+                    Value::Number(count) => sum = sum.saturating_add(count.val()),
+                    reply => return reply,
+                }
+            }
+
+            Value::from(Number::from(sum))
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use super::*;
+use crate::resp;
+
+// keys hashing to different slots
+const A: &str = "{a}";
+const B: &str = "{b}";
+
+#[test]
+fn test_plan_whole() {
+    let cmds = [
+        Cmd::new("GET").arg(A),
+        Cmd::new("MGET").arg("{a}1").arg("{a}2"),
+        Cmd::new("PING"),
+    ];
+    let plan = Plan::new(&cmds);
+
+    assert_eq!(plan.subs, cmds);
+    assert_eq!(
+        plan.merge(vec![resp!(1), resp!([2, 3]), resp!(+"PONG")]),
+        [resp!(1), resp!([2, 3]), resp!(+"PONG")]
+    );
+}
+
+#[test]
+fn test_plan_split() {
+    let cmds = [
+        Cmd::new("mget").arg(A).arg(B).arg("{a}1"),
+        Cmd::new("GET").arg(B),
+        Cmd::new("DEL").arg(A).arg(B),
+        // left to the server to refuse, splitting it would lose its atomicity
+        Cmd::new("MSET").arg(A).arg("1").arg(B).arg("2"),
+    ];
+    let plan = Plan::new(&cmds);
+
+    assert_eq!(
+        plan.subs,
+        [
+            Cmd::new("mget").arg(A).arg("{a}1"),
+            Cmd::new("mget").arg(B),
+            Cmd::new("GET").arg(B),
+            Cmd::new("DEL").arg(A),
+            Cmd::new("DEL").arg(B),
+            Cmd::new("MSET").arg(A).arg("1").arg(B).arg("2"),
+        ]
+    );
+
+    assert_eq!(
+        plan.merge(vec![
+            resp!(["a", "a1"]),
+            resp!([nil]),
+            resp!(nil),
+            resp!(1),
+            resp!(0),
+            resp!(-"CROSSSLOT Keys in request don't hash to the same slot"),
+        ]),
+        [
+            resp!(["a", nil, "a1"]),
+            resp!(nil),
+            resp!(1),
+            resp!(-"CROSSSLOT Keys in request don't hash to the same slot")
+        ]
+    );
+}
+
+#[test]
+fn test_plan_split_errors() {
+    let cmds = [
+        Cmd::new("MGET").arg(A).arg(B),
+        Cmd::new("EXISTS").arg(A).arg(B),
+    ];
+    let plan = Plan::new(&cmds);
+
+    assert_eq!(
+        plan.merge(vec![
+            resp!(["a"]),
+            resp!(-"ERR down"),
+            resp!(-"WRONGTYPE"),
+            resp!(1),
+        ]),
+        [resp!(-"ERR down"), resp!(-"WRONGTYPE")]
+    );
+}
+
+#[test]
+fn test_key() {
+    let key = |line| key(&Cmd::from_line(line).unwrap()).map(<[u8]>::to_vec);
+
+    assert_eq!(key("GET a"), Some(b"a".to_vec()));
+    assert_eq!(key("EVAL script 1 a"), Some(b"a".to_vec()));
+    assert_eq!(key("XREAD STREAMS a 0"), Some(b"a".to_vec()));
+    assert_eq!(key("PING a"), None);
+    assert_eq!(key("UNKNOWN a"), Some(b"a".to_vec()));
+}
@@ -0,0 +1,466 @@
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+use super::*;
+use crate::{
+    aio::tests::serve,
+    resp,
+    value::{Array, BlobString, Number},
+};
+
+#[test]
+fn test_slot() {
+    assert_eq!(crc16(b"123456789"), 0x31c3);
+
+    assert_eq!(slot(b"foo"), 12182);
+    assert_eq!(slot(b""), 0);
+    assert_eq!(slot(b"{user1000}.following"), slot(b"user1000"));
+    assert_eq!(slot(b"{user1000}.followers"), slot(b"user1000"));
+    // only the first `{...}` counts, and only if it is not empty
+    assert_eq!(slot(b"foo{}{bar}"), crc16(b"foo{}{bar}") % SLOTS);
+    assert_eq!(slot(b"foo{{bar}}zap"), slot(b"{bar"));
+    assert_eq!(slot(b"foo{bar}{zap}"), slot(b"bar"));
+    assert_eq!(slot(b"foo{bar"), crc16(b"foo{bar") % SLOTS);
+}
+
+#[test]
+fn test_slot_map() {
+    let reply = resp!([
+        [0, 5460, ["", 7000, "id0"], ["10.0.0.2", 7003, "id3"]],
+        [5461, 16383, ["10.0.0.1", 7001, "id1"]],
+    ]);
+    let map = SlotMap::from_cluster_slots(&reply, "10.0.0.9").unwrap();
+
+    assert_eq!(map.get(0), Some("10.0.0.9:7000"));
+    assert_eq!(map.get(5460), Some("10.0.0.9:7000"));
+    assert_eq!(map.get(5461), Some("10.0.0.1:7001"));
+    assert_eq!(map.get(SLOTS), None);
+    assert_eq!(
+        map.nodes().collect::<Vec<_>>(),
+        ["10.0.0.9:7000", "10.0.0.1:7001"]
+    );
+
+    let mut map = map;
+    map.set(0..=SLOTS - 1, "10.0.0.1:7001");
+    assert_eq!(map.nodes().collect::<Vec<_>>(), ["10.0.0.1:7001"]);
+
+    assert!(SlotMap::from_cluster_slots(&resp!([[0, 1]]), "").is_err());
+}
+
+#[derive(Debug, Default)]
+struct Cluster {
+    a: String,
+    b: String,
+    phase: Phase,
+    log: Vec<(&'static str, Vec<Bytes>)>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    #[default]
+    Stable,
+    Migrating,
+    Migrated,
+}
+
+const KEY: &str = "{user}:1";
+
+fn redirect(kind: &str, addr: &str) -> Value {
+    resp!(-(format!("{kind} {} {addr}", slot(KEY.as_bytes()))))
+}
+
+// Node `a` owns every slot until the slot of `KEY` moves to node `b`.
+async fn resharding() -> (Arc<Mutex<Cluster>>, String) {
+    let cluster = Arc::new(Mutex::new(Cluster::default()));
+
+    let state = cluster.clone();
+    let a = serve(move || {
+        let state = state.clone();
+        move |args: &[Bytes]| {
+            let mut cluster = state.lock().unwrap();
+            cluster.log.push(("a", args.to_vec()));
+
+            let reply = match (&args[0][..], cluster.phase) {
+                (b"CLUSTER", _) => resp!([[0, 16383, ["", (Number::from(port(&cluster.a))), "a"]]]),
+                (b"GET", Phase::Stable) => resp!("a"),
+                (b"GET", Phase::Migrating) => redirect("ASK", &cluster.b),
+                (b"GET", Phase::Migrated) => redirect("MOVED", &cluster.b),
+                _ => resp!(-"ERR unknown command"),
+            };
+
+            vec![reply]
+        }
+    })
+    .await;
+
+    let state = cluster.clone();
+    let b = serve(move || {
+        let state = state.clone();
+        let mut asking = false;
+        let mut busy = true;
+
+        move |args: &[Bytes]| {
+            let mut cluster = state.lock().unwrap();
+            cluster.log.push(("b", args.to_vec()));
+
+            let reply = match &args[0][..] {
+                b"ASKING" => {
+                    asking = true;
+                    resp!(+"OK")
+                }
+                b"GET" if asking || cluster.phase == Phase::Migrated => resp!("b"),
+                b"GET" => redirect("MOVED", &cluster.a),
+                b"SET" if busy => resp!(-"TRYAGAIN Multiple keys request during rehashing"),
+                b"SET" => resp!(+"OK"),
+                _ => resp!(-"ERR unknown command"),
+            };
+
+            if args[0] != "ASKING" {
+                asking = false;
+            }
+            if args[0] == "SET" {
+                busy = false;
+            }
+
+            vec![reply]
+        }
+    })
+    .await;
+
+    let mut state = cluster.lock().unwrap();
+    state.a = a.clone();
+    state.b = b;
+    drop(state);
+
+    (cluster, a)
+}
+
+fn port(addr: &str) -> i64 {
+    addr.rsplit_once(':').unwrap().1.parse().unwrap()
+}
+
+fn take_log(cluster: &Arc<Mutex<Cluster>>) -> Vec<(&'static str, Vec<u8>)> {
+    cluster
+        .lock()
+        .unwrap()
+        .log
+        .drain(..)
+        .map(|(node, args)| (node, args[0].to_vec()))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_resharding() {
+    let (cluster, a) = resharding().await;
+    let options = ClusterOptions {
+        backoff: Duration::from_millis(1),
+        ..ClusterOptions::default()
+    };
+
+    let mut client = ClusterClient::connect_with([a.clone()], options)
+        .await
+        .unwrap();
+    assert_eq!(client.slots().nodes().collect::<Vec<_>>(), [a.as_str()]);
+
+    let get = Cmd::new("GET").arg(KEY);
+    assert_eq!(client.send(&get).await.unwrap(), resp!("a"));
+    take_log(&cluster);
+
+    // an ASK redirection is followed once, the slot map stays as it is
+    cluster.lock().unwrap().phase = Phase::Migrating;
+    assert_eq!(client.send(&get).await.unwrap(), resp!("b"));
+    assert_eq!(
+        take_log(&cluster),
+        [
+            ("a", b"GET".to_vec()),
+            ("b", b"ASKING".to_vec()),
+            ("b", b"GET".to_vec())
+        ]
+    );
+    assert_eq!(client.slots().get(slot(KEY.as_bytes())), Some(a.as_str()));
+
+    // a MOVED redirection updates the slot map, later commands go straight to the new node
+    cluster.lock().unwrap().phase = Phase::Migrated;
+    assert_eq!(client.send(&get).await.unwrap(), resp!("b"));
+    assert_eq!(client.send(&get).await.unwrap(), resp!("b"));
+    assert_eq!(
+        take_log(&cluster),
+        [
+            ("a", b"GET".to_vec()),
+            ("b", b"GET".to_vec()),
+            ("b", b"GET".to_vec())
+        ]
+    );
+
+    let b = cluster.lock().unwrap().b.clone();
+    assert_eq!(client.slots().get(slot(KEY.as_bytes())), Some(b.as_str()));
+    assert_eq!(client.slots().get(slot(b"other")), Some(a.as_str()));
+
+    // TRYAGAIN is retried after a while
+    let set = Cmd::new("SET").arg(KEY).arg("v");
+    assert_eq!(client.send(&set).await.unwrap(), resp!(+"OK"));
+    assert_eq!(
+        take_log(&cluster),
+        [("b", b"SET".to_vec()), ("b", b"SET".to_vec())]
+    );
+}
+
+// Node `a` owns the lower half of the slots and `b` the upper one; values are the keys.
+async fn halves() -> (Arc<Mutex<Vec<(&'static str, Vec<Bytes>)>>>, String) {
+    let addrs = Arc::new(Mutex::new((String::new(), String::new())));
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let node = |name: &'static str| {
+        let addrs = addrs.clone();
+        let log = log.clone();
+
+        move || {
+            let addrs = addrs.clone();
+            let log = log.clone();
+
+            move |args: &[Bytes]| {
+                let (a, b) = addrs.lock().unwrap().clone();
+                log.lock().unwrap().push((name, args.to_vec()));
+
+                let value = |key: &Bytes| Value::from(BlobString::from(key.to_vec()));
+                let reply = match &args[0][..] {
+                    b"CLUSTER" => resp!([
+                        [0, 8191, ["", (Number::from(port(&a))), "a"]],
+                        [8192, 16383, ["", (Number::from(port(&b))), "b"]],
+                    ]),
+                    b"GET" => value(&args[1]),
+                    b"MGET" => args[1..].iter().map(value).collect::<Array>().into(),
+                    b"DEL" => Value::from(Number::from(args.len() as i64 - 1)),
+                    _ => resp!(-"ERR unknown command"),
+                };
+
+                vec![reply]
+            }
+        }
+    };
+
+    let a = serve(node("a")).await;
+    let b = serve(node("b")).await;
+    *addrs.lock().unwrap() = (a.clone(), b);
+
+    (log, a)
+}
+
+#[tokio::test]
+async fn test_pipeline() {
+    let (log, a) = halves().await;
+    let mut client = ClusterClient::connect(Some(a)).await.unwrap();
+    log.lock().unwrap().clear();
+
+    // first keys served by each node
+    let key = |lower: bool| {
+        (0..)
+            .map(|i| format!("k{i}"))
+            .find(|k| (slot(k.as_bytes()) < 8192) == lower)
+            .unwrap()
+    };
+    let (lo, hi) = (key(true), key(false));
+
+    let replies = client
+        .pipeline(&[
+            Cmd::new("MGET")
+                .arg(hi.clone())
+                .arg(lo.clone())
+                .arg(hi.clone()),
+            Cmd::new("GET").arg(lo.clone()),
+            Cmd::new("DEL")
+                .arg(lo.clone())
+                .arg(hi.clone())
+                .arg(lo.clone()),
+            Cmd::new("GET").arg(hi.clone()),
+        ])
+        .await
+        .unwrap();
+
+    let string = |k: &str| Value::from(BlobString::from(k.as_bytes().to_vec()));
+    assert_eq!(
+        replies,
+        [
+            Value::from(Array::from([string(&hi), string(&lo), string(&hi)])),
+            string(&lo),
+            resp!(3),
+            string(&hi),
+        ]
+    );
+
+    // one sub-pipeline per node, each multi-key command split per slot
+    let mut sent = log
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(node, args)| (*node, args.len()))
+        .collect::<Vec<_>>();
+    sent.sort();
+    assert_eq!(
+        sent,
+        [("a", 2), ("a", 2), ("a", 3), ("b", 2), ("b", 2), ("b", 3)]
+    );
+}
+
+// A node counting `INCR`s that drops the connection once it has applied them.
+async fn flaky() -> (Arc<Mutex<i64>>, String) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let count = Arc::new(Mutex::new(0));
+
+    let (state, port) = (count.clone(), port(&addr));
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let state = state.clone();
+
+            tokio::spawn(async move {
+                let mut buf = vec![];
+                let mut chunk = [0; 4096];
+
+                loop {
+                    let mut applied = false;
+                    while let Some((value, len)) = Value::decode(&buf).unwrap() {
+                        buf.drain(..len);
+
+                        let Value::Array(args) = value else {
+                            return;
+                        };
+                        match args.val().first() {
+                            Some(Value::BlobString(name)) if name.val() == "CLUSTER" => {
+                                let reply =
+                                    resp!([[0, 16383, ["127.0.0.1", (Number::from(port)), "a"]]]);
+                                let bytes = Bytes::try_from(reply).unwrap();
+                                stream.write_all(&bytes).await.unwrap();
+                            }
+                            _ => {
+                                *state.lock().unwrap() += 1;
+                                applied = true;
+                            }
+                        }
+                    }
+
+                    if applied {
+                        return;
+                    }
+                    match stream.read(&mut chunk).await {
+                        Ok(0) | Err(_) => return,
+                        Ok(len) => buf.extend_from_slice(&chunk[..len]),
+                    }
+                }
+            });
+        }
+    });
+
+    (count, addr)
+}
+
+#[tokio::test]
+async fn test_lost_batch() {
+    let (count, addr) = flaky().await;
+    let options = ClusterOptions {
+        backoff: Duration::from_millis(1),
+        ..ClusterOptions::default()
+    };
+    let mut client = ClusterClient::connect_with([addr], options).await.unwrap();
+
+    let incr = Cmd::new("INCR").arg("counter");
+    let err = client.pipeline(&[incr.clone(), incr]).await.unwrap_err();
+    assert!(err.to_string().contains("2 commands may have run"), "{err}");
+
+    // nothing was sent again
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(*count.lock().unwrap(), 2);
+
+    // neither is a batch with a write in it
+    let (get, incr) = (
+        Cmd::new("GET").arg("counter"),
+        Cmd::new("INCR").arg("counter"),
+    );
+    let err = client.pipeline(&[get, incr]).await.unwrap_err();
+    assert!(err.to_string().contains("2 commands may have run"), "{err}");
+}
+
+#[tokio::test]
+async fn test_lost_read_batch() {
+    let (count, addr) = flaky().await;
+    let options = ClusterOptions {
+        retries: 2,
+        backoff: Duration::from_millis(1),
+        ..ClusterOptions::default()
+    };
+    let mut client = ClusterClient::connect_with([addr], options).await.unwrap();
+
+    // reads are sent again one by one, the first one up to the number of retries
+    let get = Cmd::new("GET").arg("counter");
+    let err = client.pipeline(&[get.clone(), get]).await.unwrap_err();
+    assert!(!err.to_string().contains("may have run"), "{err}");
+    assert_eq!(*count.lock().unwrap(), 2 + 3);
+}
+
+#[tokio::test]
+async fn test_lost_command() {
+    let (count, addr) = flaky().await;
+    let options = ClusterOptions {
+        retries: 2,
+        backoff: Duration::from_millis(1),
+        ..ClusterOptions::default()
+    };
+    let mut client = ClusterClient::connect_with([addr], options).await.unwrap();
+
+    // a write is not sent again once it was written
+    let incr = Cmd::new("INCR").arg("counter");
+    assert!(client.execute(&incr, None).await.is_err());
+    assert_eq!(*count.lock().unwrap(), 1);
+
+    // a read is, up to the number of retries
+    let get = Cmd::new("GET").arg("counter");
+    assert!(client.execute(&get, None).await.is_err());
+    assert_eq!(*count.lock().unwrap(), 4);
+}
+
+#[tokio::test]
+async fn test_redirect_limit() {
+    let addr = Arc::new(Mutex::new(String::new()));
+
+    let state = addr.clone();
+    let node = serve(move || {
+        let state = state.clone();
+        move |args: &[Bytes]| {
+            let addr = state.lock().unwrap().clone();
+
+            match &args[0][..] {
+                b"CLUSTER" => vec![resp!([[
+                    0,
+                    16383,
+                    ["127.0.0.1", (Number::from(port(&addr))), "a"]
+                ]])],
+                _ => vec![resp!(-(format!("MOVED 0 :{}", port(&addr))))],
+            }
+        }
+    })
+    .await;
+    *addr.lock().unwrap() = node.clone();
+
+    let options = ClusterOptions {
+        max_redirects: 2,
+        ..ClusterOptions::default()
+    };
+    let mut client = ClusterClient::connect_with([node], options).await.unwrap();
+
+    let reply = client.send(&Cmd::new("GET").arg("key")).await.unwrap();
+    assert!(matches!(
+        reply.into_result().unwrap_err().kind(),
+        ErrorKind::Moved { slot: 0, .. }
+    ));
+}
+
+#[tokio::test]
+async fn test_no_seeds() {
+    let seeds: [&str; 0] = [];
+    assert!(ClusterClient::connect(seeds).await.is_err());
+}
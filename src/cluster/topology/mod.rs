@@ -0,0 +1,499 @@
+#[cfg(test)]
+pub(crate) mod tests;
+
+use std::{collections::BTreeMap, ops::RangeInclusive, str};
+
+use anyhow::{anyhow, bail, Context};
+
+use super::SLOTS;
+use crate::value::Value;
+
+/// The layout of a cluster: which shard serves which slots and which nodes make up each shard.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Topology {
+    pub shards: Vec<Shard>,
+}
+
+/// A primary, its replicas and the slots they serve.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Shard {
+    pub slots: Vec<RangeInclusive<u16>>,
+    pub nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    pub id: String,
+    /// Empty if unknown, in which case the node is reachable on the address it was asked on.
+    pub ip: String,
+    pub hostname: Option<String>,
+    pub port: Option<u16>,
+    pub tls_port: Option<u16>,
+    pub role: Role,
+    pub health: Health,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Role {
+    Primary,
+    Replica,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Health {
+    Online,
+    Loading,
+    Failed,
+}
+
+/// A difference between two topologies, see [`Topology::diff`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    NodeAdded(Node),
+    NodeRemoved(Node),
+    RoleChanged {
+        id: String,
+        from: Role,
+        to: Role,
+    },
+    HealthChanged {
+        id: String,
+        from: Health,
+        to: Health,
+    },
+    /// The slots changed hands, `None` being no primary at all.
+    SlotsMoved {
+        slots: RangeInclusive<u16>,
+        from: Option<String>,
+        to: Option<String>,
+    },
+}
+
+impl Shard {
+    pub fn primary(&self) -> Option<&Node> {
+        self.nodes.iter().find(|node| node.role == Role::Primary)
+    }
+
+    pub fn replicas(&self) -> impl Iterator<Item = &Node> {
+        self.nodes.iter().filter(|node| node.role == Role::Replica)
+    }
+}
+
+impl Node {
+    /// `ip:port`, or `hostname:port` if the ip is unknown.
+    /// `None` without a plain text port or any known address.
+    pub fn addr(&self) -> Option<String> {
+        let host = match (self.ip.as_str(), self.hostname.as_deref()) {
+            ("", hostname) => hostname?,
+            (ip, _) => ip,
+        };
+
+        self.port.map(|port| format!("{host}:{port}"))
+    }
+}
+
+impl Topology {
+    pub fn nodes(&self) -> impl Iterator<Item = &Node> {
+        self.shards.iter().flat_map(|shard| shard.nodes.iter())
+    }
+
+    pub fn node(&self, id: &str) -> Option<&Node> {
+        self.nodes().find(|node| node.id == id)
+    }
+
+    /// The shard serving `slot`, if any.
+    pub fn shard(&self, slot: u16) -> Option<&Shard> {
+        self.shards
+            .iter()
+            .find(|shard| shard.slots.iter().any(|slots| slots.contains(&slot)))
+    }
+
+    /// Lists what changed from `self` to `other`: nodes (by id) coming and going,
+    /// changing role or health, and slots changing primary.
+    pub fn diff(&self, other: &Topology) -> Vec<Change> {
+        let mut changes = vec![];
+
+        let before = self
+            .nodes()
+            .map(|node| (&node.id, node))
+            .collect::<BTreeMap<_, _>>();
+        let after = other
+            .nodes()
+            .map(|node| (&node.id, node))
+            .collect::<BTreeMap<_, _>>();
+
+        for (id, node) in before.iter() {
+            let Some(new) = after.get(id) else {
+                changes.push(Change::NodeRemoved((*node).clone()));
+                continue;
+            };
+
+            if node.role != new.role {
+                changes.push(Change::RoleChanged {
+                    id: id.to_string(),
+                    from: node.role,
+                    to: new.role,
+                });
+            }
+
+            if node.health != new.health {
+                changes.push(Change::HealthChanged {
+                    id: id.to_string(),
+                    from: node.health,
+                    to: new.health,
+                });
+            }
+        }
+
+        for (id, node) in after.iter() {
+            if !before.contains_key(id) {
+                changes.push(Change::NodeAdded((*node).clone()));
+            }
+        }
+
+        let (before, after) = (self.owners(), other.owners());
+        let mut start = 0;
+
+        for slot in 0..=SLOTS {
+            let slot = usize::from(slot);
+            let same_run = slot < usize::from(SLOTS)
+                && before[slot] == before[start]
+                && after[slot] == after[start];
+
+            if same_run {
+                continue;
+            }
+
+            if before[start] != after[start] {
+                changes.push(Change::SlotsMoved {
+                    slots: start as u16..=(slot - 1) as u16,
+                    from: before[start].map(String::from),
+                    to: after[start].map(String::from),
+                });
+            }
+
+            start = slot;
+        }
+
+        changes
+    }
+
+    // The id of the primary serving every slot.
+    fn owners(&self) -> Vec<Option<&str>> {
+        let mut owners = vec![None; SLOTS.into()];
+
+        for shard in self.shards.iter() {
+            let Some(primary) = shard.primary() else {
+                continue;
+            };
+
+            for slots in shard.slots.iter() {
+                for slot in slots.clone().filter(|slot| *slot < SLOTS) {
+                    owners[usize::from(slot)] = Some(primary.id.as_str());
+                }
+            }
+        }
+
+        owners
+    }
+}
+
+impl Topology {
+    /// Decodes a `CLUSTER SHARDS` reply, RESP3 maps and RESP2 flat arrays alike.
+    pub fn from_cluster_shards(reply: &Value) -> anyhow::Result<Self> {
+        let Value::Array(shards) = reply else {
+            bail!("Topology (unexpected CLUSTER SHARDS reply: {reply})");
+        };
+
+        let shards = shards
+            .into_iter()
+            .map(|shard| {
+                let shard = fields(shard)?;
+
+                let slots = match field(&shard, "slots")? {
+                    Value::Array(slots) => slots
+                        .val()
+                        .chunks(2)
+                        .map(|range| match range {
+                            [start, end] => Ok(slot(start)?..=slot(end)?),
+                            _ => bail!("Topology (odd number of slot bounds)"),
+                        })
+                        .collect::<anyhow::Result<Vec<_>>>()?,
+                    slots => bail!("Topology (unexpected slots: {slots})"),
+                };
+
+                let nodes = match field(&shard, "nodes")? {
+                    Value::Array(nodes) => nodes
+                        .into_iter()
+                        .map(shard_node)
+                        .collect::<anyhow::Result<Vec<_>>>()?,
+                    nodes => bail!("Topology (unexpected nodes: {nodes})"),
+                };
+
+                Ok(Shard { slots, nodes })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()
+            .context("Topology::from_cluster_shards")?;
+
+        Ok(Self { shards })
+    }
+
+    /// Decodes a `CLUSTER SLOTS` reply. Ranges served by the same primary are grouped in one shard.
+    /// Failed nodes are not listed by the server, so every node is [`Health::Online`].
+    pub fn from_cluster_slots(reply: &Value) -> anyhow::Result<Self> {
+        let Value::Array(ranges) = reply else {
+            bail!("Topology (unexpected CLUSTER SLOTS reply: {reply})");
+        };
+
+        let mut topology = Self::default();
+
+        for range in ranges {
+            let (start, end, nodes) = match range {
+                Value::Array(range) => match range.val() {
+                    [start, end, nodes @ ..] if !nodes.is_empty() => {
+                        (slot(start)?, slot(end)?, nodes)
+                    }
+                    _ => bail!("Topology::from_cluster_slots (unexpected slot range: {reply})"),
+                },
+                range => bail!("Topology::from_cluster_slots (unexpected slot range: {range})"),
+            };
+
+            let nodes = nodes
+                .iter()
+                .enumerate()
+                .map(|(i, node)| {
+                    let role = match i {
+                        0 => Role::Primary,
+                        _ => Role::Replica,
+                    };
+
+                    slots_node(node, role)
+                })
+                .collect::<anyhow::Result<Vec<_>>>()
+                .context("Topology::from_cluster_slots")?;
+
+            match topology
+                .shards
+                .iter_mut()
+                .find(|shard| shard.primary().map(|node| &node.id) == Some(&nodes[0].id))
+            {
+                Some(shard) => shard.slots.push(start..=end),
+                None => topology.shards.push(Shard {
+                    slots: vec![start..=end],
+                    nodes,
+                }),
+            }
+        }
+
+        Ok(topology)
+    }
+
+    /// Decodes the text of a `CLUSTER NODES` reply. Slots being migrated or imported
+    /// (`[slot->-id]`) are left out, they still belong to their current primary.
+    pub fn from_cluster_nodes(text: &[u8]) -> anyhow::Result<Self> {
+        let text = str::from_utf8(text).context("Topology::from_cluster_nodes (str::from_utf8)")?;
+
+        let mut shards = BTreeMap::<String, Shard>::new();
+        let mut replicas = vec![];
+
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let parts = line.split_whitespace().collect::<Vec<_>>();
+            let [id, addr, flags, primary, _ping, _pong, _epoch, _link, slots @ ..] = &parts[..]
+            else {
+                bail!("Topology::from_cluster_nodes (unexpected line {line:?})");
+            };
+
+            let flags = flags.split(',').collect::<Vec<_>>();
+            let (ip, port, hostname) = nodes_addr(addr)
+                .with_context(|| format!("Topology::from_cluster_nodes (line {line:?})"))?;
+
+            let node = Node {
+                id: id.to_string(),
+                ip,
+                hostname,
+                port,
+                tls_port: None,
+                role: match flags.contains(&"master") {
+                    true => Role::Primary,
+                    false => Role::Replica,
+                },
+                health: match flags.contains(&"fail") {
+                    true => Health::Failed,
+                    false => Health::Online,
+                },
+            };
+
+            match node.role {
+                Role::Primary => {
+                    let slots = slots
+                        .iter()
+                        .filter(|slots| !slots.starts_with('['))
+                        .map(|slots| nodes_slots(slots))
+                        .collect::<anyhow::Result<Vec<_>>>()
+                        .with_context(|| format!("Topology::from_cluster_nodes (line {line:?})"))?;
+
+                    let shard = shards.entry(node.id.clone()).or_default();
+                    shard.slots = slots;
+                    shard.nodes.insert(0, node);
+                }
+                Role::Replica => replicas.push((primary.to_string(), node)),
+            }
+        }
+
+        for (primary, node) in replicas {
+            shards.entry(primary).or_default().nodes.push(node);
+        }
+
+        Ok(Self {
+            shards: shards.into_values().collect(),
+        })
+    }
+}
+
+// RESP3 maps and RESP2 arrays of alternating names and values.
+fn fields(value: &Value) -> anyhow::Result<Vec<(&Value, &Value)>> {
+    match value {
+        Value::Map(map) => Ok(map.iter().collect()),
+        Value::Array(array) if array.len() % 2 == 0 => Ok(array
+            .val()
+            .chunks(2)
+            .map(|pair| (&pair[0], &pair[1]))
+            .collect()),
+        value => bail!("Topology (expected map: {value})"),
+    }
+}
+
+fn field<'a>(fields: &[(&Value, &'a Value)], name: &str) -> anyhow::Result<&'a Value> {
+    find(fields, name).ok_or_else(|| anyhow!("Topology (missing field {name:?})"))
+}
+
+fn find<'a>(fields: &[(&Value, &'a Value)], name: &str) -> Option<&'a Value> {
+    fields
+        .iter()
+        .find(|(k, _)| text(k).is_ok_and(|k| k == name))
+        .map(|(_, v)| *v)
+}
+
+fn text(value: &Value) -> anyhow::Result<&str> {
+    let bytes = match value {
+        Value::BlobString(inner) => &inner.val()[..],
+        Value::SimpleString(inner) => &inner.val()[..],
+        Value::VerbatimString(inner) => &inner.val()[..],
+        value => bail!("Topology (expected string: {value})"),
+    };
+
+    str::from_utf8(bytes).context("Topology (str::from_utf8)")
+}
+
+fn number(value: &Value) -> anyhow::Result<i64> {
+    match value {
+        Value::Number(inner) => Ok(inner.val()),
+        value => text(value)?.parse().context("Topology (expected number)"),
+    }
+}
+
+fn slot(value: &Value) -> anyhow::Result<u16> {
+    u16::try_from(number(value)?).context("Topology (invalid slot)")
+}
+
+fn port(value: &Value) -> anyhow::Result<Option<u16>> {
+    match u16::try_from(number(value)?).context("Topology (invalid port)")? {
+        0 => Ok(None),
+        port => Ok(Some(port)),
+    }
+}
+
+fn shard_node(value: &Value) -> anyhow::Result<Node> {
+    let node = fields(value)?;
+
+    let string = |name| find(&node, name).map(text).transpose();
+    let port_field = |name| find(&node, name).map(port).transpose().map(Option::flatten);
+
+    Ok(Node {
+        id: text(field(&node, "id")?)?.to_string(),
+        ip: string("ip")?
+            .or(string("endpoint")?)
+            .unwrap_or_default()
+            .to_string(),
+        hostname: string("hostname")?
+            .filter(|hostname| !hostname.is_empty())
+            .map(String::from),
+        port: port_field("port")?,
+        tls_port: port_field("tls-port")?,
+        role: match text(field(&node, "role")?)? {
+            "master" | "primary" => Role::Primary,
+            _ => Role::Replica,
+        },
+        health: match string("health")? {
+            Some("failed") => Health::Failed,
+            Some("loading") => Health::Loading,
+            _ => Health::Online,
+        },
+    })
+}
+
+// `[ip, port, id, {hostname: ...}]`, the id and metadata being absent on old servers.
+fn slots_node(value: &Value, role: Role) -> anyhow::Result<Node> {
+    let Value::Array(node) = value else {
+        bail!("Topology (unexpected node: {value})");
+    };
+
+    let (ip, port, rest) = match node.val() {
+        [ip, port, rest @ ..] => (text(ip)?, self::port(port)?, rest),
+        _ => bail!("Topology (unexpected node: {value})"),
+    };
+
+    let id = match rest.first() {
+        Some(id) => text(id)?.to_string(),
+        None => format!("{ip}:{}", port.unwrap_or_default()),
+    };
+
+    let hostname = match rest.get(1) {
+        Some(metadata) => find(&fields(metadata)?, "hostname")
+            .map(text)
+            .transpose()?
+            .map(String::from),
+        None => None,
+    };
+
+    Ok(Node {
+        id,
+        ip: match ip {
+            "?" => String::new(),
+            ip => ip.to_string(),
+        },
+        hostname,
+        port,
+        tls_port: None,
+        role,
+        health: Health::Online,
+    })
+}
+
+// `ip:port@cport[,hostname]`, `:0@0` when the address is unknown.
+fn nodes_addr(addr: &str) -> anyhow::Result<(String, Option<u16>, Option<String>)> {
+    let (addr, hostname) = match addr.split_once(',') {
+        Some((addr, hostname)) => (addr, Some(hostname).filter(|h| !h.is_empty())),
+        None => (addr, None),
+    };
+
+    let addr = addr.split_once('@').map_or(addr, |(addr, _)| addr);
+    let (ip, port) = addr
+        .rsplit_once(':')
+        .ok_or_else(|| anyhow!("Topology (invalid address {addr:?})"))?;
+    let port = port.parse::<u16>().context("Topology (invalid port)")?;
+
+    Ok((
+        ip.to_string(),
+        Some(port).filter(|port| *port != 0),
+        hostname.map(String::from),
+    ))
+}
+
+fn nodes_slots(slots: &str) -> anyhow::Result<RangeInclusive<u16>> {
+    let (start, end) = slots.split_once('-').unwrap_or((slots, slots));
+    let start = start.parse().context("Topology (invalid slot)")?;
+    let end = end.parse().context("Topology (invalid slot)")?;
+
+    Ok(start..=end)
+}
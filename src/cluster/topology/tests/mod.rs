@@ -0,0 +1,230 @@
+use super::*;
+use crate::{resp, value::Number};
+
+fn node(id: &str, port: u16, role: Role) -> Node {
+    Node {
+        id: id.to_string(),
+        ip: "127.0.0.1".to_string(),
+        hostname: None,
+        port: Some(port),
+        tls_port: None,
+        role,
+        health: Health::Online,
+    }
+}
+
+fn two_shards() -> Topology {
+    Topology {
+        shards: vec![
+            Shard {
+                slots: vec![0..=8191],
+                nodes: vec![
+                    node("a", 30001, Role::Primary),
+                    node("c", 30003, Role::Replica),
+                ],
+            },
+            Shard {
+                slots: vec![8192..=16383],
+                nodes: vec![node("b", 30002, Role::Primary)],
+            },
+        ],
+    }
+}
+
+#[test]
+fn test_cluster_shards() {
+    let reply = resp!([
+        {
+            "slots" => [0, 8191],
+            "nodes" => [
+                {
+                    "id" => "a", "port" => 30001, "ip" => "127.0.0.1", "endpoint" => "127.0.0.1",
+                    "hostname" => "", "role" => "master", "replication-offset" => 72156,
+                    "health" => "online",
+                },
+                {
+                    "id" => "c", "port" => 30003, "ip" => "127.0.0.1", "endpoint" => "127.0.0.1",
+                    "role" => "replica", "replication-offset" => 72156, "health" => "online",
+                },
+            ],
+        },
+        {
+            "slots" => [8192, 16383],
+            "nodes" => [
+                {
+                    "id" => "b", "port" => 30002, "ip" => "127.0.0.1", "role" => "master",
+                    "health" => "online",
+                },
+            ],
+        },
+    ]);
+
+    assert_eq!(Topology::from_cluster_shards(&reply).unwrap(), two_shards());
+}
+
+#[test]
+fn test_cluster_shards_resp2() {
+    let reply = resp!([[
+        "slots",
+        [],
+        "nodes",
+        [[
+            "id",
+            "d",
+            "port",
+            0,
+            "tls-port",
+            30004,
+            "ip",
+            "10.0.0.4",
+            "hostname",
+            "node-d.local",
+            "role",
+            "master",
+            "health",
+            "loading",
+        ]],
+    ]]);
+
+    let topology = Topology::from_cluster_shards(&reply).unwrap();
+    let node = topology.node("d").unwrap();
+
+    assert_eq!(node.port, None);
+    assert_eq!(node.tls_port, Some(30004));
+    assert_eq!(node.hostname.as_deref(), Some("node-d.local"));
+    assert_eq!(node.health, Health::Loading);
+    assert_eq!(node.addr(), None);
+    assert!(topology.shards[0].slots.is_empty());
+
+    assert!(Topology::from_cluster_shards(&resp!([["slots", [1]]])).is_err());
+    assert!(Topology::from_cluster_shards(&resp!(+"OK")).is_err());
+}
+
+#[test]
+fn test_cluster_slots() {
+    let reply = resp!([
+        [0, 4095, ["127.0.0.1", 30001, "a"], ["127.0.0.1", 30003, "c"]],
+        [4096, 8191, ["127.0.0.1", 30001, "a"], ["127.0.0.1", 30003, "c"]],
+        [8192, 16383, ["127.0.0.1", 30002, "b", {"hostname" => "node-b"}]],
+    ]);
+
+    let mut expected = two_shards();
+    expected.shards[0].slots = vec![0..=4095, 4096..=8191];
+    expected.shards[1].nodes[0].hostname = Some("node-b".to_string());
+
+    let topology = Topology::from_cluster_slots(&reply).unwrap();
+    assert_eq!(topology, expected);
+    assert_eq!(topology.shard(5000).unwrap().primary().unwrap().id, "a");
+    assert_eq!(
+        topology
+            .shard(0)
+            .unwrap()
+            .replicas()
+            .map(|n| &n.id[..])
+            .collect::<Vec<_>>(),
+        ["c"]
+    );
+
+    // servers without node ids
+    let reply = resp!([[0, 16383, ["?", 30001]]]);
+    let topology = Topology::from_cluster_slots(&reply).unwrap();
+    assert_eq!(topology.shards[0].nodes[0].id, "?:30001");
+    assert_eq!(topology.shards[0].nodes[0].ip, "");
+
+    assert!(Topology::from_cluster_slots(&resp!([[0, 16383]])).is_err());
+    assert!(Topology::from_cluster_slots(&resp!([[0, 100000, ["", (Number::from(1))]]])).is_err());
+}
+
+#[test]
+fn test_cluster_nodes() {
+    let text = b"\
+        c 127.0.0.1:30003@31003 slave a 0 1426238317239 4 connected\n\
+        a 127.0.0.1:30001@31001 myself,master - 0 0 1 connected 0-8191\n\
+        b 127.0.0.1:30002@31002,node-b master - 0 1426238316232 2 connected 8192-16382 16383 [100->-c]\n\
+        d :0@0 master,fail,noaddr - 1426238316232 1426238316232 3 disconnected\n";
+
+    let topology = Topology::from_cluster_nodes(text).unwrap();
+
+    let mut expected = two_shards();
+    expected.shards[1].slots = vec![8192..=16382, 16383..=16383];
+    expected.shards[1].nodes[0].hostname = Some("node-b".to_string());
+    expected.shards.push(Shard {
+        slots: vec![],
+        nodes: vec![Node {
+            ip: String::new(),
+            port: None,
+            health: Health::Failed,
+            ..node("d", 0, Role::Primary)
+        }],
+    });
+
+    assert_eq!(topology, expected);
+    assert!(Topology::from_cluster_nodes(b"a 127.0.0.1:30001 master").is_err());
+    assert!(Topology::from_cluster_nodes(b"a 127.0.0.1 master - 0 0 1 connected").is_err());
+}
+
+#[test]
+fn test_diff() {
+    let before = two_shards();
+    assert!(before.diff(&before).is_empty());
+
+    // slots 8000-8191 move to b, c is promoted and takes the rest of a's slots, a fails
+    let mut after = two_shards();
+    after.shards[0].slots = vec![0..=7999];
+    after.shards[1].slots = vec![8000..=16383];
+    after.shards[0].nodes[0].role = Role::Replica;
+    after.shards[0].nodes[0].health = Health::Failed;
+    after.shards[0].nodes[1].role = Role::Primary;
+    after.shards[1].nodes.push(node("e", 30005, Role::Replica));
+
+    assert_eq!(
+        before.diff(&after),
+        vec![
+            Change::RoleChanged {
+                id: "a".to_string(),
+                from: Role::Primary,
+                to: Role::Replica
+            },
+            Change::HealthChanged {
+                id: "a".to_string(),
+                from: Health::Online,
+                to: Health::Failed
+            },
+            Change::RoleChanged {
+                id: "c".to_string(),
+                from: Role::Replica,
+                to: Role::Primary
+            },
+            Change::NodeAdded(node("e", 30005, Role::Replica)),
+            Change::SlotsMoved {
+                slots: 0..=7999,
+                from: Some("a".to_string()),
+                to: Some("c".to_string())
+            },
+            Change::SlotsMoved {
+                slots: 8000..=8191,
+                from: Some("a".to_string()),
+                to: Some("b".to_string())
+            },
+        ]
+    );
+
+    assert_eq!(
+        before.diff(&Topology::default()),
+        vec![
+            Change::NodeRemoved(node("a", 30001, Role::Primary)),
+            Change::NodeRemoved(node("b", 30002, Role::Primary)),
+            Change::NodeRemoved(node("c", 30003, Role::Replica)),
+            Change::SlotsMoved {
+                slots: 0..=8191,
+                from: Some("a".to_string()),
+                to: None
+            },
+            Change::SlotsMoved {
+                slots: 8192..=16383,
+                from: Some("b".to_string()),
+                to: None
+            },
+        ]
+    );
+}
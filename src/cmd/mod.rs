@@ -0,0 +1,181 @@
+#[cfg(test)]
+pub(crate) mod tests;
+
+mod table;
+
+pub use table::{BeginSearch, CommandInfo, CommandTable, FindKeys, KeySpec};
+
+use std::io::Write;
+
+use anyhow::{bail, Context};
+use bytes::Bytes;
+
+use crate::value::{Array, BlobString, Value};
+
+static DELIMITER: &[u8] = b"\r\n";
+
+/// A command as sent by clients: an array of blob strings, name first.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Cmd {
+    args: Vec<Bytes>,
+}
+
+impl Cmd {
+    pub fn new(name: impl Into<Bytes>) -> Self {
+        Self {
+            args: vec![name.into()],
+        }
+    }
+
+    pub fn arg(mut self, arg: impl Into<Bytes>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn name(&self) -> &[u8] {
+        self.args.first().map(|name| &name[..]).unwrap_or_default()
+    }
+
+    pub fn args(&self) -> &[Bytes] {
+        &self.args
+    }
+}
+
+impl Cmd {
+    /// Splits a line the way redis-cli does: arguments are separated by whitespace
+    /// and may be "double quoted" (with escapes) or 'single quoted'.
+    pub fn from_line(line: &str) -> anyhow::Result<Self> {
+        let mut args = vec![];
+        let mut chars = line.chars().peekable();
+
+        loop {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+            let Some(first) = chars.next() else { break };
+            let mut arg = vec![];
+
+            match first {
+                '"' => loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => arg.push(b'\n'),
+                            Some('r') => arg.push(b'\r'),
+                            Some('t') => arg.push(b'\t'),
+                            Some('b') => arg.push(0x08),
+                            Some('a') => arg.push(0x07),
+                            Some('x') => {
+                                let hex = chars.next().zip(chars.next()).and_then(|(hi, lo)| {
+                                    Some(hi.to_digit(16)? as u8 * 16 + lo.to_digit(16)? as u8)
+                                });
+
+                                match hex {
+                                    Some(byte) => arg.push(byte),
+                                    None => bail!("Cmd::from_line (invalid \\x escape)"),
+                                }
+                            }
+                            Some(c) => push_char(&mut arg, c),
+                            None => bail!("Cmd::from_line (unbalanced quotes)"),
+                        },
+                        Some(c) => push_char(&mut arg, c),
+                        None => bail!("Cmd::from_line (unbalanced quotes)"),
+                    }
+                },
+                '\'' => loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some('\\') if chars.peek() == Some(&'\'') => {
+                            chars.next();
+                            arg.push(b'\'');
+                        }
+                        Some(c) => push_char(&mut arg, c),
+                        None => bail!("Cmd::from_line (unbalanced quotes)"),
+                    }
+                },
+                c => {
+                    push_char(&mut arg, c);
+
+                    while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                        push_char(&mut arg, c);
+                    }
+                }
+            }
+
+            // a closing quote must be followed by whitespace
+            if matches!(first, '"' | '\'') && chars.peek().is_some_and(|c| !c.is_whitespace()) {
+                bail!("Cmd::from_line (closing quote must be followed by a space)");
+            }
+
+            args.push(Bytes::from(arg));
+        }
+
+        if args.is_empty() {
+            bail!("Cmd::from_line (empty command)");
+        }
+
+        Ok(Self { args })
+    }
+}
+
+fn push_char(buf: &mut Vec<u8>, c: char) {
+    buf.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+}
+
+impl<A: Into<Bytes>> FromIterator<A> for Cmd {
+    fn from_iter<I: IntoIterator<Item = A>>(input: I) -> Self {
+        Self {
+            args: input.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<&Cmd> for Value {
+    fn from(input: &Cmd) -> Value {
+        Value::from(
+            input
+                .args
+                .iter()
+                .map(|arg| Value::from(BlobString::from(arg.clone())))
+                .collect::<Array>(),
+        )
+    }
+}
+
+impl From<Cmd> for Value {
+    fn from(input: Cmd) -> Value {
+        Value::from(&input)
+    }
+}
+
+impl TryFrom<&Cmd> for Bytes {
+    type Error = anyhow::Error;
+
+    fn try_from(input: &Cmd) -> anyhow::Result<Bytes> {
+        let mut buf = vec![];
+
+        buf.write(b"*")
+            .and_then(|_| buf.write(input.args.len().to_string().as_bytes()))
+            .and_then(|_| buf.write(DELIMITER))
+            .context("Cmd (buf::write)")?;
+
+        for arg in input.args.iter() {
+            buf.write(b"$")
+                .and_then(|_| buf.write(arg.len().to_string().as_bytes()))
+                .and_then(|_| buf.write(DELIMITER))
+                .and_then(|_| buf.write(arg))
+                .and_then(|_| buf.write(DELIMITER))
+                .context("Cmd (buf::write)")?;
+        }
+
+        buf.flush().context("Cmd (buf::flush)")?;
+        Ok(Bytes::from(buf))
+    }
+}
+
+impl TryFrom<Cmd> for Bytes {
+    type Error = anyhow::Error;
+
+    fn try_from(input: Cmd) -> anyhow::Result<Bytes> {
+        Bytes::try_from(&input)
+    }
+}
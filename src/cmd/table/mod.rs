@@ -0,0 +1,477 @@
+//! What the server knows about its commands: arity, flags, ACL categories and where the keys are.
+
+#[cfg(test)]
+pub(crate) mod tests;
+
+use std::{collections::HashMap, str};
+
+use anyhow::{anyhow, bail, Context};
+use once_cell::sync::Lazy;
+
+use super::Cmd;
+use crate::value::Value;
+
+// A `COMMAND INFO` reply (RESP3) listing the data, scripting, transaction, connection
+// and cluster commands of Redis 7.2.
+static SNAPSHOT: &[u8] = include_bytes!("commands.resp");
+
+static BUNDLED: Lazy<CommandTable> = Lazy::new(|| {
+    let (reply, _) = Value::decode(SNAPSHOT)
+        .ok()
+        .flatten()
+        .expect("the snapshot is a valid RESP3 value");
+
+    CommandTable::from_command_info(&reply).expect("the snapshot is a valid COMMAND INFO reply")
+});
+
+/// Commands by name, as described by `COMMAND INFO` (or `COMMAND`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandTable {
+    commands: HashMap<String, CommandInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandInfo {
+    /// Lowercase, `container|subcommand` for subcommands.
+    pub name: String,
+    /// Negative when the command takes at least `-arity` arguments, the name included.
+    pub arity: i64,
+    pub flags: Vec<String>,
+    /// The legacy key range, used when there are no key specs.
+    pub first_key: i64,
+    pub last_key: i64,
+    pub step: i64,
+    /// Without the leading `@`.
+    pub acl_categories: Vec<String>,
+    pub key_specs: Vec<KeySpec>,
+    pub subcommands: Vec<CommandInfo>,
+}
+
+/// Where to find a set of keys in the arguments, in two steps: where to begin
+/// and how to go on from there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeySpec {
+    pub begin_search: BeginSearch,
+    pub find_keys: FindKeys,
+    /// E.g. `RO`, `RW`, `ACCESS` or `NOT_KEY` (sharded channels).
+    pub flags: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BeginSearch {
+    /// The first key is at this argument.
+    Index(usize),
+    /// The first key follows `keyword`, looked up from `start_from`
+    /// (counting from the end and going backwards if negative).
+    Keyword {
+        keyword: String,
+        start_from: i64,
+    },
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FindKeys {
+    /// Keys every `key_step` arguments, up to `last_key` past the first key
+    /// (or counting from the end if negative). A `limit` other than 0 with `last_key` -1
+    /// stops at that fraction of the remaining arguments.
+    Range {
+        last_key: i64,
+        key_step: usize,
+        limit: usize,
+    },
+    /// The number of keys is at `key_num_index` past the first key,
+    /// the keys themselves start at `first_key` past it.
+    KeyNum {
+        key_num_index: usize,
+        first_key: usize,
+        key_step: usize,
+    },
+    Unknown,
+}
+
+impl CommandInfo {
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|f| f.eq_ignore_ascii_case(flag))
+    }
+
+    pub fn is_readonly(&self) -> bool {
+        self.has_flag("readonly")
+    }
+
+    pub fn is_write(&self) -> bool {
+        self.has_flag("write")
+    }
+
+    pub fn is_blocking(&self) -> bool {
+        self.has_flag("blocking")
+    }
+
+    pub fn is_pubsub(&self) -> bool {
+        self.has_flag("pubsub")
+    }
+
+    pub fn is_noscript(&self) -> bool {
+        self.has_flag("noscript")
+    }
+
+    /// Whether `args` (the name included) is a valid number of arguments.
+    pub fn accepts(&self, args: usize) -> bool {
+        let arity = self.arity.unsigned_abs() as usize;
+
+        match self.arity < 0 {
+            true => args >= arity,
+            false => args == arity,
+        }
+    }
+
+    /// Positions of the keys in `args`, the name being at 0.
+    pub fn key_positions(&self, args: &[impl AsRef<[u8]>]) -> Vec<usize> {
+        if self.key_specs.is_empty() {
+            return legacy_positions(self.first_key, self.last_key, self.step, args.len());
+        }
+
+        self.key_specs
+            .iter()
+            .flat_map(|spec| spec.positions(args))
+            .collect()
+    }
+}
+
+impl KeySpec {
+    /// Like the server with partial results allowed: unknown or inconsistent specs
+    /// (e.g. a keyword that is not there) find nothing.
+    fn positions(&self, args: &[impl AsRef<[u8]>]) -> Vec<usize> {
+        let argc = args.len() as i64;
+
+        let first = match &self.begin_search {
+            BeginSearch::Index(index) => *index as i64,
+            BeginSearch::Keyword {
+                keyword,
+                start_from,
+            } => {
+                let found = match *start_from > 0 {
+                    true => (*start_from..argc).find(|i| is_keyword(args, *i, keyword)),
+                    false => (1..=argc + start_from)
+                        .rev()
+                        .find(|i| is_keyword(args, *i, keyword)),
+                };
+
+                match found {
+                    Some(i) => i + 1,
+                    None => return vec![],
+                }
+            }
+            BeginSearch::Unknown => return vec![],
+        };
+
+        let (first, last, step) = match &self.find_keys {
+            FindKeys::Range {
+                last_key,
+                key_step,
+                limit,
+            } => {
+                let last = match (*last_key, *limit) {
+                    (last_key, _) if last_key >= 0 => first + last_key,
+                    (last_key, 0) => argc + last_key,
+                    (last_key, limit) => first + (argc - first) / limit as i64 + last_key,
+                };
+
+                (first, last, *key_step)
+            }
+            FindKeys::KeyNum {
+                key_num_index,
+                first_key,
+                key_step,
+            } => {
+                let count = args
+                    .get((first + *key_num_index as i64) as usize)
+                    .and_then(|count| str::from_utf8(count.as_ref()).ok())
+                    .and_then(|count| count.parse::<i64>().ok())
+                    .filter(|count| *count >= 0);
+
+                let Some(count) = count else {
+                    return vec![];
+                };
+
+                let first = first + *first_key as i64;
+                (first, first + count - 1, *key_step)
+            }
+            FindKeys::Unknown => return vec![],
+        };
+
+        if first < 1 || first >= argc || last >= argc || last < first || step == 0 {
+            return vec![];
+        }
+
+        (first as usize..=last as usize).step_by(step).collect()
+    }
+}
+
+fn is_keyword(args: &[impl AsRef<[u8]>], i: i64, keyword: &str) -> bool {
+    usize::try_from(i)
+        .ok()
+        .and_then(|i| args.get(i))
+        .is_some_and(|arg| arg.as_ref().eq_ignore_ascii_case(keyword.as_bytes()))
+}
+
+// `first`, `last` and `step` from before key specs, `last` counting from the end if negative.
+fn legacy_positions(first: i64, last: i64, step: i64, argc: usize) -> Vec<usize> {
+    let argc = argc as i64;
+    let last = match last {
+        last if last < 0 => argc + last,
+        last => last,
+    };
+
+    if first < 1 || step < 1 || last < first {
+        return vec![];
+    }
+
+    (first..=last.min(argc - 1))
+        .step_by(step as usize)
+        .map(|i| i as usize)
+        .collect()
+}
+
+impl CommandTable {
+    /// The table bundled with the crate, for when asking the server is not an option.
+    pub fn bundled() -> &'static CommandTable {
+        &BUNDLED
+    }
+
+    /// Decodes a `COMMAND INFO` (or `COMMAND`) reply, RESP3 and RESP2 alike.
+    /// Unknown commands (`nil` entries) are left out.
+    pub fn from_command_info(reply: &Value) -> anyhow::Result<Self> {
+        let Value::Array(entries) = reply else {
+            bail!("CommandTable (unexpected COMMAND INFO reply: {reply})");
+        };
+
+        let commands = entries
+            .into_iter()
+            .filter(|entry| !matches!(entry, Value::Null(_)))
+            .map(|entry| {
+                let info = command_info(entry)?;
+                Ok((info.name.clone(), info))
+            })
+            .collect::<anyhow::Result<_>>()
+            .context("CommandTable::from_command_info")?;
+
+        Ok(Self { commands })
+    }
+
+    /// Looks a command up by name, case insensitively. Subcommands go by `container|subcommand`.
+    pub fn get(&self, name: &str) -> Option<&CommandInfo> {
+        let name = name.to_ascii_lowercase();
+
+        match name.split_once('|') {
+            Some((container, _)) => self
+                .commands
+                .get(container)?
+                .subcommands
+                .iter()
+                .find(|sub| sub.name == name),
+            None => self.commands.get(&name),
+        }
+    }
+
+    /// The command `cmd` runs: the subcommand if its container has one by the name
+    /// of the second argument, the command itself otherwise.
+    pub fn lookup(&self, cmd: &Cmd) -> Option<&CommandInfo> {
+        let name = str::from_utf8(cmd.name()).ok()?.to_ascii_lowercase();
+        let info = self.commands.get(&name)?;
+
+        let sub = cmd
+            .args()
+            .get(1)
+            .and_then(|sub| str::from_utf8(sub).ok())
+            .map(|sub| format!("{name}|{}", sub.to_ascii_lowercase()));
+
+        sub.and_then(|sub| info.subcommands.iter().find(|info| info.name == sub))
+            .or(Some(info))
+    }
+
+    /// The keys of `cmd`, in the order of its key specs. Empty for unknown commands.
+    pub fn extract_keys<'a>(&self, cmd: &'a Cmd) -> Vec<&'a [u8]> {
+        let Some(info) = self.lookup(cmd) else {
+            return vec![];
+        };
+
+        info.key_positions(cmd.args())
+            .into_iter()
+            .map(|i| &cmd.args()[i][..])
+            .collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &CommandInfo> {
+        self.commands.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+}
+
+// `[name, arity, flags, first key, last key, step, acl categories, tips, key specs, subcommands]`,
+// old servers stopping after the step (or the ACL categories).
+fn command_info(value: &Value) -> anyhow::Result<CommandInfo> {
+    let Value::Array(entry) = value else {
+        bail!("CommandTable (unexpected command: {value})");
+    };
+
+    let [name, arity, flags, first_key, last_key, step, rest @ ..] = entry.val() else {
+        bail!("CommandTable (unexpected command: {value})");
+    };
+
+    let acl_categories = match rest.first() {
+        Some(categories) => strings(categories)?
+            .into_iter()
+            .map(|category| category.trim_start_matches('@').to_string())
+            .collect(),
+        None => vec![],
+    };
+
+    let key_specs = match rest.get(2) {
+        Some(specs) => items(specs)?
+            .iter()
+            .map(key_spec)
+            .collect::<anyhow::Result<_>>()?,
+        None => vec![],
+    };
+
+    let subcommands = match rest.get(3) {
+        Some(subs) => items(subs)?
+            .iter()
+            .map(command_info)
+            .collect::<anyhow::Result<_>>()?,
+        None => vec![],
+    };
+
+    Ok(CommandInfo {
+        name: text(name)?.to_ascii_lowercase(),
+        arity: number(arity)?,
+        flags: strings(flags)?.into_iter().map(String::from).collect(),
+        first_key: number(first_key)?,
+        last_key: number(last_key)?,
+        step: number(step)?,
+        acl_categories,
+        key_specs,
+        subcommands,
+    })
+}
+
+fn key_spec(value: &Value) -> anyhow::Result<KeySpec> {
+    let spec = fields(value)?;
+
+    let flags = match find(&spec, "flags") {
+        Some(flags) => strings(flags)?.into_iter().map(String::from).collect(),
+        None => vec![],
+    };
+
+    let (kind, begin) = search(field(&spec, "begin_search")?)?;
+    let begin_search = match kind {
+        "index" => BeginSearch::Index(index(field(&begin, "index")?)?),
+        "keyword" => BeginSearch::Keyword {
+            keyword: text(field(&begin, "keyword")?)?.to_string(),
+            start_from: number(field(&begin, "startfrom")?)?,
+        },
+        _ => BeginSearch::Unknown,
+    };
+
+    let (kind, keys) = search(field(&spec, "find_keys")?)?;
+    let find_keys = match kind {
+        "range" => FindKeys::Range {
+            last_key: number(field(&keys, "lastkey")?)?,
+            key_step: index(field(&keys, "keystep")?)?,
+            limit: index(field(&keys, "limit")?)?,
+        },
+        "keynum" => FindKeys::KeyNum {
+            key_num_index: index(field(&keys, "keynumidx")?)?,
+            first_key: index(field(&keys, "firstkey")?)?,
+            key_step: index(field(&keys, "keystep")?)?,
+        },
+        _ => FindKeys::Unknown,
+    };
+
+    Ok(KeySpec {
+        begin_search,
+        find_keys,
+        flags,
+    })
+}
+
+// `{type, spec: {...}}`, the spec being empty (or absent) for unknown types.
+fn search(value: &Value) -> anyhow::Result<(&str, Vec<(&Value, &Value)>)> {
+    let search = fields(value)?;
+    let kind = text(field(&search, "type")?)?;
+
+    let spec = match find(&search, "spec") {
+        Some(spec) => fields(spec)?,
+        None => vec![],
+    };
+
+    Ok((kind, spec))
+}
+
+fn items(value: &Value) -> anyhow::Result<&[Value]> {
+    match value {
+        Value::Array(inner) => Ok(inner.val()),
+        Value::Set(inner) => Ok(inner.val()),
+        value => bail!("CommandTable (expected array: {value})"),
+    }
+}
+
+fn strings(value: &Value) -> anyhow::Result<Vec<&str>> {
+    items(value)?.iter().map(text).collect()
+}
+
+// RESP3 maps and RESP2 arrays of alternating names and values.
+fn fields(value: &Value) -> anyhow::Result<Vec<(&Value, &Value)>> {
+    match value {
+        Value::Map(map) => Ok(map.iter().collect()),
+        Value::Array(array) if array.len() % 2 == 0 => Ok(array
+            .val()
+            .chunks(2)
+            .map(|pair| (&pair[0], &pair[1]))
+            .collect()),
+        value => bail!("CommandTable (expected map: {value})"),
+    }
+}
+
+fn field<'a>(fields: &[(&Value, &'a Value)], name: &str) -> anyhow::Result<&'a Value> {
+    find(fields, name).ok_or_else(|| anyhow!("CommandTable (missing field {name:?})"))
+}
+
+fn find<'a>(fields: &[(&Value, &'a Value)], name: &str) -> Option<&'a Value> {
+    fields
+        .iter()
+        .find(|(k, _)| text(k).is_ok_and(|k| k == name))
+        .map(|(_, v)| *v)
+}
+
+fn text(value: &Value) -> anyhow::Result<&str> {
+    let bytes = match value {
+        Value::BlobString(inner) => &inner.val()[..],
+        Value::SimpleString(inner) => &inner.val()[..],
+        Value::VerbatimString(inner) => &inner.val()[..],
+        value => bail!("CommandTable (expected string: {value})"),
+    };
+
+    str::from_utf8(bytes).context("CommandTable (str::from_utf8)")
+}
+
+fn number(value: &Value) -> anyhow::Result<i64> {
+    match value {
+        Value::Number(inner) => Ok(inner.val()),
+        value => text(value)?
+            .parse()
+            .context("CommandTable (expected number)"),
+    }
+}
+
+fn index(value: &Value) -> anyhow::Result<usize> {
+    usize::try_from(number(value)?).context("CommandTable (expected positive number)")
+}
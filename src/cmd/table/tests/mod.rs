@@ -0,0 +1,180 @@
+use super::*;
+use crate::resp;
+
+fn keys(cmd: &str) -> Vec<String> {
+    let cmd = Cmd::from_line(cmd).unwrap();
+
+    CommandTable::bundled()
+        .extract_keys(&cmd)
+        .into_iter()
+        .map(|key| String::from_utf8(key.to_vec()).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_from_command_info() {
+    let reply = resp!([
+        [
+            "get",
+            2,
+            ~[+"readonly", +"fast"],
+            1,
+            1,
+            1,
+            ~[+"@read", +"@string", +"@fast"],
+            ~[],
+            [{
+                "flags" => ~[+"RO", +"ACCESS"],
+                "begin_search" => {"type" => "index", "spec" => {"index" => 1}},
+                "find_keys" => {"type" => "range", "spec" => {"lastkey" => 0, "keystep" => 1, "limit" => 0}},
+            }],
+            [],
+        ],
+        nil,
+        // RESP2: maps are flat arrays
+        [
+            "XREAD",
+            -4,
+            [+"readonly", +"blocking", +"movablekeys"],
+            0,
+            0,
+            0,
+            [+"@read", +"@stream", +"@slow", +"@blocking"],
+            [],
+            [[
+                "flags", [+"RO", +"ACCESS"],
+                "begin_search", ["type", "keyword", "spec", ["keyword", "STREAMS", "startfrom", 1]],
+                "find_keys", ["type", "range", "spec", ["lastkey", -1, "keystep", 1, "limit", 2]],
+            ]],
+            [],
+        ],
+        // before key specs and ACL categories
+        ["mget", -2, [+"readonly", +"fast"], 1, -1, 1],
+    ]);
+    let table = CommandTable::from_command_info(&reply).unwrap();
+    assert_eq!(table.len(), 3);
+
+    let get = table.get("GET").unwrap();
+    assert_eq!(
+        get,
+        &CommandInfo {
+            name: "get".to_string(),
+            arity: 2,
+            flags: vec!["readonly".to_string(), "fast".to_string()],
+            first_key: 1,
+            last_key: 1,
+            step: 1,
+            acl_categories: vec!["read".to_string(), "string".to_string(), "fast".to_string()],
+            key_specs: vec![KeySpec {
+                begin_search: BeginSearch::Index(1),
+                find_keys: FindKeys::Range {
+                    last_key: 0,
+                    key_step: 1,
+                    limit: 0
+                },
+                flags: vec!["RO".to_string(), "ACCESS".to_string()],
+            }],
+            subcommands: vec![],
+        }
+    );
+    assert!(get.is_readonly() && !get.is_write() && !get.is_blocking());
+
+    let xread = table.get("xread").unwrap();
+    assert!(xread.is_blocking());
+    assert_eq!(
+        xread.key_specs[0].begin_search,
+        BeginSearch::Keyword {
+            keyword: "STREAMS".to_string(),
+            start_from: 1
+        }
+    );
+    // with a limit of 2 only the first half of what follows STREAMS are keys
+    let args = ["XREAD", "COUNT", "2", "STREAMS", "a", "b", "0", "0"];
+    assert_eq!(xread.key_positions(&args), [4, 5]);
+
+    let mget = table.get("mget").unwrap();
+    assert!(mget.key_specs.is_empty() && mget.acl_categories.is_empty());
+    assert_eq!(mget.key_positions(&["MGET", "a", "b"]), [1, 2]);
+
+    assert!(CommandTable::from_command_info(&resp!([["get", 2]])).is_err());
+    assert!(CommandTable::from_command_info(&resp!(+"OK")).is_err());
+}
+
+#[test]
+fn test_lookup() {
+    let table = CommandTable::bundled();
+
+    assert!(table.len() > 200);
+    assert_eq!(table.get("config|get").unwrap().name, "config|get");
+    assert!(table.get("config|nope").is_none());
+    assert!(table.get("nope").is_none());
+
+    let lookup = |line| {
+        table
+            .lookup(&Cmd::from_line(line).unwrap())
+            .map(|info| &info.name[..])
+    };
+    assert_eq!(lookup("XINFO stream s"), Some("xinfo|stream"));
+    assert_eq!(lookup("xinfo"), Some("xinfo"));
+    assert_eq!(lookup("COMMAND"), Some("command"));
+    assert_eq!(lookup("GET key"), Some("get"));
+    assert_eq!(lookup("NOPE"), None);
+
+    let set = table.get("set").unwrap();
+    assert!(set.is_write() && set.acl_categories.contains(&"string".to_string()));
+    assert!(set.accepts(3) && set.accepts(5) && !set.accepts(2));
+    assert!(table.get("get").unwrap().accepts(2));
+    assert!(!table.get("get").unwrap().accepts(3));
+    assert!(table.get("subscribe").unwrap().is_pubsub());
+    assert!(table.get("multi").unwrap().is_noscript());
+}
+
+#[test]
+fn test_extract_keys() {
+    assert_eq!(keys("GET a"), ["a"]);
+    assert_eq!(keys("get a"), ["a"]);
+    assert_eq!(keys("MGET a b c"), ["a", "b", "c"]);
+    assert_eq!(keys("MSET a 1 b 2"), ["a", "b"]);
+    assert_eq!(keys("BLPOP a b 0"), ["a", "b"]);
+    assert_eq!(keys("RENAME a b"), ["a", "b"]);
+    assert_eq!(keys("BITOP AND dest a b"), ["dest", "a", "b"]);
+    assert_eq!(keys("SINTERSTORE dest a b"), ["dest", "a", "b"]);
+    assert_eq!(keys("LCS a b"), ["a", "b"]);
+
+    // key counts
+    assert_eq!(keys("EVAL script 2 a b arg"), ["a", "b"]);
+    assert_eq!(keys("EVALSHA sha 0 arg"), Vec::<String>::new());
+    assert_eq!(
+        keys("ZUNIONSTORE dest 2 a b WEIGHTS 1 2"),
+        ["dest", "a", "b"]
+    );
+    assert_eq!(keys("BLMPOP 0 2 a b LEFT"), ["a", "b"]);
+    assert!(keys("EVAL script x a").is_empty());
+    assert!(keys("EVAL script 3 a").is_empty());
+
+    // keywords
+    assert_eq!(keys("XREAD COUNT 1 STREAMS a b 0 0"), ["a", "b"]);
+    assert_eq!(keys("XREADGROUP GROUP g c streams a >"), ["a"]);
+    assert_eq!(keys("SORT a BY w_* STORE dest"), ["a", "dest"]);
+    assert_eq!(keys("SORT a"), ["a"]);
+    assert_eq!(keys("GEORADIUS a 0 0 1 km STORE b"), ["a", "b"]);
+
+    // subcommands
+    assert_eq!(keys("XINFO STREAM s"), ["s"]);
+    assert_eq!(keys("OBJECT ENCODING k"), ["k"]);
+    assert!(keys("CONFIG GET maxmemory").is_empty());
+
+    // sharded channels
+    assert_eq!(keys("SSUBSCRIBE a b"), ["a", "b"]);
+
+    assert!(keys("PING hello").is_empty());
+    assert!(keys("NOPE a").is_empty());
+}
+
+#[test]
+fn test_legacy_positions() {
+    assert_eq!(legacy_positions(1, 1, 1, 2), [1]);
+    assert_eq!(legacy_positions(1, -1, 2, 5), [1, 3]);
+    assert_eq!(legacy_positions(0, 0, 0, 3), Vec::<usize>::new());
+    assert_eq!(legacy_positions(1, 1, 1, 1), Vec::<usize>::new());
+}
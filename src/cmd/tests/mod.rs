@@ -0,0 +1,37 @@
+use super::*;
+
+#[test]
+fn test_encode() {
+    let cmd = Cmd::new("SET").arg("key").arg(b"va\r\nl".to_vec());
+
+    assert_eq!(
+        Bytes::try_from(&cmd).unwrap(),
+        Bytes::from_static(b"*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$5\r\nva\r\nl\r\n")
+    );
+
+    assert_eq!(
+        Bytes::try_from(&cmd).unwrap(),
+        Bytes::try_from(Value::from(&cmd)).unwrap()
+    );
+}
+
+#[test]
+fn test_from_line() {
+    assert_eq!(
+        Cmd::from_line("  set key   value ").unwrap(),
+        Cmd::from_iter(["set", "key", "value"])
+    );
+
+    assert_eq!(
+        Cmd::from_line(r#"SET "a b\r\n\x41" 'it\'s' """#).unwrap(),
+        Cmd::from_iter(["SET", "a b\r\nA", "it's", ""])
+    );
+}
+
+#[test]
+fn test_from_line_invalid() {
+    assert!(Cmd::from_line("").is_err());
+    assert!(Cmd::from_line(r#"SET "key"#).is_err());
+    assert!(Cmd::from_line(r#"SET "key"value"#).is_err());
+    assert!(Cmd::from_line(r#"SET "\xZZ""#).is_err());
+}
@@ -0,0 +1,461 @@
+//! Where and how to connect, as given by `redis://` URLs.
+
+#[cfg(test)]
+pub(crate) mod tests;
+
+use std::{fmt, path::PathBuf, str::FromStr, time::Duration};
+
+use anyhow::{anyhow, bail, Context};
+use bytes::Bytes;
+
+use crate::protocol::{Hello, Version};
+
+const DEFAULT_PORT: u16 = 6379;
+
+/// The server to connect to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Addr {
+    Tcp { host: String, port: u16 },
+    Unix(PathBuf),
+}
+
+/// TLS parameters, set for `rediss://` URLs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TlsOptions {
+    /// PEM file with the certificates to trust instead of the Mozilla roots (`tls_ca`).
+    pub ca_file: Option<PathBuf>,
+    /// PEM file with the client certificate chain, for mutual TLS (`tls_cert`).
+    pub cert_file: Option<PathBuf>,
+    /// PEM file with the client private key, for mutual TLS (`tls_key`).
+    pub key_file: Option<PathBuf>,
+    /// Name to verify the certificate against, the host by default (`tls_server_name`).
+    pub server_name: Option<String>,
+    /// Skips verifying the server certificate (`insecure`, or a `#insecure` fragment).
+    pub insecure: bool,
+    /// Used as is by every connection instead of building a configuration out of the
+    /// fields above each time, see [`ConnectionInfo::load_tls`].
+    #[cfg(feature = "tls")]
+    pub client_config: Option<crate::tls::TlsConfig>,
+}
+
+/// Everything needed to open a connection, parsed from a URL or built by hand:
+///
+/// ```text
+/// redis://[[username]:password@]host[:port][/db][?param=value&...]
+/// rediss://...                                    (TLS)
+/// unix://[[username]:password@]/path[?param=value&...]
+/// redis+unix://...
+/// ```
+///
+/// Parameters: `db`, `protocol` (`2` or `3`), `client_name`, `connect_timeout` and
+/// `response_timeout` (seconds, or with an `ms` or `s` suffix), plus the TLS ones
+/// of [`TlsOptions`] for `rediss://`. Credentials and paths may be percent-encoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionInfo {
+    pub addr: Addr,
+    pub tls: Option<TlsOptions>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Selected after connecting unless 0.
+    pub db: i64,
+    /// Negotiated with `HELLO`; without it the server's default (RESP2) applies.
+    pub protocol: Option<Version>,
+    pub client_name: Option<String>,
+    pub connect_timeout: Option<Duration>,
+    pub response_timeout: Option<Duration>,
+}
+
+impl Default for ConnectionInfo {
+    fn default() -> Self {
+        Self::tcp("127.0.0.1", DEFAULT_PORT)
+    }
+}
+
+impl ConnectionInfo {
+    pub fn tcp(host: impl Into<String>, port: u16) -> Self {
+        Self::new(Addr::Tcp {
+            host: host.into(),
+            port,
+        })
+    }
+
+    pub fn unix(path: impl Into<PathBuf>) -> Self {
+        Self::new(Addr::Unix(path.into()))
+    }
+
+    fn new(addr: Addr) -> Self {
+        Self {
+            addr,
+            tls: None,
+            username: None,
+            password: None,
+            db: 0,
+            protocol: None,
+            client_name: None,
+            connect_timeout: None,
+            response_timeout: None,
+        }
+    }
+
+    pub fn from_url(url: &str) -> anyhow::Result<Self> {
+        parse(url).with_context(|| format!("ConnectionInfo (invalid URL {})", redacted(url)))
+    }
+
+    /// Reads the URL from `REDIS_URL` (`redis://127.0.0.1:6379` if unset),
+    /// then applies [`ConnectionInfo::with_env`].
+    pub fn from_env() -> anyhow::Result<Self> {
+        let info = match std::env::var("REDIS_URL") {
+            Ok(url) => Self::from_url(&url)?,
+            Err(_) => Self::default(),
+        };
+
+        info.with_env()
+    }
+
+    /// Overrides settings with the `REDIS_USERNAME`, `REDIS_PASSWORD`, `REDIS_DB`,
+    /// `REDIS_PROTOCOL`, `REDIS_CLIENT_NAME`, `REDIS_CONNECT_TIMEOUT` and
+    /// `REDIS_RESPONSE_TIMEOUT` environment variables, where set.
+    pub fn with_env(self) -> anyhow::Result<Self> {
+        self.with_overrides(|name| std::env::var(name).ok())
+    }
+
+    /// Same as [`ConnectionInfo::with_env`], with variables looked up by `var`.
+    pub fn with_overrides(mut self, var: impl Fn(&str) -> Option<String>) -> anyhow::Result<Self> {
+        for (name, param) in [
+            ("REDIS_USERNAME", "username"),
+            ("REDIS_PASSWORD", "password"),
+            ("REDIS_DB", "db"),
+            ("REDIS_PROTOCOL", "protocol"),
+            ("REDIS_CLIENT_NAME", "client_name"),
+            ("REDIS_CONNECT_TIMEOUT", "connect_timeout"),
+            ("REDIS_RESPONSE_TIMEOUT", "response_timeout"),
+        ] {
+            if let Some(value) = var(name) {
+                self.set(param, value)
+                    .with_context(|| format!("ConnectionInfo (invalid {name})"))?;
+            }
+        }
+
+        Ok(self)
+    }
+
+    pub fn with_tls(mut self, tls: TlsOptions) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// Builds the TLS client configuration out of the files now and keeps it, so the
+    /// connections opened with this info share it. Call it again to pick up rotated
+    /// certificates. Does nothing without TLS.
+    #[cfg(feature = "tls")]
+    pub fn load_tls(&mut self) -> anyhow::Result<()> {
+        if let Some(tls) = self.tls.as_mut() {
+            let config = crate::tls::client_config(tls).context("ConnectionInfo (load_tls)")?;
+            tls.client_config = Some(crate::tls::TlsConfig(config));
+        }
+
+        Ok(())
+    }
+
+    pub fn with_username(mut self, username: impl Into<String>) -> Self {
+        self.username = Some(username.into());
+        self
+    }
+
+    pub fn with_password(mut self, password: impl Into<String>) -> Self {
+        self.password = Some(password.into());
+        self
+    }
+
+    pub fn with_db(mut self, db: i64) -> Self {
+        self.db = db;
+        self
+    }
+
+    pub fn with_protocol(mut self, protocol: Version) -> Self {
+        self.protocol = Some(protocol);
+        self
+    }
+
+    pub fn with_client_name(mut self, name: impl Into<String>) -> Self {
+        self.client_name = Some(name.into());
+        self
+    }
+
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_response_timeout(mut self, timeout: Duration) -> Self {
+        self.response_timeout = Some(timeout);
+        self
+    }
+
+    /// The handshake to perform, if any: when a protocol, credentials or a client name are set.
+    /// A password alone authenticates the `default` user.
+    pub fn hello(&self) -> Option<Hello> {
+        let auth = self.password.as_ref().map(|password| {
+            let username = self.username.as_deref().unwrap_or("default");
+            (
+                Bytes::from(username.to_string()),
+                Bytes::from(password.clone()),
+            )
+        });
+
+        if self.protocol.is_none() && auth.is_none() && self.client_name.is_none() {
+            return None;
+        }
+
+        Some(Hello {
+            version: self.protocol.unwrap_or(Version::Resp2),
+            auth,
+            client_name: self.client_name.clone().map(Bytes::from),
+        })
+    }
+
+    // One setting given as text, the way URL parameters and environment variables are.
+    fn set(&mut self, param: &str, value: String) -> anyhow::Result<()> {
+        match param {
+            "username" => self.username = Some(value),
+            "password" => self.password = Some(value),
+            "db" => self.db = database(&value)?,
+            "protocol" => {
+                self.protocol = Some(match value.as_str() {
+                    "2" | "resp2" => Version::Resp2,
+                    "3" | "resp3" => Version::Resp3,
+                    _ => bail!("protocol has to be 2 or 3, not {value:?}"),
+                })
+            }
+            "client_name" => self.client_name = Some(value),
+            "connect_timeout" => self.connect_timeout = Some(duration(&value)?),
+            "response_timeout" => self.response_timeout = Some(duration(&value)?),
+            param if param == "insecure" || param.starts_with("tls_") => {
+                let tls = self
+                    .tls
+                    .as_mut()
+                    .with_context(|| format!("{param} is only valid with rediss://"))?;
+
+                match param {
+                    "tls_ca" => tls.ca_file = Some(value.into()),
+                    "tls_cert" => tls.cert_file = Some(value.into()),
+                    "tls_key" => tls.key_file = Some(value.into()),
+                    "tls_server_name" => tls.server_name = Some(value),
+                    "insecure" => tls.insecure = boolean(&value)?,
+                    _ => bail!("unknown parameter {param}"),
+                }
+            }
+            _ => bail!("unknown parameter {param}"),
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for ConnectionInfo {
+    type Err = anyhow::Error;
+
+    fn from_str(url: &str) -> anyhow::Result<Self> {
+        Self::from_url(url)
+    }
+}
+
+impl fmt::Display for Addr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp { host, port } if host.contains(':') => write!(f, "[{host}]:{port}"),
+            Self::Tcp { host, port } => write!(f, "{host}:{port}"),
+            Self::Unix(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+fn parse(url: &str) -> anyhow::Result<ConnectionInfo> {
+    let (scheme, rest) = url.split_once("://").context("missing scheme")?;
+
+    let (rest, fragment) = match rest.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(fragment)),
+        None => (rest, None),
+    };
+    let (rest, query) = match rest.split_once('?') {
+        Some((rest, query)) => (rest, Some(query)),
+        None => (rest, None),
+    };
+
+    let (userinfo, rest) = userinfo(rest);
+    // a db in the path, even 0, rules out another one in the query
+    let path_db = rest.split_once('/').is_some_and(|(_, db)| !db.is_empty());
+
+    let mut info = match scheme.to_ascii_lowercase().as_str() {
+        "redis" => tcp(rest)?,
+        "rediss" => tcp(rest)?.with_tls(TlsOptions::default()),
+        "unix" | "redis+unix" => {
+            if !rest.starts_with('/') {
+                bail!("unix socket path has to be absolute");
+            }
+            ConnectionInfo::unix(decode(rest)?)
+        }
+        _ => bail!("unsupported scheme {scheme:?}"),
+    };
+
+    if let Some(userinfo) = userinfo {
+        let (username, password) = match userinfo.split_once(':') {
+            Some((username, password)) => (username, Some(password)),
+            None => (userinfo, None),
+        };
+
+        if !username.is_empty() {
+            info.username = Some(decode(username)?);
+        }
+        if let Some(password) = password {
+            info.password = Some(decode(password)?);
+        }
+        if info.username.is_some() && info.password.is_none() {
+            bail!("username without a password");
+        }
+    }
+
+    let path_db = path_db && matches!(info.addr, Addr::Tcp { .. });
+    for pair in query.into_iter().flat_map(|query| query.split('&')) {
+        if pair.is_empty() {
+            continue;
+        }
+
+        let (param, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let param = decode(param)?;
+        if param == "db" && path_db {
+            bail!("db given in both the path and the query");
+        }
+
+        info.set(&param, decode(value)?)?;
+    }
+
+    match fragment {
+        None | Some("") => {}
+        Some("insecure") => info.set("insecure", "true".to_string())?,
+        Some(fragment) => bail!("unknown fragment {fragment:?}"),
+    }
+
+    Ok(info)
+}
+
+// Splits `[userinfo@]rest`. Credentials end at the last '@' before the path, passwords may
+// contain unencoded ones and the path of unix URLs its own.
+fn userinfo(rest: &str) -> (Option<&str>, &str) {
+    let authority = rest.find('/').map_or(rest, |end| &rest[..end]);
+
+    match authority.rsplit_once('@') {
+        Some((userinfo, _)) => (Some(userinfo), &rest[userinfo.len() + 1..]),
+        None => (None, rest),
+    }
+}
+
+// `host[:port][/db]`
+fn tcp(rest: &str) -> anyhow::Result<ConnectionInfo> {
+    let (authority, path) = match rest.split_once('/') {
+        Some((authority, path)) => (authority, path),
+        None => (rest, ""),
+    };
+
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(ipv6) => {
+            let (host, port) = ipv6.split_once(']').context("unclosed IPv6 address")?;
+            let port = match port {
+                "" => None,
+                port => Some(
+                    port.strip_prefix(':')
+                        .context("garbage after IPv6 address")?,
+                ),
+            };
+            (host, port)
+        }
+        None => match authority.split_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+
+    if host.is_empty() {
+        bail!("missing host");
+    }
+
+    let port = match port {
+        Some(port) => port.parse().map_err(|_| anyhow!("invalid port {port:?}"))?,
+        None => DEFAULT_PORT,
+    };
+
+    let mut info = ConnectionInfo::tcp(decode(host)?, port);
+    if !path.is_empty() {
+        info.db = database(path)?;
+    }
+
+    Ok(info)
+}
+
+fn database(value: &str) -> anyhow::Result<i64> {
+    match value.parse() {
+        Ok(db) if db >= 0 => Ok(db),
+        _ => bail!("invalid database {value:?}"),
+    }
+}
+
+fn boolean(value: &str) -> anyhow::Result<bool> {
+    match value {
+        "" | "1" | "true" | "yes" => Ok(true),
+        "0" | "false" | "no" => Ok(false),
+        _ => bail!("invalid boolean {value:?}"),
+    }
+}
+
+// Seconds, possibly fractional, or with an `ms` or `s` suffix.
+fn duration(value: &str) -> anyhow::Result<Duration> {
+    let (number, scale) = match value.strip_suffix("ms") {
+        Some(number) => (number, 0.001),
+        None => (value.strip_suffix('s').unwrap_or(value), 1.0),
+    };
+
+    number
+        .parse::<f64>()
+        .ok()
+        .and_then(|number| Duration::try_from_secs_f64(number * scale).ok())
+        .with_context(|| format!("invalid duration {value:?}"))
+}
+
+fn decode(input: &str) -> anyhow::Result<String> {
+    let mut out = Vec::with_capacity(input.len());
+    let mut bytes = input.bytes();
+
+    while let Some(byte) = bytes.next() {
+        if byte != b'%' {
+            out.push(byte);
+            continue;
+        }
+
+        let hex = [bytes.next(), bytes.next()];
+        let byte = match hex {
+            [Some(high), Some(low)] => std::str::from_utf8(&[high, low])
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+        out.push(byte.with_context(|| format!("invalid percent-encoding in {input:?}"))?);
+    }
+
+    String::from_utf8(out).context("percent-encoded text is not UTF-8")
+}
+
+// The URL with the password hidden, for error messages.
+fn redacted(url: &str) -> String {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return url.to_string();
+    };
+
+    match userinfo(rest) {
+        (Some(userinfo), rest) => match userinfo.split_once(':') {
+            Some((username, _)) => format!("{scheme}://{username}:***@{rest}"),
+            // a password mistaken for a username, e.g. `redis://secret@host`
+            None => format!("{scheme}://***@{rest}"),
+        },
+        (None, _) => url.to_string(),
+    }
+}
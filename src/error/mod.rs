@@ -0,0 +1,129 @@
+//! Error replies sent by the server.
+
+#[cfg(test)]
+pub(crate) mod tests;
+
+use std::{error::Error, fmt, str};
+
+use bytes::Bytes;
+
+use crate::value::{split_code, BlobError, SimpleError};
+
+/// What went wrong, according to the error code.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// The slot is served by another node (cluster).
+    Moved {
+        slot: u16,
+        addr: String,
+    },
+    /// The slot is being migrated, retry on the given node after `ASKING` (cluster).
+    Ask {
+        slot: u16,
+        addr: String,
+    },
+    TryAgain,
+    ClusterDown,
+    Loading,
+    NoAuth,
+    WrongPass,
+    WrongType,
+    NoScript,
+    ReadOnly,
+    Busy,
+    ExecAbort,
+    OOM,
+    /// Any other code (`ERR` included), empty if the error has none.
+    Other(String),
+}
+
+impl ErrorKind {
+    fn parse(code: Option<&str>, msg: &[u8]) -> Self {
+        match code {
+            Some(code @ ("MOVED" | "ASK")) => match redirect(msg) {
+                Some((slot, addr)) if code == "MOVED" => Self::Moved { slot, addr },
+                Some((slot, addr)) => Self::Ask { slot, addr },
+                None => Self::Other(code.to_string()),
+            },
+            Some("TRYAGAIN") => Self::TryAgain,
+            Some("CLUSTERDOWN") => Self::ClusterDown,
+            Some("LOADING") => Self::Loading,
+            Some("NOAUTH") => Self::NoAuth,
+            Some("WRONGPASS") => Self::WrongPass,
+            Some("WRONGTYPE") => Self::WrongType,
+            Some("NOSCRIPT") => Self::NoScript,
+            Some("READONLY") => Self::ReadOnly,
+            Some("BUSY") => Self::Busy,
+            Some("EXECABORT") => Self::ExecAbort,
+            Some("OOM") => Self::OOM,
+            code => Self::Other(code.unwrap_or_default().to_string()),
+        }
+    }
+}
+
+/// An error reply (simple or blob) with its code parsed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ServerError {
+    kind: ErrorKind,
+    val: Bytes,
+}
+
+impl ServerError {
+    pub fn new(val: impl Into<Bytes>) -> Self {
+        let val = val.into();
+        let (code, msg) = split_code(&val);
+
+        Self {
+            kind: ErrorKind::parse(code, msg),
+            val,
+        }
+    }
+
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// The whole payload, code included.
+    pub fn val(&self) -> &Bytes {
+        &self.val
+    }
+
+    pub fn code(&self) -> Option<&str> {
+        split_code(&self.val).0
+    }
+
+    /// The payload without the error code.
+    pub fn msg(&self) -> &[u8] {
+        split_code(&self.val).1
+    }
+}
+
+impl From<SimpleError> for ServerError {
+    fn from(input: SimpleError) -> Self {
+        Self::new(input.val().clone())
+    }
+}
+
+impl From<BlobError> for ServerError {
+    fn from(input: BlobError) -> Self {
+        Self::new(input.val().clone())
+    }
+}
+
+impl fmt::Display for ServerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", String::from_utf8_lossy(&self.val))
+    }
+}
+
+impl Error for ServerError {}
+
+// `<slot> <host>:<port>`
+fn redirect(msg: &[u8]) -> Option<(u16, String)> {
+    let mut parts = str::from_utf8(msg).ok()?.split(' ');
+
+    let slot = parts.next()?.parse().ok()?;
+    let addr = parts.next().filter(|addr| !addr.is_empty())?;
+
+    Some((slot, addr.to_string()))
+}
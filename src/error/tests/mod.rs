@@ -0,0 +1,84 @@
+use super::*;
+use crate::value::{Number, Value};
+
+#[test]
+fn test_kind() {
+    let kind = |val: &'static str| ServerError::new(val).kind().clone();
+
+    assert_eq!(
+        kind("MOVED 3999 127.0.0.1:6381"),
+        ErrorKind::Moved {
+            slot: 3999,
+            addr: "127.0.0.1:6381".to_string()
+        }
+    );
+    assert_eq!(
+        kind("ASK 3999 127.0.0.1:6381"),
+        ErrorKind::Ask {
+            slot: 3999,
+            addr: "127.0.0.1:6381".to_string()
+        }
+    );
+    assert_eq!(kind("MOVED garbage"), ErrorKind::Other("MOVED".to_string()));
+    assert_eq!(kind("TRYAGAIN Multiple keys request"), ErrorKind::TryAgain);
+    assert_eq!(
+        kind("CLUSTERDOWN The cluster is down"),
+        ErrorKind::ClusterDown
+    );
+    assert_eq!(kind("LOADING Redis is loading"), ErrorKind::Loading);
+    assert_eq!(kind("NOAUTH Authentication required."), ErrorKind::NoAuth);
+    assert_eq!(kind("WRONGPASS invalid password"), ErrorKind::WrongPass);
+    assert_eq!(
+        kind("WRONGTYPE Operation against a key"),
+        ErrorKind::WrongType
+    );
+    assert_eq!(kind("NOSCRIPT No matching script."), ErrorKind::NoScript);
+    assert_eq!(kind("READONLY You can't write"), ErrorKind::ReadOnly);
+    assert_eq!(kind("BUSY Redis is busy"), ErrorKind::Busy);
+    assert_eq!(
+        kind("EXECABORT Transaction discarded"),
+        ErrorKind::ExecAbort
+    );
+    assert_eq!(kind("OOM command not allowed"), ErrorKind::OOM);
+    assert_eq!(
+        kind("ERR unknown command"),
+        ErrorKind::Other("ERR".to_string())
+    );
+    assert_eq!(kind("oops"), ErrorKind::Other(String::new()));
+}
+
+#[test]
+fn test_into_result() {
+    let value = Value::from(Number::from(1));
+    assert_eq!(value.clone().into_result(), Ok(value));
+
+    let err = Value::from(SimpleError::new("WRONGTYPE Operation against a key").unwrap())
+        .into_result()
+        .unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::WrongType);
+    assert_eq!(err.code(), Some("WRONGTYPE"));
+    assert_eq!(err.msg(), b"Operation against a key");
+    assert_eq!(err.to_string(), "WRONGTYPE Operation against a key");
+
+    let err = Value::from(BlobError::new("ERR boom"))
+        .into_result()
+        .unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::Other("ERR".to_string()));
+
+    let err: anyhow::Error = err.into();
+    assert!(err.downcast_ref::<ServerError>().is_some());
+}
+
+#[test]
+fn test_server_error() {
+    assert_eq!(Value::from(Number::from(1)).server_error(), None);
+
+    let value = Value::from(SimpleError::new("MOVED 3999 127.0.0.1:6381").unwrap());
+    assert_eq!(value.server_error(), value.clone().into_result().err());
+    assert!(matches!(
+        Value::from(BlobError::new("TRYAGAIN later"))
+            .server_error()
+            .map(|err| err.kind().clone()),
+        Some(ErrorKind::TryAgain)
+    ));
+}
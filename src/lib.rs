@@ -1 +1,21 @@
+#[cfg(feature = "async")]
+pub mod aio;
+pub mod blocking;
+pub mod capture;
+#[cfg(feature = "async")]
+pub mod cluster;
+pub mod cmd;
+pub mod config;
+pub mod error;
+#[doc(hidden)]
+pub mod macros;
+#[cfg(feature = "async")]
+pub mod pool;
+pub mod protocol;
+#[cfg(feature = "async")]
+pub mod script;
+#[cfg(feature = "tls")]
+pub mod tls;
+#[cfg(feature = "async")]
+pub mod transaction;
 pub mod value;
@@ -0,0 +1,228 @@
+//! Support for the [`resp!`](crate::resp) macro.
+
+#[cfg(test)]
+pub(crate) mod tests;
+
+use std::str::FromStr;
+
+use bytes::Bytes;
+use num_bigint::BigInt;
+
+use crate::value::{
+    BigNumber, BlobString, Double, Number, SimpleError, SimpleString, Value, VerbatimFormat,
+    VerbatimString,
+};
+
+/// Builds a [`Value`](crate::value::Value) with a JSON-like syntax.
+///
+/// | syntax                   | value                                 |
+/// |--------------------------|---------------------------------------|
+/// | `[a, b]`                 | array                                 |
+/// | `~[a, b]`                | set                                   |
+/// | `>[a, b]`                | push                                  |
+/// | `{k => v}`               | map                                   |
+/// | `\|{k => v}\| value`     | `value` with an attribute             |
+/// | `+"OK"`                  | simple string                         |
+/// | `-"ERR msg"`             | simple error                          |
+/// | `!"ERR msg"`             | blob error                            |
+/// | `"text"`, `b"text"`      | blob string                           |
+/// | `42`, `-42`              | number                                |
+/// | `3.5`, `-3.5`            | double                                |
+/// | `true`, `false`          | boolean                               |
+/// | `nil`                    | null                                  |
+/// | `(big 123)`              | big number, of any length             |
+/// | `verbatim!("txt", text)` | verbatim string                       |
+/// | `(expr)`, `ident`        | anything convertible into a `Value`   |
+///
+/// Panics if a simple string or a simple error contains CR or LF.
+///
+/// ```
+/// use resp3::{resp, value::BlobString};
+///
+/// let name = BlobString::from("key");
+/// let value = resp!(|{+"ttl" => 3600}| {
+///     (name) => [1, 2.5, nil],
+///     +"ok" => ~[true, b"\xff"],
+/// });
+/// ```
+#[macro_export]
+macro_rules! resp {
+    (nil) => {
+        $crate::value::Value::from($crate::value::Null::default())
+    };
+    (true) => {
+        $crate::value::Value::from($crate::value::Boolean::from(true))
+    };
+    (false) => {
+        $crate::value::Value::from($crate::value::Boolean::from(false))
+    };
+    ([ $($items:tt)* ]) => {
+        $crate::value::Value::from($crate::value::Array::from(
+            $crate::resp!(@seq [] [] $($items)*)
+        ))
+    };
+    (~[ $($items:tt)* ]) => {
+        $crate::value::Value::from($crate::value::Set::from(
+            $crate::resp!(@seq [] [] $($items)*)
+        ))
+    };
+    (>[ $($items:tt)* ]) => {
+        $crate::value::Value::from($crate::value::Push::from(
+            $crate::resp!(@seq [] [] $($items)*)
+        ))
+    };
+    ({ $($entries:tt)* }) => {
+        $crate::value::Value::from($crate::value::Map::from(
+            $crate::resp!(@map [] [] $($entries)*)
+        ))
+    };
+    (|{ $($entries:tt)* }| $($value:tt)+) => {
+        $crate::resp!($($value)+).with_attr($crate::value::Attribute::from(
+            $crate::resp!(@map [] [] $($entries)*)
+        ))
+    };
+    (+ ($val:expr)) => {
+        $crate::macros::simple_string($val)
+    };
+    (- ($val:expr)) => {
+        $crate::macros::Negate::negate($val)
+    };
+    (! ($val:expr)) => {
+        $crate::value::Value::from($crate::value::BlobError::new($val))
+    };
+    (+ $val:tt) => {
+        $crate::macros::simple_string($val)
+    };
+    (- $val:tt) => {
+        $crate::macros::Negate::negate($val)
+    };
+    (! $val:tt) => {
+        $crate::value::Value::from($crate::value::BlobError::new($val))
+    };
+    ((big - $val:literal)) => {
+        $crate::macros::big_number(concat!("-", stringify!($val)))
+    };
+    ((big $val:literal)) => {
+        $crate::macros::big_number(stringify!($val))
+    };
+    (verbatim!($format:expr, $val:expr $(,)?)) => {
+        $crate::macros::verbatim($format, $val)
+    };
+    ($val:literal) => {
+        $crate::macros::Literal::into_value($val)
+    };
+    ($val:expr) => {
+        $crate::value::Value::from($val)
+    };
+
+    // items are split on top level commas, each one is built on its own
+    (@seq [$($out:expr,)*] []) => {
+        vec![$($out,)*]
+    };
+    (@seq [$($out:expr,)*] [$($item:tt)+]) => {
+        vec![$($out,)* $crate::resp!($($item)+)]
+    };
+    (@seq [$($out:expr,)*] [$($item:tt)+] , $($rest:tt)*) => {
+        $crate::resp!(@seq [$($out,)* $crate::resp!($($item)+),] [] $($rest)*)
+    };
+    (@seq [$($out:expr,)*] [$($item:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::resp!(@seq [$($out,)*] [$($item)* $next] $($rest)*)
+    };
+
+    (@map [$($out:expr,)*] []) => {
+        vec![$($out,)*]
+    };
+    (@map [$($out:expr,)*] [$($key:tt)+] => $($rest:tt)*) => {
+        $crate::resp!(@entry [$($out,)*] [$($key)+] [] $($rest)*)
+    };
+    (@map [$($out:expr,)*] [$($key:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::resp!(@map [$($out,)*] [$($key)* $next] $($rest)*)
+    };
+
+    (@entry [$($out:expr,)*] [$($key:tt)+] [$($val:tt)+]) => {
+        vec![$($out,)* ($crate::resp!($($key)+), $crate::resp!($($val)+))]
+    };
+    (@entry [$($out:expr,)*] [$($key:tt)+] [$($val:tt)+] , $($rest:tt)*) => {
+        $crate::resp!(
+            @map [$($out,)* ($crate::resp!($($key)+), $crate::resp!($($val)+)),] [] $($rest)*
+        )
+    };
+    (@entry [$($out:expr,)*] [$($key:tt)+] [$($val:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::resp!(@entry [$($out,)*] [$($key)+] [$($val)* $next] $($rest)*)
+    };
+}
+
+/// Literals the [`resp!`](crate::resp) macro takes as they are.
+pub trait Literal {
+    fn into_value(self) -> Value;
+}
+
+impl Literal for i64 {
+    fn into_value(self) -> Value {
+        Value::from(Number::from(self))
+    }
+}
+
+impl Literal for f64 {
+    fn into_value(self) -> Value {
+        Value::from(Double::from(self))
+    }
+}
+
+impl Literal for &'static str {
+    fn into_value(self) -> Value {
+        Value::from(BlobString::from(self))
+    }
+}
+
+impl<const N: usize> Literal for &'static [u8; N] {
+    fn into_value(self) -> Value {
+        Value::from(BlobString::from(&self[..]))
+    }
+}
+
+/// What follows a `-` in the [`resp!`](crate::resp) macro:
+/// numbers are negated, strings become simple errors.
+pub trait Negate {
+    fn negate(self) -> Value;
+}
+
+impl Negate for i64 {
+    fn negate(self) -> Value {
+        Value::from(Number::from(-self))
+    }
+}
+
+impl Negate for f64 {
+    fn negate(self) -> Value {
+        Value::from(Double::from(-self))
+    }
+}
+
+impl Negate for &str {
+    fn negate(self) -> Value {
+        Negate::negate(self.to_string())
+    }
+}
+
+impl Negate for String {
+    fn negate(self) -> Value {
+        Value::from(SimpleError::new(self).expect("resp! (invalid simple error)"))
+    }
+}
+
+pub fn simple_string(val: impl AsRef<[u8]>) -> Value {
+    let val = Bytes::copy_from_slice(val.as_ref());
+    Value::from(SimpleString::new(val).expect("resp! (invalid simple string)"))
+}
+
+pub fn big_number(digits: &str) -> Value {
+    let val = BigInt::from_str(digits).expect("resp! (invalid big number)");
+    Value::from(BigNumber::from(val))
+}
+
+pub fn verbatim(format: &str, val: impl AsRef<[u8]>) -> Value {
+    let format = <[u8; 3]>::try_from(format.as_bytes()).expect("resp! (invalid verbatim format)");
+    let val = Bytes::copy_from_slice(val.as_ref());
+    Value::from(VerbatimString::new(VerbatimFormat::from(format), val))
+}
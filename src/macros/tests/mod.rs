@@ -0,0 +1,71 @@
+use std::str::FromStr;
+
+use num_bigint::BigInt;
+
+use crate::{resp, value::*};
+
+#[test]
+fn test_leaves() {
+    assert_eq!(resp!(nil), Value::from(Null::default()));
+    assert_eq!(resp!(true), Value::from(Boolean::from(true)));
+    assert_eq!(resp!(42), Value::from(Number::from(42)));
+    assert_eq!(resp!(-42), Value::from(Number::from(-42)));
+    assert_eq!(resp!(3.5), Value::from(Double::from(3.5)));
+    assert_eq!(resp!(-3.5), Value::from(Double::from(-3.5)));
+    assert_eq!(resp!(+"OK"), Value::from(SimpleString::new("OK").unwrap()));
+    assert_eq!(
+        resp!(-"ERR msg"),
+        Value::from(SimpleError::new("ERR msg").unwrap())
+    );
+    assert_eq!(resp!(!"ERR msg"), Value::from(BlobError::new("ERR msg")));
+    assert_eq!(resp!("text"), Value::from(BlobString::from("text")));
+    assert_eq!(resp!(b"\xff"), Value::from(BlobString::from(&b"\xff"[..])));
+    assert_eq!(
+        resp!((big - 123456789012345678901234567890)),
+        Value::from(BigNumber::from(
+            BigInt::from_str("-123456789012345678901234567890").unwrap()
+        ))
+    );
+    assert_eq!(
+        resp!(verbatim!("mkd", "# title")),
+        Value::from(VerbatimString::mkd("# title"))
+    );
+}
+
+#[test]
+fn test_interpolation() {
+    let key = String::from("key");
+    let ttl = Number::from(10);
+
+    assert_eq!(
+        resp!([(BlobString::from(key.clone())), ttl, +(key.as_str()), -(format!("ERR {key}"))]),
+        Value::from(Array::from([
+            Value::from(BlobString::from("key")),
+            Value::from(Number::from(10)),
+            Value::from(SimpleString::new("key").unwrap()),
+            Value::from(SimpleError::new("ERR key").unwrap()),
+        ]))
+    );
+}
+
+#[test]
+fn test_aggregates() {
+    let raw = b"\
+                |1\r\n+ttl\r\n:3600\r\n\
+                %2\r\n\
+                +a\r\n*3\r\n:1\r\n,2.5\r\n_\r\n\
+                |1\r\n+hot\r\n#t\r\n$1\r\nb\r\n~2\r\n#f\r\n>1\r\n-ERR x\r\n\
+                ";
+
+    assert_eq!(
+        resp!(|{+"ttl" => 3600}| {
+            +"a" => [1, 2.5, nil],
+            |{+"hot" => true}| "b" => ~[false, >[-"ERR x"]],
+        }),
+        Value::parse(raw).unwrap().1
+    );
+
+    assert_eq!(resp!([]), Value::from(Array::from([])));
+    assert_eq!(resp!({}), Value::from(Map::from([])));
+    assert_eq!(resp!([[], {}]), resp!([[], {},]));
+}
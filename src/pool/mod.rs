@@ -0,0 +1,316 @@
+//! A pool of async connections to one server.
+
+#[cfg(test)]
+pub(crate) mod tests;
+
+use std::{
+    collections::VecDeque,
+    ops::{Deref, DerefMut},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail, Context};
+use tokio::{
+    sync::{OwnedSemaphorePermit, Semaphore},
+    time::{timeout, timeout_at, Instant},
+};
+
+use crate::{aio::Connection, cmd::Cmd, protocol::Hello, value::Value};
+
+/// Parameters of [`Pool::connect_with`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolOptions {
+    /// Sent on every new connection right after connecting.
+    pub hello: Option<Hello>,
+    /// Opened up front, and never closed for being idle. Connections closed for another
+    /// reason (broken, too old) are not replaced until [`Pool::get`] needs one.
+    pub min_size: usize,
+    /// Connections open at most, in use or not.
+    pub max_size: usize,
+    /// Idle connections above `min_size` are closed after this long. There is no background
+    /// task: they are closed by the next [`Pool::get`].
+    pub idle_timeout: Option<Duration>,
+    /// Connections are closed once this old, instead of going back to the pool.
+    pub max_lifetime: Option<Duration>,
+    /// How long [`Pool::get`] waits for a connection, opening it included.
+    pub checkout_timeout: Duration,
+    /// Whether idle connections are checked with a `PING` before being handed out.
+    pub health_check: bool,
+    /// How long resetting a returned connection may take before it is closed instead.
+    pub reset_timeout: Duration,
+}
+
+impl Default for PoolOptions {
+    fn default() -> Self {
+        Self {
+            hello: None,
+            min_size: 0,
+            max_size: 10,
+            idle_timeout: Some(Duration::from_secs(10 * 60)),
+            max_lifetime: Some(Duration::from_secs(30 * 60)),
+            checkout_timeout: Duration::from_secs(30),
+            health_check: true,
+            reset_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Hands out connections to one server, opening them as needed up to a limit.
+///
+/// Callers waiting for a connection are served in the order they asked.
+/// Connections go back to the pool when dropped, reset with [`Connection::reset`] if needed.
+/// Clones share the same connections.
+#[derive(Debug, Clone)]
+pub struct Pool {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    addr: String,
+    options: PoolOptions,
+    // one per connection in use (or being opened, checked or reset)
+    permits: Arc<Semaphore>,
+    // least recently used first
+    idle: Mutex<VecDeque<Idle>>,
+}
+
+#[derive(Debug)]
+struct Idle {
+    connection: Connection,
+    created: Instant,
+    since: Instant,
+}
+
+impl Pool {
+    pub async fn connect(addr: impl Into<String>) -> anyhow::Result<Self> {
+        Self::connect_with(addr, PoolOptions::default()).await
+    }
+
+    /// Opens `min_size` connections before returning.
+    pub async fn connect_with(
+        addr: impl Into<String>,
+        options: PoolOptions,
+    ) -> anyhow::Result<Self> {
+        if options.max_size == 0 || options.min_size > options.max_size {
+            bail!(
+                "Pool (invalid size: min {} max {})",
+                options.min_size,
+                options.max_size
+            );
+        }
+
+        let inner = Arc::new(Inner {
+            addr: addr.into(),
+            permits: Arc::new(Semaphore::new(options.max_size)),
+            idle: Mutex::new(VecDeque::new()),
+            options,
+        });
+
+        for _ in 0..inner.options.min_size {
+            let connection = inner.open().await?;
+            inner.put(connection, Instant::now());
+        }
+
+        Ok(Self { inner })
+    }
+
+    /// Waits for a connection, failing after [`PoolOptions::checkout_timeout`].
+    /// Idle connections failing the health check are closed and another one is tried.
+    pub async fn get(&self) -> anyhow::Result<PooledConnection> {
+        let deadline = Instant::now() + self.inner.options.checkout_timeout;
+
+        let permit = timeout_at(deadline, self.inner.permits.clone().acquire_owned())
+            .await
+            .map_err(|_| anyhow!("Pool (timed out waiting for a connection)"))?
+            .context("Pool (semaphore closed)")?;
+
+        while let Some(mut idle) = self.inner.take() {
+            if self.inner.options.health_check {
+                let ping = timeout_at(deadline, idle.connection.send(&Cmd::new("PING"))).await;
+
+                match ping {
+                    Ok(Ok(reply))
+                        if !matches!(reply, Value::SimpleError(_) | Value::BlobError(_)) => {}
+                    Ok(_) => continue,
+                    Err(_) => bail!("Pool (timed out checking a connection)"),
+                }
+            }
+
+            return Ok(PooledConnection::new(
+                &self.inner,
+                idle.connection,
+                idle.created,
+                permit,
+            ));
+        }
+
+        let connection = timeout_at(deadline, self.inner.open())
+            .await
+            .map_err(|_| anyhow!("Pool (timed out opening a connection)"))??;
+
+        Ok(PooledConnection::new(
+            &self.inner,
+            connection,
+            Instant::now(),
+            permit,
+        ))
+    }
+
+    /// Connections waiting in the pool.
+    pub fn idle(&self) -> usize {
+        self.inner.idle.lock().expect("pool lock poisoned").len()
+    }
+
+    /// Connections handed out (or being opened, checked or reset).
+    pub fn in_use(&self) -> usize {
+        self.inner.options.max_size - self.inner.permits.available_permits()
+    }
+}
+
+impl Inner {
+    async fn open(&self) -> anyhow::Result<Connection> {
+        let mut connection = Connection::connect(self.addr.as_str()).await?;
+
+        if let Some(hello) = self.options.hello.as_ref() {
+            connection.hello(hello).await?;
+        }
+
+        Ok(connection)
+    }
+
+    // The most recently used idle connection, once the expired ones are closed.
+    fn take(&self) -> Option<Idle> {
+        let mut idle = self.idle.lock().expect("pool lock poisoned");
+
+        idle.retain(|idle| !self.expired(idle.created));
+
+        // the least recently used go first, as long as enough connections stay open
+        // (not counting the caller, whose permit is taken already)
+        let in_use = self.options.max_size - self.permits.available_permits() - 1;
+        while idle.len() + in_use > self.options.min_size {
+            let timed_out = idle.front().is_some_and(|idle| {
+                self.options
+                    .idle_timeout
+                    .is_some_and(|timeout| idle.since.elapsed() >= timeout)
+            });
+
+            if !timed_out {
+                break;
+            }
+            idle.pop_front();
+        }
+
+        idle.pop_back()
+    }
+
+    fn put(&self, connection: Connection, created: Instant) {
+        if self.expired(created) || connection.protocol().is_broken() {
+            return;
+        }
+
+        self.idle
+            .lock()
+            .expect("pool lock poisoned")
+            .push_back(Idle {
+                connection,
+                created,
+                since: Instant::now(),
+            });
+    }
+
+    fn expired(&self, created: Instant) -> bool {
+        self.options
+            .max_lifetime
+            .is_some_and(|lifetime| created.elapsed() >= lifetime)
+    }
+}
+
+/// A connection borrowed from a [`Pool`], going back to it when dropped.
+#[derive(Debug)]
+pub struct PooledConnection {
+    connection: Option<Connection>,
+    created: Instant,
+    pool: Arc<Inner>,
+    permit: Option<OwnedSemaphorePermit>,
+}
+
+impl PooledConnection {
+    fn new(
+        pool: &Arc<Inner>,
+        connection: Connection,
+        created: Instant,
+        permit: OwnedSemaphorePermit,
+    ) -> Self {
+        Self {
+            connection: Some(connection),
+            created,
+            pool: pool.clone(),
+            permit: Some(permit),
+        }
+    }
+
+    /// Takes the connection out of the pool for good, making room for another one.
+    pub fn detach(mut self) -> Connection {
+        self.connection
+            .take()
+            .expect("connection is only taken once")
+    }
+}
+
+impl Deref for PooledConnection {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.connection
+            .as_ref()
+            .expect("connection is only taken once")
+    }
+}
+
+impl DerefMut for PooledConnection {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.connection
+            .as_mut()
+            .expect("connection is only taken once")
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        let (Some(mut connection), Some(permit)) = (self.connection.take(), self.permit.take())
+        else {
+            return;
+        };
+
+        // a reply still on its way (e.g. the caller gave up waiting) would go to the next user
+        if connection.protocol().in_flight() > 0 {
+            return;
+        }
+
+        let protocol = connection.protocol();
+        let dirty = protocol.is_subscribed() || protocol.in_transaction() || protocol.db() != 0;
+
+        if !dirty {
+            self.pool.put(connection, self.created);
+            return;
+        }
+
+        // resetting takes a round trip, the connection counts as in use until it is done
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+
+        // a server not answering would keep the permit forever
+        let (pool, created) = (self.pool.clone(), self.created);
+        runtime.spawn(async move {
+            let reset = timeout(pool.options.reset_timeout, connection.reset()).await;
+            if let Ok(Ok(())) = reset {
+                pool.put(connection, created);
+            }
+
+            drop(permit);
+        });
+    }
+}
@@ -0,0 +1,216 @@
+use bytes::Bytes;
+
+use super::*;
+use crate::{
+    aio::tests::{serve, serve_logged, take_log_of, Log},
+    resp,
+    value::{BlobString, Number, Value},
+};
+
+// Connections are numbered from 1 and tell their number on `ID`. `PING` fails on the first one.
+async fn server() -> (String, Log) {
+    serve_logged(|id| {
+        let mut subscriptions = 0;
+
+        move |args: &[Bytes]| {
+            let name = String::from_utf8_lossy(&args[0]).to_string();
+
+            let reply = match name.as_str() {
+                "ID" => Value::from(Number::from(id as i64)),
+                "PING" if id == 1 => resp!(-"ERR broken"),
+                "PING" => resp!(+"PONG"),
+                "SUBSCRIBE" => {
+                    subscriptions += 1;
+                    resp!([
+                        "subscribe",
+                        (BlobString::from(args[1].clone())),
+                        (Number::from(subscriptions))
+                    ])
+                }
+                "UNSUBSCRIBE" | "PUNSUBSCRIBE" | "SUNSUBSCRIBE" => {
+                    subscriptions = 0;
+                    resp!([(BlobString::from(name.to_lowercase().into_bytes())), nil, 0])
+                }
+                _ => resp!(+"OK"),
+            };
+
+            vec![reply]
+        }
+    })
+    .await
+}
+
+async fn id(connection: &mut Connection) -> Value {
+    connection.send(&Cmd::new("ID")).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_reset() {
+    let (addr, log) = server().await;
+    let options = PoolOptions {
+        max_size: 1,
+        ..PoolOptions::default()
+    };
+    let pool = Pool::connect_with(addr, options).await.unwrap();
+
+    // the first connection fails health checks, leave it behind
+    pool.get().await.unwrap().detach();
+
+    let mut connection = pool.get().await.unwrap();
+    assert_eq!(id(&mut connection).await, resp!(2));
+    drop(connection);
+    take_log_of(&log, 2);
+
+    // a clean connection goes straight back
+    let mut connection = pool.get().await.unwrap();
+    connection.send(&Cmd::new("SELECT").arg("3")).await.unwrap();
+    connection.send(&Cmd::new("MULTI")).await.unwrap();
+    drop(connection);
+
+    let mut connection = pool.get().await.unwrap();
+    assert_eq!(id(&mut connection).await, resp!(2));
+    assert_eq!(
+        take_log_of(&log, 2),
+        ["PING", "SELECT", "MULTI", "DISCARD", "SELECT", "PING", "ID"]
+    );
+
+    connection
+        .write(&Cmd::new("SUBSCRIBE").arg("ch"))
+        .await
+        .unwrap();
+    connection.next_push().await.unwrap();
+    assert!(connection.protocol().is_subscribed());
+    drop(connection);
+
+    let mut connection = pool.get().await.unwrap();
+    assert!(!connection.protocol().is_subscribed());
+    assert_eq!(id(&mut connection).await, resp!(2));
+    assert_eq!(
+        take_log_of(&log, 2),
+        [
+            "SUBSCRIBE",
+            "UNSUBSCRIBE",
+            "PUNSUBSCRIBE",
+            "SUNSUBSCRIBE",
+            "PING",
+            "PING",
+            "ID"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_reset_timeout() {
+    // never answers the `SELECT 0` resetting a connection
+    let addr = serve(|| {
+        |args: &[Bytes]| match (&args[0][..], args.get(1)) {
+            (b"SELECT", Some(db)) if db == "0" => vec![],
+            _ => vec![resp!(+"OK")],
+        }
+    })
+    .await;
+    let options = PoolOptions {
+        max_size: 1,
+        health_check: false,
+        reset_timeout: Duration::from_millis(20),
+        ..PoolOptions::default()
+    };
+    let pool = Pool::connect_with(addr, options).await.unwrap();
+
+    let mut connection = pool.get().await.unwrap();
+    connection.send(&Cmd::new("SELECT").arg("3")).await.unwrap();
+    drop(connection);
+    assert_eq!(pool.in_use(), 1);
+
+    // the connection is closed and its place freed
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!((pool.in_use(), pool.idle()), (0, 0));
+    assert!(pool.get().await.is_ok());
+}
+
+#[tokio::test]
+async fn test_waiting() {
+    let (addr, _) = server().await;
+    let options = PoolOptions {
+        max_size: 1,
+        checkout_timeout: Duration::from_millis(50),
+        health_check: false,
+        ..PoolOptions::default()
+    };
+    let pool = Pool::connect_with(addr, options).await.unwrap();
+
+    let connection = pool.get().await.unwrap();
+    assert_eq!(pool.in_use(), 1);
+    assert!(pool.get().await.is_err());
+
+    // served in the order they asked
+    let order = Arc::new(Mutex::new(vec![]));
+    let mut waiters = vec![];
+    for i in 0..3 {
+        let (pool, order) = (pool.clone(), order.clone());
+        waiters.push(tokio::spawn(async move {
+            let _connection = pool.get().await.unwrap();
+            order.lock().unwrap().push(i);
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }));
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+
+    drop(connection);
+    for waiter in waiters {
+        waiter.await.unwrap();
+    }
+
+    assert_eq!(*order.lock().unwrap(), [0, 1, 2]);
+    assert_eq!((pool.in_use(), pool.idle()), (0, 1));
+}
+
+#[tokio::test]
+async fn test_expiry() {
+    let (addr, _) = server().await;
+    let options = PoolOptions {
+        min_size: 2,
+        idle_timeout: Some(Duration::from_millis(20)),
+        max_lifetime: Some(Duration::from_millis(100)),
+        health_check: false,
+        ..PoolOptions::default()
+    };
+    let pool = Pool::connect_with(addr, options).await.unwrap();
+    assert_eq!(pool.idle(), 2);
+
+    // above the minimum idle connections time out
+    let mut connections = vec![];
+    for _ in 0..3 {
+        connections.push(pool.get().await.unwrap());
+    }
+    assert_eq!(id(&mut connections[2]).await, resp!(3));
+    connections.clear();
+    assert_eq!(pool.idle(), 3);
+
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    let mut connection = pool.get().await.unwrap();
+    assert_eq!(id(&mut connection).await, resp!(3));
+    assert_eq!(pool.idle(), 1);
+    drop(connection);
+
+    // too old connections do not go back, nor out
+    let connection = pool.get().await.unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    drop(connection);
+    assert_eq!(pool.idle(), 1);
+
+    let mut connection = pool.get().await.unwrap();
+    assert_eq!(id(&mut connection).await, resp!(4));
+    assert_eq!(pool.idle(), 0);
+}
+
+#[tokio::test]
+async fn test_invalid_size() {
+    let options = PoolOptions {
+        min_size: 2,
+        max_size: 1,
+        ..PoolOptions::default()
+    };
+
+    assert!(Pool::connect_with("127.0.0.1:0", options).await.is_err());
+}
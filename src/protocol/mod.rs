@@ -0,0 +1,516 @@
+//! Runtime agnostic client side of the protocol.
+//!
+//! [`ClientProtocol`] does no IO: feed it the bytes read from the socket with
+//! [`ClientProtocol::receive`], write out whatever [`ClientProtocol::poll_transmit`] returns
+//! and handle the [`Event`]s it produces.
+
+#[cfg(test)]
+pub(crate) mod tests;
+
+use std::collections::VecDeque;
+
+use anyhow::anyhow;
+use bytes::Bytes;
+
+use crate::{
+    cmd::Cmd,
+    value::{DecodeOptions, FrameScanner, Push, Value},
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Version {
+    Resp2,
+    #[default]
+    Resp3,
+}
+
+impl Version {
+    pub fn number(&self) -> u8 {
+        match self {
+            Self::Resp2 => 2,
+            Self::Resp3 => 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RequestId(u64);
+
+#[derive(Debug)]
+pub enum Event {
+    /// The reply to the command sent under the given id (server errors included).
+    Reply(RequestId, Value),
+    /// An out-of-band message: pub/sub traffic, client side caching invalidations, etc.
+    Push(Value),
+    /// The server accepted a `HELLO`.
+    ProtocolSwitched(Version),
+    /// A refused `HELLO` or a protocol violation.
+    /// After a violation the connection is broken and no more events are produced.
+    Error(anyhow::Error),
+}
+
+/// Parameters of the `HELLO` handshake.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Hello {
+    pub version: Version,
+    pub auth: Option<(Bytes, Bytes)>,
+    pub client_name: Option<Bytes>,
+}
+
+impl From<&Hello> for Cmd {
+    fn from(input: &Hello) -> Cmd {
+        let mut cmd = Cmd::new("HELLO").arg(input.version.number().to_string());
+
+        if let Some((username, password)) = input.auth.as_ref() {
+            cmd = cmd.arg("AUTH").arg(username.clone()).arg(password.clone());
+        }
+
+        if let Some(name) = input.client_name.as_ref() {
+            cmd = cmd.arg("SETNAME").arg(name.clone());
+        }
+
+        cmd
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pending {
+    Reply(RequestId, Option<Effect>),
+    Hello(Version),
+}
+
+// Commands changing the state of the session, applied once the server confirms them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Effect {
+    Multi,
+    /// `EXEC` or `DISCARD`.
+    Exec,
+    Select(i64),
+    Reset,
+}
+
+impl Effect {
+    fn of(cmd: &Cmd) -> Option<Self> {
+        match &cmd.name().to_ascii_lowercase()[..] {
+            b"multi" => Some(Self::Multi),
+            b"exec" | b"discard" => Some(Self::Exec),
+            b"select" => {
+                let db = std::str::from_utf8(cmd.args().get(1)?).ok()?;
+                db.parse().ok().map(Self::Select)
+            }
+            b"reset" => Some(Self::Reset),
+            _ => None,
+        }
+    }
+}
+
+// Commands confirmed with push messages (arrays in RESP2) instead of regular replies.
+static PUBSUB: &[&[u8]] = &[
+    b"subscribe",
+    b"unsubscribe",
+    b"psubscribe",
+    b"punsubscribe",
+    b"ssubscribe",
+    b"sunsubscribe",
+];
+
+// Kinds of messages a RESP2 connection receives while subscribed.
+static PUBSUB_MESSAGES: &[&[u8]] = &[b"message", b"pmessage", b"smessage"];
+
+#[derive(Debug)]
+pub struct ClientProtocol {
+    version: Version,
+    next_id: u64,
+    pending: VecDeque<Pending>,
+    // subscriptions as of the last confirmation
+    channels: usize,
+    patterns: usize,
+    shard_channels: usize,
+    // (un)subscribe commands sent and not fully confirmed yet: how many confirmations
+    // are expected, `None` when unsubscribing from all of a kind
+    unconfirmed: VecDeque<Option<usize>>,
+    transaction: bool,
+    db: i64,
+    // selected by a `SELECT` queued in the transaction, once `EXEC` succeeds
+    queued_db: Option<i64>,
+    read: Vec<u8>,
+    scanner: FrameScanner,
+    write: Vec<u8>,
+    events: VecDeque<Event>,
+    // push messages set aside while waiting for a reply
+    pushes: VecDeque<Value>,
+    broken: bool,
+    decode_options: DecodeOptions,
+}
+
+impl Default for ClientProtocol {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClientProtocol {
+    /// Every connection starts out speaking RESP2 until a `HELLO` says otherwise.
+    pub fn new() -> Self {
+        Self {
+            version: Version::Resp2,
+            next_id: 0,
+            pending: VecDeque::new(),
+            channels: 0,
+            patterns: 0,
+            shard_channels: 0,
+            unconfirmed: VecDeque::new(),
+            transaction: false,
+            db: 0,
+            queued_db: None,
+            read: vec![],
+            scanner: FrameScanner::new(),
+            write: vec![],
+            events: VecDeque::new(),
+            pushes: VecDeque::new(),
+            broken: false,
+            decode_options: DecodeOptions::default(),
+        }
+    }
+
+    /// Applies to every frame received from now on.
+    pub fn set_decode_options(&mut self, options: DecodeOptions) {
+        self.decode_options = options;
+    }
+
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Number of commands still waiting for a reply.
+    pub fn in_flight(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_broken(&self) -> bool {
+        self.broken
+    }
+
+    /// Gives up on the connection, e.g. after the server took too long to reply: a late
+    /// reply would be taken for the next request's. Marks it broken, like a protocol
+    /// violation does, and forgets the requests in flight along with unsent bytes.
+    pub fn abort(&mut self) {
+        self.broken = true;
+        self.pending.clear();
+        self.unconfirmed.clear();
+        self.read.clear();
+        self.write.clear();
+        self.scanner = FrameScanner::new();
+    }
+
+    /// Whether the last (un)subscribe confirmation left any subscription,
+    /// or an (un)subscribe command is still waiting for its own.
+    pub fn is_subscribed(&self) -> bool {
+        self.channels + self.patterns + self.shard_channels > 0 || !self.unconfirmed.is_empty()
+    }
+
+    /// Whether the server accepted a `MULTI` not followed by `EXEC` or `DISCARD` yet.
+    pub fn in_transaction(&self) -> bool {
+        self.transaction
+    }
+
+    /// The database selected by the last successful `SELECT`, 0 at first.
+    pub fn db(&self) -> i64 {
+        self.db
+    }
+
+    /// Queues a `HELLO`. Its reply is reported as [`Event::ProtocolSwitched`]
+    /// (or [`Event::Error`] if the server refuses it) instead of [`Event::Reply`].
+    pub fn hello(&mut self, hello: &Hello) {
+        self.queue(&Cmd::from(hello));
+        self.pending.push_back(Pending::Hello(hello.version));
+    }
+
+    /// Queues a command and returns the id its reply will be reported under.
+    ///
+    /// (Un)subscribe commands never get an [`Event::Reply`]:
+    /// the server confirms them with [`Event::Push`] messages instead.
+    pub fn send(&mut self, cmd: &Cmd) -> RequestId {
+        let id = RequestId(self.next_id);
+        self.next_id += 1;
+
+        self.queue(cmd);
+
+        match ClientProtocol::expects_reply(cmd) {
+            true => self.pending.push_back(Pending::Reply(id, Effect::of(cmd))),
+            false => self.unconfirmed.push_back(match cmd.args().len() {
+                1 => None,
+                len => Some(len - 1),
+            }),
+        }
+
+        id
+    }
+
+    /// Whether the command gets a regular reply, see [`ClientProtocol::send`].
+    pub fn expects_reply(cmd: &Cmd) -> bool {
+        !PUBSUB.contains(&&cmd.name().to_ascii_lowercase()[..])
+    }
+
+    /// Bytes which have to be written to the server, if any.
+    pub fn poll_transmit(&mut self) -> Option<Bytes> {
+        match self.write.is_empty() {
+            true => None,
+            false => Some(Bytes::from(std::mem::take(&mut self.write))),
+        }
+    }
+
+    /// The next event, push messages set aside by the `poll_*` helpers first.
+    pub fn poll_event(&mut self) -> Option<Event> {
+        match self.pushes.pop_front() {
+            Some(value) => Some(Event::Push(value)),
+            None => self.events.pop_front(),
+        }
+    }
+
+    /// The reply to the request `expected`, `None` until it has been received.
+    /// Replies have to be waited for in the order the requests were sent.
+    /// Push messages received in the meantime are set aside for [`ClientProtocol::poll_push`].
+    pub fn poll_reply(&mut self, expected: RequestId) -> Option<anyhow::Result<Value>> {
+        loop {
+            match self.events.pop_front()? {
+                Event::Reply(id, value) if id == expected => return Some(Ok(value)),
+                Event::Reply(..) => {
+                    return Some(Err(anyhow!("ClientProtocol (reply out of order)")))
+                }
+                Event::Push(value) => self.pushes.push_back(value),
+                Event::Error(err) => return Some(Err(err)),
+                Event::ProtocolSwitched(_) => {}
+            }
+        }
+    }
+
+    /// The outcome of the `HELLO` queued with [`ClientProtocol::hello`], `None` until
+    /// it has been received. Push messages are set aside like with [`ClientProtocol::poll_reply`].
+    pub fn poll_hello(&mut self) -> Option<anyhow::Result<()>> {
+        loop {
+            match self.events.pop_front()? {
+                Event::ProtocolSwitched(_) => return Some(Ok(())),
+                Event::Error(err) => return Some(Err(err)),
+                Event::Push(value) => self.pushes.push_back(value),
+                Event::Reply(..) => return Some(Err(anyhow!("ClientProtocol (unexpected reply)"))),
+            }
+        }
+    }
+
+    /// The next push message, `None` until one has been received.
+    pub fn poll_push(&mut self) -> Option<anyhow::Result<Value>> {
+        if let Some(value) = self.pushes.pop_front() {
+            return Some(Ok(value));
+        }
+
+        loop {
+            match self.events.pop_front()? {
+                Event::Push(value) => return Some(Ok(value)),
+                Event::Error(err) => return Some(Err(err)),
+                Event::ProtocolSwitched(_) => {}
+                Event::Reply(..) => return Some(Err(anyhow!("ClientProtocol (unexpected reply)"))),
+            }
+        }
+    }
+
+    /// Drops the push messages set aside and not read yet.
+    pub fn clear_pushes(&mut self) {
+        self.pushes.clear();
+    }
+
+    /// Feeds bytes read from the server. Partial frames are kept until the rest arrives.
+    pub fn receive(&mut self, bytes: &[u8]) {
+        if self.broken {
+            return;
+        }
+
+        self.read.extend_from_slice(bytes);
+
+        let mut offset = 0;
+        loop {
+            // frames are only parsed once complete, the scan resumes with the next bytes
+            let decoded = self
+                .scanner
+                .scan(&self.read[offset..])
+                .and_then(|len| match len {
+                    Some(len) => {
+                        Value::decode_with(&self.read[offset..offset + len], &self.decode_options)
+                    }
+                    None => Ok(None),
+                });
+
+            match decoded {
+                Ok(Some((value, len))) => {
+                    offset += len;
+                    self.dispatch(value);
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    self.fail(err.context("ClientProtocol (malformed frame)"));
+                    return;
+                }
+            }
+
+            if self.broken {
+                return;
+            }
+        }
+
+        self.read.drain(..offset);
+    }
+
+    fn queue(&mut self, cmd: &Cmd) {
+        match Bytes::try_from(cmd) {
+            Ok(bytes) => self.write.extend_from_slice(&bytes),
+            Err(err) => self.fail(err.context("ClientProtocol (Bytes::try_from)")),
+        }
+    }
+
+    fn dispatch(&mut self, value: Value) {
+        let value = match value {
+            Value::Push(push) => {
+                self.count_subscriptions(&push);
+                return self.events.push_back(Event::Push(Value::from(push)));
+            }
+            value if self.version == Version::Resp2 && self.is_subscribed() => {
+                match self.resp2_push(value) {
+                    Ok(push) => return self.events.push_back(Event::Push(push)),
+                    Err(value) => value,
+                }
+            }
+            value => value,
+        };
+
+        match self.pending.pop_front() {
+            Some(Pending::Reply(id, effect)) => {
+                if let Some(effect) = effect {
+                    self.apply(effect, &value);
+                }
+
+                self.events.push_back(Event::Reply(id, value));
+            }
+            Some(Pending::Hello(version)) => match value {
+                Value::Map(_) | Value::Array(_) => {
+                    self.version = version;
+                    self.events.push_back(Event::ProtocolSwitched(version));
+                }
+                value => {
+                    let err = anyhow!(
+                        "ClientProtocol (HELLO {} refused: {value})",
+                        version.number()
+                    );
+                    self.events.push_back(Event::Error(err));
+                }
+            },
+            None => self.fail(anyhow!("ClientProtocol (unexpected reply: {value})")),
+        }
+    }
+
+    // In RESP2 pub/sub traffic is made of plain arrays which have to be told apart by content.
+    fn resp2_push(&mut self, value: Value) -> Result<Value, Value> {
+        let push = match &value {
+            Value::Array(inner) => inner.val().iter().cloned().collect::<Push>(),
+            _ => return Err(value),
+        };
+
+        let kind = push
+            .kind()
+            .map(<[u8]>::to_ascii_lowercase)
+            .unwrap_or_default();
+
+        let is_push = match PUBSUB.contains(&&kind[..]) {
+            true => self.count_subscriptions(&push),
+            false => PUBSUB_MESSAGES.contains(&&kind[..]),
+        };
+
+        match is_push {
+            true => Ok(Value::from(push)),
+            false => Err(value),
+        }
+    }
+
+    // (Un)subscribe confirmations carry the number of subscriptions left: channels and
+    // patterns together, shard channels on their own. Returns whether `push` is one of them.
+    fn count_subscriptions(&mut self, push: &Push) -> bool {
+        let kind = push
+            .kind()
+            .map(<[u8]>::to_ascii_lowercase)
+            .unwrap_or_default();
+
+        let (channel, count) = match (PUBSUB.contains(&&kind[..]), push.val()) {
+            (true, [_, channel, Value::Number(count), ..]) => {
+                (channel, usize::try_from(count.val()).unwrap_or_default())
+            }
+            _ => return false,
+        };
+
+        let left = match &kind[..] {
+            b"subscribe" | b"unsubscribe" => {
+                self.channels = count.saturating_sub(self.patterns);
+                self.channels
+            }
+            b"psubscribe" | b"punsubscribe" => {
+                self.patterns = count.saturating_sub(self.channels);
+                self.patterns
+            }
+            _ => {
+                self.shard_channels = count;
+                self.shard_channels
+            }
+        };
+
+        if let Some(expected) = self.unconfirmed.front_mut() {
+            let done = match expected {
+                Some(expected) => {
+                    *expected = expected.saturating_sub(1);
+                    *expected == 0
+                }
+                None => left == 0 || matches!(channel, Value::Null(_)),
+            };
+
+            if done {
+                self.unconfirmed.pop_front();
+            }
+        }
+
+        true
+    }
+
+    fn apply(&mut self, effect: Effect, reply: &Value) {
+        let ok = !matches!(reply, Value::SimpleError(_) | Value::BlobError(_));
+
+        match effect {
+            Effect::Multi if ok => self.transaction = true,
+            // ends the transaction even when aborted
+            Effect::Exec => {
+                let db = self.queued_db.take();
+                if let (Some(db), Value::Array(_)) = (db, reply) {
+                    self.db = db;
+                }
+
+                self.transaction = false;
+            }
+            Effect::Select(db) if ok && self.transaction => self.queued_db = Some(db),
+            Effect::Select(db) if ok => self.db = db,
+            Effect::Reset if ok => {
+                self.version = Version::Resp2;
+                self.channels = 0;
+                self.patterns = 0;
+                self.shard_channels = 0;
+                self.unconfirmed.clear();
+                self.transaction = false;
+                self.db = 0;
+                self.queued_db = None;
+            }
+            _ => {}
+        }
+    }
+
+    fn fail(&mut self, err: anyhow::Error) {
+        self.broken = true;
+        self.read.clear();
+        self.scanner = FrameScanner::new();
+        self.events.push_back(Event::Error(err));
+    }
+}
@@ -0,0 +1,309 @@
+use super::*;
+use crate::value::{BlobString, Map, Number, SimpleError, SimpleString};
+
+fn events(protocol: &mut ClientProtocol) -> Vec<Event> {
+    std::iter::from_fn(|| protocol.poll_event()).collect()
+}
+
+fn hello_reply() -> &'static [u8] {
+    b"%1\r\n$5\r\nproto\r\n:3\r\n"
+}
+
+#[test]
+fn test_reply_correlation() {
+    let mut protocol = ClientProtocol::new();
+
+    let get = protocol.send(&Cmd::new("GET").arg("key"));
+    let incr = protocol.send(&Cmd::new("INCR").arg("key"));
+
+    assert_eq!(
+        protocol.poll_transmit().unwrap(),
+        Bytes::from_static(b"*2\r\n$3\r\nGET\r\n$3\r\nkey\r\n*2\r\n$4\r\nINCR\r\n$3\r\nkey\r\n")
+    );
+    assert_eq!(protocol.poll_transmit(), None);
+    assert_eq!(protocol.in_flight(), 2);
+
+    // replies may arrive split at any byte
+    protocol.receive(b"$3\r\nv");
+    assert!(events(&mut protocol).is_empty());
+
+    protocol.receive(b"al\r\n-ERR not an integer\r\n");
+
+    match &events(&mut protocol)[..] {
+        [Event::Reply(id1, v1), Event::Reply(id2, v2)] => {
+            assert_eq!((*id1, v1), (get, &Value::from(BlobString::from("val"))));
+            assert_eq!(
+                (*id2, v2),
+                (
+                    incr,
+                    &Value::from(SimpleError::new("ERR not an integer").unwrap())
+                )
+            );
+        }
+        events => panic!("unexpected events: {events:?}"),
+    }
+
+    assert_eq!(protocol.in_flight(), 0);
+}
+
+#[test]
+fn test_hello() {
+    let mut protocol = ClientProtocol::new();
+    assert_eq!(protocol.version(), Version::Resp2);
+
+    protocol.hello(&Hello {
+        version: Version::Resp3,
+        auth: Some((Bytes::from("user"), Bytes::from("pass"))),
+        client_name: Some(Bytes::from("app")),
+    });
+
+    assert_eq!(
+        protocol.poll_transmit().unwrap(),
+        Bytes::try_from(Cmd::from_iter([
+            "HELLO", "3", "AUTH", "user", "pass", "SETNAME", "app"
+        ]))
+        .unwrap()
+    );
+
+    protocol.receive(hello_reply());
+
+    assert!(matches!(
+        &events(&mut protocol)[..],
+        [Event::ProtocolSwitched(Version::Resp3)]
+    ));
+    assert_eq!(protocol.version(), Version::Resp3);
+}
+
+#[test]
+fn test_hello_refused() {
+    let mut protocol = ClientProtocol::new();
+
+    protocol.hello(&Hello::default());
+    protocol.receive(b"-NOPROTO unsupported protocol version\r\n");
+
+    assert!(matches!(&events(&mut protocol)[..], [Event::Error(_)]));
+    assert_eq!(protocol.version(), Version::Resp2);
+    assert!(!protocol.is_broken());
+}
+
+#[test]
+fn test_push() {
+    let mut protocol = ClientProtocol::new();
+    protocol.hello(&Hello::default());
+    protocol.receive(hello_reply());
+    events(&mut protocol);
+
+    protocol.send(&Cmd::new("SUBSCRIBE").arg("ch"));
+    let get = protocol.send(&Cmd::new("GET").arg("key"));
+
+    protocol.receive(b">3\r\n$9\r\nsubscribe\r\n$2\r\nch\r\n:1\r\n");
+    protocol.receive(b">3\r\n$7\r\nmessage\r\n$2\r\nch\r\n$2\r\nhi\r\n");
+    protocol.receive(b"_\r\n");
+
+    match &events(&mut protocol)[..] {
+        [Event::Push(Value::Push(subscribe)), Event::Push(Value::Push(message)), Event::Reply(id, _)] =>
+        {
+            assert_eq!(subscribe.kind(), Some(&b"subscribe"[..]));
+            assert_eq!(message.kind(), Some(&b"message"[..]));
+            assert_eq!(*id, get);
+        }
+        events => panic!("unexpected events: {events:?}"),
+    }
+}
+
+#[test]
+fn test_poll_reply() {
+    let mut protocol = ClientProtocol::new();
+    protocol.hello(&Hello::default());
+    assert!(protocol.poll_hello().is_none());
+    protocol.receive(hello_reply());
+    assert!(protocol.poll_hello().unwrap().is_ok());
+
+    let get = protocol.send(&Cmd::new("GET").arg("key"));
+    let incr = protocol.send(&Cmd::new("INCR").arg("key"));
+    assert!(protocol.poll_reply(get).is_none());
+
+    // pushes are set aside until asked for
+    protocol.receive(b">2\r\n$10\r\ninvalidate\r\n*1\r\n$3\r\nkey\r\n_\r\n:1\r\n");
+    assert!(matches!(protocol.poll_reply(get), Some(Ok(Value::Null(_)))));
+    assert_eq!(
+        protocol.poll_reply(incr).unwrap().unwrap(),
+        Value::from(Number::from(1))
+    );
+
+    let push = protocol.poll_push().unwrap().unwrap();
+    assert!(matches!(push, Value::Push(push) if push.kind() == Some(&b"invalidate"[..])));
+    assert!(protocol.poll_push().is_none());
+
+    // replies are expected in order
+    protocol.send(&Cmd::new("GET").arg("key"));
+    let second = protocol.send(&Cmd::new("GET").arg("key"));
+    protocol.receive(b"_\r\n");
+    assert!(protocol.poll_reply(second).unwrap().is_err());
+}
+
+#[test]
+fn test_resp2_pubsub() {
+    let mut protocol = ClientProtocol::new();
+
+    // arrays looking like messages are regular replies until subscribed
+    let lrange = protocol.send(&Cmd::new("LRANGE").arg("l").arg("0").arg("-1"));
+    protocol.receive(b"*3\r\n$7\r\nmessage\r\n$2\r\nch\r\n$2\r\nhi\r\n");
+    assert!(matches!(&events(&mut protocol)[..], [Event::Reply(id, _)] if *id == lrange));
+
+    protocol.send(&Cmd::new("SUBSCRIBE").arg("ch"));
+    protocol.receive(b"*3\r\n$9\r\nsubscribe\r\n$2\r\nch\r\n:1\r\n");
+    protocol.receive(b"*3\r\n$7\r\nmessage\r\n$2\r\nch\r\n$2\r\nhi\r\n");
+
+    let ping = protocol.send(&Cmd::new("PING"));
+    protocol.receive(b"*2\r\n$4\r\npong\r\n$0\r\n\r\n");
+
+    protocol.send(&Cmd::new("UNSUBSCRIBE"));
+    protocol.receive(b"*3\r\n$11\r\nunsubscribe\r\n$2\r\nch\r\n:0\r\n");
+
+    let get = protocol.send(&Cmd::new("GET").arg("key"));
+    protocol.receive(b"*3\r\n$7\r\nmessage\r\n$2\r\nch\r\n$2\r\nhi\r\n");
+
+    match &events(&mut protocol)[..] {
+        [Event::Push(Value::Push(_)), Event::Push(Value::Push(message)), Event::Reply(id1, _), Event::Push(Value::Push(_)), Event::Reply(id2, reply)] =>
+        {
+            assert_eq!(message.kind(), Some(&b"message"[..]));
+            assert_eq!(*id1, ping);
+            assert_eq!(*id2, get);
+            assert!(matches!(reply, Value::Array(_)));
+        }
+        events => panic!("unexpected events: {events:?}"),
+    }
+}
+
+#[test]
+fn test_resp2_nulls() {
+    let mut protocol = ClientProtocol::new();
+
+    protocol.send(&Cmd::new("GET").arg("missing"));
+    protocol.send(&Cmd::new("EXEC"));
+    protocol.receive(b"$-1\r\n*-1\r\n");
+
+    assert!(matches!(
+        &events(&mut protocol)[..],
+        [
+            Event::Reply(_, Value::Null(_)),
+            Event::Reply(_, Value::Null(_))
+        ]
+    ));
+}
+
+#[test]
+fn test_broken() {
+    let mut protocol = ClientProtocol::new();
+
+    protocol.receive(b"+unsolicited\r\n");
+    assert!(matches!(&events(&mut protocol)[..], [Event::Error(_)]));
+    assert!(protocol.is_broken());
+
+    let mut protocol = ClientProtocol::new();
+    protocol.send(&Cmd::new("PING"));
+    protocol.receive(b"?garbage\r\n+PONG\r\n");
+
+    assert!(matches!(&events(&mut protocol)[..], [Event::Error(_)]));
+    protocol.receive(b"+PONG\r\n");
+    assert!(events(&mut protocol).is_empty());
+}
+
+#[test]
+fn test_reply_values() {
+    let mut protocol = ClientProtocol::new();
+
+    protocol.send(&Cmd::new("CONFIG").arg("GET").arg("maxmemory"));
+    protocol.send(&Cmd::new("DBSIZE"));
+    protocol.send(&Cmd::new("PING"));
+    protocol.receive(b"%1\r\n$9\r\nmaxmemory\r\n$1\r\n0\r\n:42\r\n+PONG\r\n");
+
+    let values = events(&mut protocol)
+        .into_iter()
+        .map(|event| match event {
+            Event::Reply(_, value) => value,
+            event => panic!("unexpected event: {event:?}"),
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        values,
+        vec![
+            Value::from(Map::from([(
+                Value::from(BlobString::from("maxmemory")),
+                Value::from(BlobString::from("0"))
+            )])),
+            Value::from(Number::from(42)),
+            Value::from(SimpleString::new("PONG").unwrap()),
+        ]
+    );
+}
+
+#[test]
+fn test_session_state() {
+    let mut protocol = ClientProtocol::new();
+
+    // changes count once the server confirms them
+    protocol.send(&Cmd::new("SELECT").arg("2"));
+    protocol.send(&Cmd::new("select").arg("3"));
+    assert_eq!(protocol.db(), 0);
+    protocol.receive(b"+OK\r\n-ERR DB index is out of range\r\n");
+    assert_eq!(protocol.db(), 2);
+
+    protocol.send(&Cmd::new("MULTI"));
+    protocol.send(&Cmd::new("SELECT").arg("4"));
+    protocol.receive(b"+OK\r\n+QUEUED\r\n");
+    assert!(protocol.in_transaction());
+    assert_eq!(protocol.db(), 2);
+
+    protocol.send(&Cmd::new("EXEC"));
+    protocol.receive(b"*1\r\n+OK\r\n");
+    assert!(!protocol.in_transaction());
+    assert_eq!(protocol.db(), 4);
+
+    protocol.send(&Cmd::new("MULTI"));
+    protocol.send(&Cmd::new("SELECT").arg("5"));
+    protocol.send(&Cmd::new("DISCARD"));
+    protocol.receive(b"+OK\r\n+QUEUED\r\n+OK\r\n");
+    assert!(!protocol.in_transaction());
+    assert_eq!(protocol.db(), 4);
+
+    protocol.send(&Cmd::new("SUBSCRIBE").arg("a").arg("b"));
+    assert!(protocol.is_subscribed());
+    protocol.receive(b"*3\r\n$9\r\nsubscribe\r\n$1\r\na\r\n:1\r\n");
+    protocol.receive(b"*3\r\n$9\r\nsubscribe\r\n$1\r\nb\r\n:2\r\n");
+
+    // the last confirmation of one command may leave nothing, not the last of all
+    protocol.send(&Cmd::new("UNSUBSCRIBE"));
+    protocol.send(&Cmd::new("PUNSUBSCRIBE"));
+    let ping = protocol.send(&Cmd::new("PING"));
+    protocol.receive(b"*3\r\n$11\r\nunsubscribe\r\n$1\r\na\r\n:1\r\n");
+    protocol.receive(b"*3\r\n$11\r\nunsubscribe\r\n$1\r\nb\r\n:0\r\n");
+    assert!(protocol.is_subscribed());
+    protocol.receive(b"*3\r\n$12\r\npunsubscribe\r\n*-1\r\n:0\r\n");
+    assert!(!protocol.is_subscribed());
+    protocol.receive(b"+PONG\r\n");
+    assert!(matches!(
+        &events(&mut protocol)[..],
+        [.., Event::Push(_), Event::Push(_), Event::Push(_), Event::Reply(id, _)] if *id == ping
+    ));
+
+    protocol.send(&Cmd::new("SUBSCRIBE").arg("a"));
+    protocol.receive(b"*3\r\n$9\r\nsubscribe\r\n$1\r\na\r\n:1\r\n");
+    protocol.send(&Cmd::new("RESET"));
+    protocol.receive(b"+RESET\r\n");
+    assert!(!protocol.is_subscribed());
+    assert_eq!(protocol.db(), 0);
+    assert_eq!(protocol.version(), Version::Resp2);
+
+    // RESP3 confirmations are counted too
+    protocol.hello(&Hello::default());
+    protocol.receive(hello_reply());
+    protocol.send(&Cmd::new("SUBSCRIBE").arg("a"));
+    protocol.receive(b">3\r\n$9\r\nsubscribe\r\n$1\r\na\r\n:1\r\n");
+    assert!(protocol.is_subscribed());
+    protocol.send(&Cmd::new("UNSUBSCRIBE"));
+    protocol.receive(b">3\r\n$11\r\nunsubscribe\r\n$1\r\na\r\n:0\r\n");
+    assert!(!protocol.is_subscribed());
+}
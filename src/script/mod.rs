@@ -0,0 +1,222 @@
+//! Lua scripts and functions.
+
+#[cfg(test)]
+pub(crate) mod tests;
+
+use anyhow::{bail, Context};
+use bytes::Bytes;
+use sha1_smol::Sha1;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::{aio::Connection, cmd::Cmd, error::ErrorKind, value::FromValue};
+
+/// A Lua script, run by its SHA1 so the body is only sent when the server does not know it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Script {
+    body: Bytes,
+    hash: String,
+}
+
+/// A call to a [`Script`] with its keys and arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invocation<'a> {
+    script: &'a Script,
+    keys: Vec<Bytes>,
+    args: Vec<Bytes>,
+}
+
+impl Script {
+    pub fn new(body: impl Into<Bytes>) -> Self {
+        let body = body.into();
+        let hash = Sha1::from(&body).digest().to_string();
+
+        Self { body, hash }
+    }
+
+    pub fn body(&self) -> &Bytes {
+        &self.body
+    }
+
+    /// The SHA1 of the body in hex, as `EVALSHA` wants it.
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    pub fn invocation(&self) -> Invocation<'_> {
+        Invocation {
+            script: self,
+            keys: vec![],
+            args: vec![],
+        }
+    }
+
+    pub fn key(&self, key: impl Into<Bytes>) -> Invocation<'_> {
+        self.invocation().key(key)
+    }
+
+    pub fn arg(&self, arg: impl Into<Bytes>) -> Invocation<'_> {
+        self.invocation().arg(arg)
+    }
+
+    /// Loads the script ahead of time with `SCRIPT LOAD`.
+    pub async fn load<S>(&self, connection: &mut Connection<S>) -> anyhow::Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let cmd = Cmd::new("SCRIPT").arg("LOAD").arg(self.body.clone());
+        let hash = String::from_value(connection.send(&cmd).await?).context("Script (LOAD)")?;
+
+        if !hash.eq_ignore_ascii_case(&self.hash) {
+            bail!("Script (server hashed the script as {hash})");
+        }
+
+        Ok(())
+    }
+}
+
+impl Invocation<'_> {
+    pub fn key(mut self, key: impl Into<Bytes>) -> Self {
+        self.keys.push(key.into());
+        self
+    }
+
+    pub fn arg(mut self, arg: impl Into<Bytes>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn evalsha_cmd(&self) -> Cmd {
+        call(
+            Cmd::new("EVALSHA").arg(self.script.hash.clone()),
+            &self.keys,
+            &self.args,
+        )
+    }
+
+    pub fn eval_cmd(&self) -> Cmd {
+        call(
+            Cmd::new("EVAL").arg(self.script.body.clone()),
+            &self.keys,
+            &self.args,
+        )
+    }
+
+    /// Runs the script with `EVALSHA`, sending the body with `EVAL` if the server
+    /// replies `NOSCRIPT`.
+    pub async fn invoke<T, S>(&self, connection: &mut Connection<S>) -> anyhow::Result<T>
+    where
+        T: FromValue,
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let mut reply = connection.send(&self.evalsha_cmd()).await?;
+
+        if reply
+            .server_error()
+            .is_some_and(|err| err.kind() == &ErrorKind::NoScript)
+        {
+            reply = connection.send(&self.eval_cmd()).await?;
+        }
+
+        T::from_value(reply).context("Script (reply)")
+    }
+}
+
+/// A library of functions, e.g. starting with `#!lua name=mylib`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Library {
+    code: Bytes,
+}
+
+impl Library {
+    pub fn new(code: impl Into<Bytes>) -> Self {
+        Self { code: code.into() }
+    }
+
+    pub fn code(&self) -> &Bytes {
+        &self.code
+    }
+
+    pub fn load_cmd(&self) -> Cmd {
+        Cmd::new("FUNCTION")
+            .arg("LOAD")
+            .arg("REPLACE")
+            .arg(self.code.clone())
+    }
+
+    /// Loads the library with `FUNCTION LOAD REPLACE`, returning its name.
+    pub async fn load<S>(&self, connection: &mut Connection<S>) -> anyhow::Result<String>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let reply = connection.send(&self.load_cmd()).await?;
+
+        String::from_value(reply).context("Library (FUNCTION LOAD)")
+    }
+}
+
+/// A call to a function of a loaded [`Library`] with `FCALL` or `FCALL_RO`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    name: Bytes,
+    keys: Vec<Bytes>,
+    args: Vec<Bytes>,
+}
+
+impl Function {
+    pub fn new(name: impl Into<Bytes>) -> Self {
+        Self {
+            name: name.into(),
+            keys: vec![],
+            args: vec![],
+        }
+    }
+
+    pub fn key(mut self, key: impl Into<Bytes>) -> Self {
+        self.keys.push(key.into());
+        self
+    }
+
+    pub fn arg(mut self, arg: impl Into<Bytes>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// `FCALL`, or `FCALL_RO` for functions flagged `no-writes`.
+    pub fn cmd(&self, read_only: bool) -> Cmd {
+        let name = if read_only { "FCALL_RO" } else { "FCALL" };
+
+        call(
+            Cmd::new(name).arg(self.name.clone()),
+            &self.keys,
+            &self.args,
+        )
+    }
+
+    pub async fn call<T, S>(&self, connection: &mut Connection<S>) -> anyhow::Result<T>
+    where
+        T: FromValue,
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let reply = connection.send(&self.cmd(false)).await?;
+
+        T::from_value(reply).context("Function (FCALL)")
+    }
+
+    /// Calls the function as read only, which replicas accept too.
+    pub async fn call_ro<T, S>(&self, connection: &mut Connection<S>) -> anyhow::Result<T>
+    where
+        T: FromValue,
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let reply = connection.send(&self.cmd(true)).await?;
+
+        T::from_value(reply).context("Function (FCALL_RO)")
+    }
+}
+
+// `<cmd> <target> numkeys key... arg...`
+fn call(cmd: Cmd, keys: &[Bytes], args: &[Bytes]) -> Cmd {
+    let cmd = cmd.arg(keys.len().to_string());
+
+    keys.iter().chain(args).cloned().fold(cmd, Cmd::arg)
+}
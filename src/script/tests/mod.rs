@@ -0,0 +1,139 @@
+use std::collections::HashSet;
+
+use super::*;
+use crate::{
+    aio::tests::{serve_logged, take_log, Log},
+    resp,
+    value::{Array, BlobString, Value},
+};
+
+fn hex(data: &[u8]) -> String {
+    Script::new(Bytes::copy_from_slice(data)).hash().to_string()
+}
+
+// Knows scripts once run with EVAL or loaded, and returns their keys and arguments.
+// Functions return their name, libraries the name on their first line.
+async fn server() -> (String, Log) {
+    serve_logged(|_| {
+        let mut scripts = HashSet::new();
+
+        move |args: &[Bytes]| {
+            let name = String::from_utf8_lossy(&args[0]).to_uppercase();
+
+            let reply = match name.as_str() {
+                "EVALSHA" if !scripts.contains(&args[1]) => {
+                    resp!(-"NOSCRIPT No matching script. Please use EVAL.")
+                }
+                "EVALSHA" | "EVAL" | "FCALL" | "FCALL_RO" => {
+                    if name == "EVAL" {
+                        scripts.insert(Bytes::from(hex(&args[1])));
+                    }
+
+                    let args = args[3..]
+                        .iter()
+                        .map(|arg| Value::from(BlobString::from(arg.clone())))
+                        .collect::<Vec<_>>();
+                    Value::from(Array::from(args))
+                }
+                "SCRIPT" => {
+                    let hash = hex(&args[2]);
+                    scripts.insert(Bytes::from(hash.clone()));
+                    resp!(BlobString::from(hash))
+                }
+                "FUNCTION" => {
+                    let code = String::from_utf8_lossy(&args[3]).to_string();
+                    match code
+                        .lines()
+                        .next()
+                        .and_then(|line| line.strip_prefix("#!lua name="))
+                    {
+                        Some(name) => resp!(BlobString::from(name.to_string())),
+                        None => resp!(-"ERR Missing library metadata"),
+                    }
+                }
+                _ => resp!(-"ERR unknown command"),
+            };
+
+            vec![reply]
+        }
+    })
+    .await
+}
+
+#[test]
+fn test_hash() {
+    assert_eq!(
+        Script::new("").hash(),
+        "da39a3ee5e6b4b0d3255bfef95601890afd80709"
+    );
+    assert_eq!(
+        Script::new("return 1").hash(),
+        "e0e1f9fabfc9d4800c877a703b823ac0578ff8db"
+    );
+}
+
+#[test]
+fn test_cmds() {
+    let script = Script::new("return KEYS[1]");
+    let invocation = script.key("a").key("b").arg("1");
+
+    let cmd = invocation.evalsha_cmd();
+    assert_eq!(cmd.name(), b"EVALSHA");
+    assert_eq!(cmd.args()[1], script.hash());
+    assert_eq!(&cmd.args()[2..], ["2", "a", "b", "1"]);
+    assert_eq!(invocation.eval_cmd().args()[1], "return KEYS[1]");
+
+    let function = Function::new("f").arg("x");
+    assert_eq!(function.cmd(false).args(), ["FCALL", "f", "0", "x"]);
+    assert_eq!(function.cmd(true).name(), b"FCALL_RO");
+}
+
+#[tokio::test]
+async fn test_script() {
+    let (addr, log) = server().await;
+    let mut connection = Connection::connect(addr).await.unwrap();
+    let script = Script::new("return {KEYS[1], ARGV[1]}");
+
+    // unknown at first, then cached
+    for _ in 0..2 {
+        let invocation = script.key("k").arg("v");
+        let reply = invocation
+            .invoke::<(String, String), _>(&mut connection)
+            .await;
+        assert_eq!(reply.unwrap(), ("k".to_string(), "v".to_string()));
+    }
+    assert_eq!(take_log(&log), ["EVALSHA", "EVAL", "EVALSHA"]);
+
+    let other = Script::new("return ARGV");
+    other.load(&mut connection).await.unwrap();
+    let reply = other
+        .invocation()
+        .invoke::<Vec<String>, _>(&mut connection)
+        .await;
+    assert!(reply.unwrap().is_empty());
+    assert_eq!(take_log(&log), ["SCRIPT", "EVALSHA"]);
+}
+
+#[tokio::test]
+async fn test_function() {
+    let (addr, log) = server().await;
+    let mut connection = Connection::connect(addr).await.unwrap();
+
+    let library = Library::new("#!lua name=mylib\nredis.register_function('f', ...)");
+    assert_eq!(library.load(&mut connection).await.unwrap(), "mylib");
+    assert!(Library::new("return 1")
+        .load(&mut connection)
+        .await
+        .is_err());
+
+    let function = Function::new("f").key("k").arg("v");
+    let reply = function.call::<Vec<String>, _>(&mut connection).await;
+    assert_eq!(reply.unwrap(), ["k", "v"]);
+    let reply = function.call_ro::<Value, _>(&mut connection).await;
+    assert_eq!(reply.unwrap(), resp!(["k", "v"]));
+
+    assert_eq!(
+        take_log(&log),
+        ["FUNCTION", "FUNCTION", "FCALL", "FCALL_RO"]
+    );
+}
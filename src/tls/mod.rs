@@ -0,0 +1,206 @@
+//! TLS over rustls, behind the `tls` feature.
+
+#[cfg(test)]
+pub(crate) mod tests;
+
+use std::{path::Path, sync::Arc};
+
+use anyhow::{bail, Context};
+use once_cell::sync::Lazy;
+use rustls::{
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{self, CryptoProvider},
+    pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer, ServerName, UnixTime},
+    server::WebPkiClientVerifier,
+    ClientConfig, DigitallySignedStruct, RootCertStore, ServerConfig, SignatureScheme,
+};
+use tokio::net::TcpStream;
+use tokio_rustls::{client::TlsStream, TlsConnector};
+
+use crate::config::TlsOptions;
+
+static PROVIDER: Lazy<Arc<CryptoProvider>> =
+    Lazy::new(|| Arc::new(crypto::ring::default_provider()));
+
+/// A client configuration built ahead of time, see [`TlsOptions::client_config`].
+/// Options compare equal when they hold the same one.
+#[derive(Debug, Clone)]
+pub struct TlsConfig(pub Arc<ClientConfig>);
+
+impl PartialEq for TlsConfig {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for TlsConfig {}
+
+/// The client side as described by the options: the CA bundle to trust (the Mozilla roots
+/// by default), the certificate to present if any, or no verification at all if insecure.
+/// [`TlsOptions::client_config`] is not looked at, the files are read every time.
+pub fn client_config(options: &TlsOptions) -> anyhow::Result<Arc<ClientConfig>> {
+    let provider = provider();
+    let builder = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .context("tls (ClientConfig::builder)")?;
+
+    let builder = if options.insecure {
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(SkipVerify(provider)))
+    } else {
+        let mut roots = RootCertStore::empty();
+        match options.ca_file.as_ref() {
+            Some(path) => {
+                for cert in certs(path)? {
+                    roots
+                        .add(cert)
+                        .with_context(|| format!("tls (invalid CA in {})", path.display()))?;
+                }
+            }
+            None => roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned()),
+        }
+
+        builder.with_root_certificates(roots)
+    };
+
+    let config = match (options.cert_file.as_ref(), options.key_file.as_ref()) {
+        (Some(cert), Some(key)) => builder
+            .with_client_auth_cert(certs(cert)?, private_key(key)?)
+            .context("tls (ClientConfig::with_client_auth_cert)")?,
+        (None, None) => builder.with_no_client_auth(),
+        _ => bail!("tls (client certificate and key go together)"),
+    };
+
+    Ok(Arc::new(config))
+}
+
+/// The server side: the certificate chain and key to present, and the CA bundle
+/// client certificates have to be signed by, if they are required.
+pub fn server_config(
+    cert_file: &Path,
+    key_file: &Path,
+    client_ca_file: Option<&Path>,
+) -> anyhow::Result<Arc<ServerConfig>> {
+    let provider = provider();
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .context("tls (ServerConfig::builder)")?;
+
+    let builder = match client_ca_file {
+        Some(path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in certs(path)? {
+                roots
+                    .add(cert)
+                    .with_context(|| format!("tls (invalid CA in {})", path.display()))?;
+            }
+
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                .build()
+                .context("tls (WebPkiClientVerifier::build)")?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let config = builder
+        .with_single_cert(certs(cert_file)?, private_key(key_file)?)
+        .context("tls (ServerConfig::with_single_cert)")?;
+
+    Ok(Arc::new(config))
+}
+
+/// Performs the handshake over a connected stream. The certificate is checked against
+/// [`TlsOptions::server_name`] if set, `host` otherwise; either is sent as SNI.
+///
+/// Uses [`TlsOptions::client_config`] if set, builds the configuration otherwise.
+pub async fn connect(
+    stream: TcpStream,
+    host: &str,
+    options: &TlsOptions,
+) -> anyhow::Result<TlsStream<TcpStream>> {
+    let name = options.server_name.as_deref().unwrap_or(host);
+    let name = ServerName::try_from(name.to_string())
+        .with_context(|| format!("tls (invalid server name {name:?})"))?;
+
+    let config = match options.client_config.as_ref() {
+        Some(config) => config.0.clone(),
+        None => client_config(options)?,
+    };
+
+    TlsConnector::from(config)
+        .connect(name, stream)
+        .await
+        .context("tls (handshake)")
+}
+
+fn provider() -> Arc<CryptoProvider> {
+    PROVIDER.clone()
+}
+
+fn certs(path: &Path) -> anyhow::Result<Vec<CertificateDer<'static>>> {
+    let certs = CertificateDer::pem_file_iter(path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .with_context(|| format!("tls (reading certificates from {})", path.display()))?;
+
+    if certs.is_empty() {
+        bail!("tls (no certificate in {})", path.display());
+    }
+
+    Ok(certs)
+}
+
+fn private_key(path: &Path) -> anyhow::Result<PrivateKeyDer<'static>> {
+    PrivateKeyDer::from_pem_file(path)
+        .with_context(|| format!("tls (reading a private key from {})", path.display()))
+}
+
+// Accepts any certificate, still checking the handshake signatures.
+#[derive(Debug)]
+struct SkipVerify(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for SkipVerify {
+    fn verify_server_cert(
+        &self,
+        _: &CertificateDer<'_>,
+        _: &[CertificateDer<'_>],
+        _: &ServerName<'_>,
+        _: &[u8],
+        _: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
@@ -0,0 +1,257 @@
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use rcgen::{
+    BasicConstraints, Certificate, CertificateParams, ExtendedKeyUsagePurpose, IsCa, KeyPair,
+};
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+
+use super::*;
+use crate::{aio::Connection, cmd::Cmd, config::ConnectionInfo, resp};
+
+type Names = Arc<Mutex<Vec<Option<String>>>>;
+
+// A CA, with a server certificate for `localhost` and `redis.test` and a client one,
+// written as PEM files to a fresh directory.
+struct Certs {
+    dir: PathBuf,
+}
+
+impl Certs {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("resp3-tls-{}-{name}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let ca_key = KeyPair::generate().unwrap();
+        let mut params = CertificateParams::new(vec![]).unwrap();
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = params.self_signed(&ca_key).unwrap();
+        std::fs::write(dir.join("ca.pem"), ca.pem()).unwrap();
+
+        let certs = Self { dir };
+        certs.issue(
+            "server",
+            &["localhost", "redis.test"],
+            ExtendedKeyUsagePurpose::ServerAuth,
+            (&ca, &ca_key),
+        );
+        certs.issue(
+            "client",
+            &["client"],
+            ExtendedKeyUsagePurpose::ClientAuth,
+            (&ca, &ca_key),
+        );
+
+        certs
+    }
+
+    fn issue(
+        &self,
+        name: &str,
+        names: &[&str],
+        usage: ExtendedKeyUsagePurpose,
+        (ca, ca_key): (&Certificate, &KeyPair),
+    ) {
+        let key = KeyPair::generate().unwrap();
+        let names = names
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+        let mut params = CertificateParams::new(names).unwrap();
+        params.extended_key_usages = vec![usage];
+        let cert = params.signed_by(&key, ca, ca_key).unwrap();
+
+        std::fs::write(self.path(&format!("{name}.pem")), cert.pem()).unwrap();
+        std::fs::write(self.path(&format!("{name}.key")), key.serialize_pem()).unwrap();
+    }
+
+    fn path(&self, file: &str) -> PathBuf {
+        self.dir.join(file)
+    }
+
+    fn server_config(&self, mtls: bool) -> Arc<ServerConfig> {
+        let ca = self.path("ca.pem");
+        let client_ca = mtls.then_some(ca.as_path());
+
+        server_config(
+            &self.path("server.pem"),
+            &self.path("server.key"),
+            client_ca,
+        )
+        .unwrap()
+    }
+}
+
+impl Drop for Certs {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+// Sends back whatever it gets, so commands come back as arrays. Records the SNI of every client.
+async fn echo(config: Arc<ServerConfig>) -> (u16, Names) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let names = Names::default();
+
+    let acceptor = TlsAcceptor::from(config);
+    let state = names.clone();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let (acceptor, names) = (acceptor.clone(), state.clone());
+
+            tokio::spawn(async move {
+                let Ok(stream) = acceptor.accept(stream).await else {
+                    return;
+                };
+
+                let name = stream.get_ref().1.server_name().map(str::to_string);
+                names.lock().unwrap().push(name);
+
+                let (mut reader, mut writer) = tokio::io::split(stream);
+                let _ = tokio::io::copy(&mut reader, &mut writer).await;
+            });
+        }
+    });
+
+    (port, names)
+}
+
+async fn ping(url: &str) -> anyhow::Result<()> {
+    let info = ConnectionInfo::from_url(url)?;
+    let mut connection = Connection::open(&info).await?;
+
+    let reply = connection.send(&Cmd::new("PING")).await?;
+    assert_eq!(reply, resp!(["PING"]));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ca() {
+    let certs = Certs::new("ca");
+    let (port, names) = echo(certs.server_config(false)).await;
+    let ca = certs.path("ca.pem");
+
+    ping(&format!(
+        "rediss://localhost:{port}?tls_ca={}",
+        ca.display()
+    ))
+    .await
+    .unwrap();
+    assert_eq!(*names.lock().unwrap(), [Some("localhost".to_string())]);
+
+    // not signed by a known CA
+    assert!(ping(&format!("rediss://localhost:{port}")).await.is_err());
+
+    // unless nothing is checked
+    ping(&format!("rediss://localhost:{port}/#insecure"))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_server_name() {
+    let certs = Certs::new("sni");
+    let (port, names) = echo(certs.server_config(false)).await;
+    let ca = certs.path("ca.pem");
+
+    // the certificate has no IP address in it
+    let url = format!("rediss://127.0.0.1:{port}?tls_ca={}", ca.display());
+    assert!(ping(&url).await.is_err());
+
+    ping(&format!("{url}&tls_server_name=redis.test"))
+        .await
+        .unwrap();
+    assert!(ping(&format!("{url}&tls_server_name=other.test"))
+        .await
+        .is_err());
+
+    let names = names.lock().unwrap();
+    assert!(names.contains(&Some("redis.test".to_string())));
+    assert!(!names.contains(&Some("other.test".to_string())));
+}
+
+#[tokio::test]
+async fn test_mutual() {
+    let certs = Certs::new("mtls");
+    let (port, _) = echo(certs.server_config(true)).await;
+    let url = format!(
+        "rediss://localhost:{port}?tls_ca={}",
+        certs.path("ca.pem").display()
+    );
+
+    // the server only notices once the handshake is over for the client
+    assert!(ping(&url).await.is_err());
+
+    ping(&format!(
+        "{url}&tls_cert={}&tls_key={}",
+        certs.path("client.pem").display(),
+        certs.path("client.key").display()
+    ))
+    .await
+    .unwrap();
+}
+
+#[test]
+fn test_config_errors() {
+    let certs = Certs::new("errors");
+
+    let options = TlsOptions {
+        cert_file: Some(certs.path("client.pem")),
+        ..TlsOptions::default()
+    };
+    assert!(client_config(&options).is_err());
+
+    let options = TlsOptions {
+        ca_file: Some(certs.path("missing.pem")),
+        ..TlsOptions::default()
+    };
+    assert!(client_config(&options).is_err());
+
+    // a key is no certificate
+    let options = TlsOptions {
+        ca_file: Some(certs.path("client.key")),
+        ..TlsOptions::default()
+    };
+    assert!(client_config(&options).is_err());
+
+    assert!(client_config(&TlsOptions::default()).is_ok());
+    assert!(server_config(&certs.path("server.pem"), &certs.path("ca.pem"), None).is_err());
+}
+
+#[tokio::test]
+async fn test_loaded_config() {
+    let certs = Certs::new("loaded");
+    let (port, _) = echo(certs.server_config(false)).await;
+
+    let ca = certs.path("rotated.pem");
+    let url = format!("rediss://localhost:{port}?tls_ca={}", ca.display());
+    let mut info = ConnectionInfo::from_url(&url).unwrap();
+    assert!(info.load_tls().is_err());
+
+    std::fs::copy(certs.path("ca.pem"), &ca).unwrap();
+    info.load_tls().unwrap();
+    let config = info.tls.as_ref().unwrap().client_config.clone().unwrap();
+
+    // the file is not read again once loaded
+    std::fs::remove_file(&ca).unwrap();
+    let mut connection = Connection::open(&info).await.unwrap();
+    assert_eq!(
+        connection.send(&Cmd::new("PING")).await.unwrap(),
+        resp!(["PING"])
+    );
+    assert_eq!(
+        info.tls.as_ref().unwrap().client_config,
+        Some(config.clone())
+    );
+    assert!(ping(&url).await.is_err());
+
+    // loading again builds a new one
+    std::fs::copy(certs.path("ca.pem"), &ca).unwrap();
+    info.load_tls().unwrap();
+    assert_ne!(info.tls.unwrap().client_config, Some(config));
+}
@@ -0,0 +1,198 @@
+//! `MULTI`/`EXEC` transactions.
+
+#[cfg(test)]
+pub(crate) mod tests;
+
+use std::{error::Error, fmt};
+
+use anyhow::{bail, Context};
+use bytes::Bytes;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::{
+    aio::Connection,
+    cmd::Cmd,
+    error::ServerError,
+    value::{FromValue, Value},
+};
+
+/// Commands to run atomically, sent along with `MULTI` and `EXEC` in one write.
+///
+/// [`Transaction::cmds`] and [`Transaction::decode`] work with any connection,
+/// [`Transaction::exec`] does both over an async [`Connection`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Transaction {
+    cmds: Vec<Cmd>,
+}
+
+/// `EXEC` refused to run the transaction, because some commands were rejected when queued.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecAbort {
+    /// The `EXECABORT` reply.
+    pub error: ServerError,
+    /// Each rejected command, by position in the transaction, with the reason.
+    pub rejected: Vec<(usize, ServerError)>,
+}
+
+impl Transaction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, cmd: Cmd) -> &mut Self {
+        self.cmds.push(cmd);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.cmds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cmds.is_empty()
+    }
+
+    /// What to send: the commands between `MULTI` and `EXEC`.
+    pub fn cmds(&self) -> Vec<Cmd> {
+        let mut cmds = Vec::with_capacity(self.cmds.len() + 2);
+        cmds.push(Cmd::new("MULTI"));
+        cmds.extend(self.cmds.iter().cloned());
+        cmds.push(Cmd::new("EXEC"));
+
+        cmds
+    }
+
+    /// Checks the replies to [`Transaction::cmds`] and converts the results of `EXEC`,
+    /// `None` if it did not run because a watched key changed.
+    /// Fails with [`ExecAbort`] if some commands were rejected.
+    pub fn decode<T: FromValue>(&self, replies: Vec<Value>) -> anyhow::Result<Option<T>> {
+        if replies.len() != self.cmds.len() + 2 {
+            bail!(
+                "Transaction (expected {} replies, got {})",
+                self.cmds.len() + 2,
+                replies.len()
+            );
+        }
+
+        let mut replies = replies.into_iter();
+
+        let multi = replies.next().expect("length was just checked");
+        multi.into_result().context("Transaction (MULTI)")?;
+
+        let mut rejected = vec![];
+        for (i, reply) in replies.by_ref().take(self.cmds.len()).enumerate() {
+            match reply.into_result() {
+                Ok(Value::SimpleString(inner)) if inner.val() == "QUEUED" => {}
+                Ok(reply) => bail!("Transaction (expected QUEUED, got {reply:?})"),
+                Err(err) => rejected.push((i, err)),
+            }
+        }
+
+        match replies
+            .next()
+            .expect("length was just checked")
+            .into_result()
+        {
+            Ok(Value::Null(_)) => Ok(None),
+            Ok(reply) => T::from_value(reply).context("Transaction (EXEC)").map(Some),
+            Err(error) => Err(ExecAbort { error, rejected }.into()),
+        }
+    }
+
+    /// Runs the transaction, see [`Transaction::decode`].
+    pub async fn exec<T, S>(&self, connection: &mut Connection<S>) -> anyhow::Result<Option<T>>
+    where
+        T: FromValue,
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let replies = connection.pipeline(&self.cmds()).await?;
+
+        self.decode(replies)
+    }
+}
+
+/// Parameters of [`watch_with`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchOptions {
+    /// How many times to run the transaction before giving up on watched keys which keep changing.
+    pub max_attempts: usize,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        Self { max_attempts: 16 }
+    }
+}
+
+/// Optimistic locking: `WATCH`es the keys, lets `build` read what it needs and fill
+/// in a transaction, then runs it. Starts over as long as a watched key changes
+/// before `EXEC`, up to [`WatchOptions::max_attempts`] times. The keys are unwatched
+/// if `build` fails.
+pub async fn watch<T, S, K, F>(
+    connection: &mut Connection<S>,
+    keys: impl IntoIterator<Item = K>,
+    build: F,
+) -> anyhow::Result<T>
+where
+    T: FromValue,
+    S: AsyncRead + AsyncWrite + Unpin,
+    K: Into<Bytes>,
+    F: AsyncFnMut(&mut Connection<S>, &mut Transaction) -> anyhow::Result<()>,
+{
+    watch_with(connection, keys, WatchOptions::default(), build).await
+}
+
+pub async fn watch_with<T, S, K, F>(
+    connection: &mut Connection<S>,
+    keys: impl IntoIterator<Item = K>,
+    options: WatchOptions,
+    mut build: F,
+) -> anyhow::Result<T>
+where
+    T: FromValue,
+    S: AsyncRead + AsyncWrite + Unpin,
+    K: Into<Bytes>,
+    F: AsyncFnMut(&mut Connection<S>, &mut Transaction) -> anyhow::Result<()>,
+{
+    let watch = keys.into_iter().fold(Cmd::new("WATCH"), Cmd::arg);
+    if watch.args().len() < 2 {
+        bail!("Transaction (no keys to watch)");
+    }
+
+    for _ in 0..options.max_attempts {
+        connection
+            .send(&watch)
+            .await?
+            .into_result()
+            .context("Transaction (WATCH)")?;
+
+        let mut txn = Transaction::new();
+        if let Err(err) = build(connection, &mut txn).await {
+            connection.send(&Cmd::new("UNWATCH")).await?;
+            return Err(err);
+        }
+
+        if let Some(results) = txn.exec(connection).await? {
+            return Ok(results);
+        }
+    }
+
+    bail!(
+        "Transaction (watched keys changed {} times in a row)",
+        options.max_attempts
+    )
+}
+
+impl fmt::Display for ExecAbort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.error)?;
+
+        for (i, err) in &self.rejected {
+            write!(f, "; command {i}: {err}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for ExecAbort {}
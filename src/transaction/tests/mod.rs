@@ -0,0 +1,196 @@
+use super::*;
+use crate::{
+    aio::tests::{serve_logged, take_log, Log},
+    error::ErrorKind,
+    resp,
+    value::{Array, BlobString, Number},
+};
+
+// Queues commands between MULTI and EXEC, and keeps one counter. The first `conflicts`
+// transactions fail as if a watched key changed.
+async fn server(conflicts: usize) -> (String, Log) {
+    serve_logged(move |_| {
+        let mut conflicts = conflicts;
+        let mut counter = 0;
+        let mut queued: Option<Vec<String>> = None;
+        let mut aborted = false;
+
+        move |args: &[Bytes]| {
+            let name = String::from_utf8_lossy(&args[0]).to_uppercase();
+
+            let known = matches!(name.as_str(), "SET" | "INCR" | "GET");
+            let reply = match (name.as_str(), queued.as_mut()) {
+                ("MULTI", None) => {
+                    queued = Some(vec![]);
+                    resp!(+"OK")
+                }
+                ("EXEC", Some(_)) if std::mem::take(&mut aborted) => {
+                    queued = None;
+                    resp!(-"EXECABORT Transaction discarded because of previous errors.")
+                }
+                ("EXEC", Some(_)) if conflicts > 0 => {
+                    conflicts -= 1;
+                    queued = None;
+                    resp!(nil)
+                }
+                ("EXEC", Some(_)) => {
+                    let results = queued
+                        .take()
+                        .unwrap()
+                        .into_iter()
+                        .map(|name| match name.as_str() {
+                            "INCR" => {
+                                counter += 1;
+                                Value::from(Number::from(counter))
+                            }
+                            "GET" => resp!(BlobString::from(counter.to_string())),
+                            _ => resp!(+"OK"),
+                        })
+                        .collect::<Vec<_>>();
+                    Value::from(Array::from(results))
+                }
+                (_, Some(queued)) if known => {
+                    queued.push(name);
+                    resp!(+"QUEUED")
+                }
+                (_, Some(_)) => {
+                    aborted = true;
+                    resp!(-"ERR unknown command")
+                }
+                ("GET", None) => resp!(BlobString::from(counter.to_string())),
+                ("WATCH" | "UNWATCH", None) => resp!(+"OK"),
+                _ => resp!(-"ERR unknown command"),
+            };
+
+            vec![reply]
+        }
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_exec() {
+    let (addr, log) = server(0).await;
+    let mut connection = Connection::connect(addr).await.unwrap();
+
+    let mut txn = Transaction::new();
+    txn.add(Cmd::new("SET").arg("a").arg("1"))
+        .add(Cmd::new("INCR").arg("a"))
+        .add(Cmd::new("GET").arg("a"));
+    assert_eq!(txn.len(), 3);
+
+    let results = txn.exec::<(String, i64, i64), _>(&mut connection).await;
+    assert_eq!(results.unwrap(), Some(("OK".to_string(), 1, 1)));
+    assert!(!connection.protocol().in_transaction());
+    assert_eq!(take_log(&log), ["MULTI", "SET", "INCR", "GET", "EXEC"]);
+
+    // or as they come
+    let results = txn.exec::<Value, _>(&mut connection).await.unwrap();
+    assert_eq!(results, Some(resp!([+"OK", 2, "2"])));
+
+    // the results have to fit
+    assert!(txn.exec::<(i64, i64), _>(&mut connection).await.is_err());
+}
+
+#[tokio::test]
+async fn test_exec_abort() {
+    let (addr, _) = server(0).await;
+    let mut connection = Connection::connect(addr).await.unwrap();
+
+    let mut txn = Transaction::new();
+    txn.add(Cmd::new("INCR").arg("a")).add(Cmd::new("NOPE"));
+
+    let err = txn.exec::<Value, _>(&mut connection).await.unwrap_err();
+    let abort = err.downcast::<ExecAbort>().unwrap();
+    assert_eq!(abort.error.kind(), &ErrorKind::ExecAbort);
+    assert_eq!(abort.rejected.len(), 1);
+    assert_eq!(abort.rejected[0].0, 1);
+    assert_eq!(abort.rejected[0].1.msg(), b"unknown command");
+    assert!(!connection.protocol().in_transaction());
+
+    // nothing ran
+    let reply = connection.send(&Cmd::new("GET").arg("a")).await.unwrap();
+    assert_eq!(reply, resp!("0"));
+}
+
+#[tokio::test]
+async fn test_watch() {
+    let (addr, log) = server(2).await;
+    let mut connection = Connection::connect(addr).await.unwrap();
+
+    let mut attempts = 0;
+    let (value,) = watch::<(String,), _, _, _>(&mut connection, ["a"], async |connection, txn| {
+        attempts += 1;
+        let current = connection.send(&Cmd::new("GET").arg("a")).await?;
+        let current = i64::from_value(current)?;
+
+        txn.add(Cmd::new("SET").arg("a").arg((current + 1).to_string()));
+        Ok(())
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(value, "OK");
+    assert_eq!(attempts, 3);
+    assert_eq!(
+        take_log(&log),
+        ["WATCH", "GET", "MULTI", "SET", "EXEC"].repeat(3)
+    );
+
+    // giving up unwatches the keys
+    let result = watch::<Value, _, _, _>(&mut connection, ["a", "b"], async |_, _| {
+        bail!("changed my mind")
+    })
+    .await;
+    assert!(result.is_err());
+    assert_eq!(take_log(&log), ["WATCH", "UNWATCH"]);
+
+    let result = watch::<Value, _, &str, _>(&mut connection, [], async |_, _| Ok(())).await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_watch_limit() {
+    let (addr, log) = server(5).await;
+    let mut connection = Connection::connect(addr).await.unwrap();
+    let options = WatchOptions { max_attempts: 3 };
+
+    let result = watch_with::<Value, _, _, _>(&mut connection, ["a"], options, async |_, txn| {
+        txn.add(Cmd::new("INCR").arg("a"));
+        Ok(())
+    })
+    .await;
+
+    let err = result.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Transaction (watched keys changed 3 times in a row)"
+    );
+    assert_eq!(take_log(&log), ["WATCH", "MULTI", "INCR", "EXEC"].repeat(3));
+}
+
+#[test]
+fn test_decode() {
+    let mut txn = Transaction::new();
+    txn.add(Cmd::new("INCR").arg("a"));
+
+    let cmds = txn.cmds();
+    assert_eq!(cmds.len(), 3);
+    assert_eq!(
+        (cmds[0].name(), cmds[2].name()),
+        (&b"MULTI"[..], &b"EXEC"[..])
+    );
+
+    let decode = |replies: Vec<Value>| txn.decode::<(i64,)>(replies);
+    assert_eq!(
+        decode(vec![resp!(+"OK"), resp!(+"QUEUED"), resp!([1])]).unwrap(),
+        Some((1,))
+    );
+    assert_eq!(
+        decode(vec![resp!(+"OK"), resp!(+"QUEUED"), resp!(nil)]).unwrap(),
+        None
+    );
+    assert!(decode(vec![resp!(+"OK"), resp!([1])]).is_err());
+    assert!(decode(vec![resp!(-"ERR nested"), resp!(+"QUEUED"), resp!([1])]).is_err());
+    assert!(decode(vec![resp!(+"OK"), resp!(+"OK"), resp!([1])]).is_err());
+}
@@ -2,18 +2,19 @@
 pub(crate) mod tests;
 
 use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
     io::Write,
     str::{self, FromStr},
 };
 
-use anyhow::Context;
+use anyhow::{bail, Context};
 use bytes::Bytes;
-use derivative::Derivative;
 use nom::{
     branch::alt,
     bytes::complete::tag,
-    character::complete::digit1,
-    combinator::{map_res, opt},
+    character::complete::{digit1, one_of},
+    combinator::{map_res, opt, recognize},
     sequence::{delimited, pair, preceded, tuple},
     IResult, Parser,
 };
@@ -36,30 +37,15 @@ impl Sign {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct Parts {
-    sign: Sign,
-    int: u64,
-    frac: Option<u64>,
-}
-
-#[derive(Debug, Clone, Derivative)]
-#[derivative(PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct Val {
-    parts: Parts,
-    #[derivative(
-        PartialEq = "ignore",
-        PartialOrd = "ignore",
-        Ord = "ignore",
-        Hash = "ignore"
-    )]
-    inner: f64,
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub enum Double {
-    Inf { sign: Sign, attr: Option<Attribute> },
-    Val { val: Val, attr: Option<Attribute> },
+/// A double keeping the exact text it was parsed from, so it re-encodes byte for byte.
+///
+/// Doubles are ordered by [`f64::total_cmp`] (NaN included) and then by their text,
+/// e.g. `1.5` sorts before `1.50`.
+#[derive(Debug, Clone)]
+pub struct Double {
+    text: String,
+    val: f64,
+    attr: Option<Attribute>,
 }
 
 impl From<Double> for Value {
@@ -68,182 +54,158 @@ impl From<Double> for Value {
     }
 }
 
-impl From<Val> for Double {
-    fn from(val: Val) -> Self {
-        Self::Val { val, attr: None }
-    }
-}
-
 impl Double {
     pub fn val(&self) -> f64 {
-        match self {
-            Self::Inf {
-                sign: Sign::Minus, ..
-            } => f64::NEG_INFINITY,
-            Self::Inf {
-                sign: Sign::Plus, ..
-            } => f64::INFINITY,
-            Self::Val { val, .. } => val.inner,
-        }
+        self.val
+    }
+
+    /// The textual form as it appears on the wire, e.g. `1.5e10`, `-inf` or `nan`.
+    pub fn text(&self) -> &str {
+        &self.text
     }
 
     pub fn attr(&self) -> Option<&Attribute> {
-        match self {
-            Self::Inf { attr, .. } => attr.as_ref(),
-            Self::Val { attr, .. } => attr.as_ref(),
-        }
+        self.attr.as_ref()
     }
 
-    pub fn with_attr(self, attr: Attribute) -> Self {
-        match self {
-            Self::Inf { sign, .. } => Self::Inf {
-                sign,
-                attr: Some(attr),
-            },
-            Self::Val { val, .. } => Self::Val {
-                val,
-                attr: Some(attr),
-            },
-        }
+    pub fn attr_mut(&mut self) -> &mut Option<Attribute> {
+        &mut self.attr
+    }
+
+    pub fn take_attr(&mut self) -> Option<Attribute> {
+        self.attr.take()
+    }
+
+    pub fn with_attr(mut self, attr: Attribute) -> Self {
+        self.attr = Some(attr);
+        self
     }
 }
 
 impl Double {
-    pub fn from_parts(parts: Parts) -> anyhow::Result<Self> {
-        let inner = Bytes::try_from(parts.clone())
-            .context("(Value::Double) Bytes::try_from")
-            .and_then(|v| {
-                str::from_utf8(&v)
-                    .context("Value::Double (str::from_utf8)")
-                    .and_then(|v| f64::from_str(v).context("Value::Double (f64::from_str)"))
-            })?;
-
-        Ok(Self::Val {
-            val: Val { parts, inner },
-            attr: None,
-        })
+    pub fn inf(sign: Sign) -> Self {
+        match sign {
+            Sign::Plus => Self::from(f64::INFINITY),
+            Sign::Minus => Self::from(f64::NEG_INFINITY),
+        }
     }
 
-    pub fn inf(sign: Sign) -> Self {
-        Self::Inf { sign, attr: None }
+    pub fn nan() -> Self {
+        Self::from(f64::NAN)
     }
 }
 
 impl Double {
     pub fn parse(input: &[u8]) -> IResult<&[u8], Self> {
-        let parse_inf = pair(opt(tag("-")), tag("inf")).map(|(sign, _inf)| {
-            sign.map(|_| Double::Inf {
-                sign: Sign::Minus,
-                attr: None,
-            })
-            .unwrap_or(Double::Inf {
-                sign: Sign::Plus,
-                attr: None,
-            })
-        });
-
-        let parse_num = {
-            let parser = tuple((opt(tag("-")), digit1, opt(preceded(tag("."), digit1))));
-
-            map_res(parser, |(sign, int, frac)| {
-                let sign = sign.map(|_| Sign::Minus).unwrap_or(Sign::Plus);
-                let int = str::from_utf8(int)
-                    .context("Value::Double (str::from_utf8)")
-                    .and_then(|int| u64::from_str(int).context("Value::Double (u64::from_str)"))?;
-                let frac = frac
-                    .map(|frac| {
-                        String::from_utf8(frac.to_vec())
-                            .context("Value::Double (String::from_utf8)")
-                            .and_then(|frac| {
-                                u64::from_str(&frac).context("Value::Double (u64::from_str)")
-                            })
-                    })
-                    .transpose()?;
-
-                let parts = Parts { sign, int, frac };
-                let value =
-                    Double::from_parts(parts).context("Value::Double (Double::from_parts)")?;
-
-                Ok::<_, anyhow::Error>(value)
+        let parse_num = recognize(tuple((
+            opt(one_of("+-")),
+            digit1,
+            opt(preceded(tag("."), digit1)),
+            opt(tuple((one_of("eE"), opt(one_of("+-")), digit1))),
+        )));
+        let parse_special = alt((tag("inf"), tag("-inf"), tag("nan")));
+
+        let parse_val = {
+            let parser = delimited(tag(","), alt((parse_num, parse_special)), tag(DELIMITER));
+
+            map_res(parser, |text: &[u8]| {
+                let text = str::from_utf8(text).context("Value::Double (str::from_utf8)")?;
+                let val = f64::from_str(text).context("Value::Double (f64::from_str)")?;
+
+                Ok::<_, anyhow::Error>(Double {
+                    text: text.to_string(),
+                    val,
+                    attr: None,
+                })
             })
         };
 
         let parse_attr = opt(Attribute::parse);
-        let parse_val = delimited(tag(","), alt((parse_num, parse_inf)), tag(DELIMITER));
 
         pair(parse_attr, parse_val)
-            .map(|(attr, val)| match attr {
-                Some(attr) => val.with_attr(attr),
-                None => val,
-            })
+            .map(|(attr, val)| Double { attr, ..val })
             .parse(input)
     }
 }
 
-impl TryFrom<&Double> for Bytes {
-    type Error = anyhow::Error;
-
-    fn try_from(input: &Double) -> anyhow::Result<Bytes> {
-        let mut buf = vec![];
+/// Uses the shortest text which parses back to the same `f64`.
+/// Every NaN becomes [`f64::NAN`], as its sign and payload cannot be written out.
+impl From<f64> for Double {
+    fn from(val: f64) -> Self {
+        let (text, val) = match val {
+            val if val.is_nan() => ("nan".to_string(), f64::NAN),
+            f64::INFINITY => ("inf".to_string(), val),
+            f64::NEG_INFINITY => ("-inf".to_string(), val),
+            val => (format!("{val:?}"), val),
+        };
 
-        if let Some(attr) = input.attr() {
-            let bytes = Bytes::try_from(attr).context("Value::Double (Bytes::from)")?;
-            buf.write(&bytes).context("Value::Double (buf::write)")?;
+        Self {
+            text,
+            val,
+            attr: None,
         }
+    }
+}
+
+/// Parses the textual form alone, e.g. `1.5e10`.
+impl FromStr for Double {
+    type Err = anyhow::Error;
 
-        buf.write(b",").context("Value::Double (buf::write)")?;
-
-        match input {
-            Double::Inf { sign, .. } => {
-                sign.is_minus()
-                    .then(|| buf.write(b"-"))
-                    .transpose()
-                    .and_then(|_| buf.write(b"inf"))
-                    .context("Value::Double (buf::write)")?;
-            }
-            Double::Val { val, .. } => {
-                let bytes =
-                    Bytes::try_from(&val.parts).context("Value::Double (Bytes::Try_from)")?;
-                buf.write(&bytes).context("Value::Double (buf::write)")?;
-            }
+    fn from_str(input: &str) -> anyhow::Result<Self> {
+        let raw = format!(",{input}\r\n");
+
+        match Double::parse(raw.as_bytes()) {
+            Ok((b"", val)) if val.attr.is_none() => Ok(val),
+            _ => bail!("Value::Double (invalid double {input:?})"),
         }
+    }
+}
 
-        buf.write(DELIMITER).context("Value::Double (buf::write)")?;
+impl PartialEq for Double {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
 
-        buf.flush().context("Value::Double (buf::flush)")?;
-        Ok(Bytes::from(buf))
+impl Eq for Double {}
+
+impl PartialOrd for Double {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
-impl TryFrom<Double> for Bytes {
-    type Error = anyhow::Error;
+impl Ord for Double {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.val
+            .total_cmp(&other.val)
+            .then_with(|| self.text.cmp(&other.text))
+            .then_with(|| self.attr.cmp(&other.attr))
+    }
+}
 
-    fn try_from(input: Double) -> anyhow::Result<Self> {
-        Bytes::try_from(&input)
+// equal doubles have equal texts, and the text determines the value
+impl Hash for Double {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.text.hash(state);
+        self.attr.hash(state);
     }
 }
 
-impl TryFrom<&Parts> for Bytes {
+impl TryFrom<&Double> for Bytes {
     type Error = anyhow::Error;
 
-    fn try_from(input: &Parts) -> anyhow::Result<Self> {
+    fn try_from(input: &Double) -> anyhow::Result<Bytes> {
         let mut buf = vec![];
 
-        input
-            .sign
-            .is_minus()
-            .then(|| buf.write(b"-"))
-            .transpose()
-            .and_then(|_| buf.write(input.int.to_string().as_bytes()))
-            .and_then(|_| {
-                input
-                    .frac
-                    .map(|frac| {
-                        buf.write(b".")
-                            .and_then(|_| buf.write(frac.to_string().as_bytes()))
-                    })
-                    .transpose()
-            })
+        if let Some(attr) = input.attr() {
+            let bytes = Bytes::try_from(attr).context("Value::Double (Bytes::from)")?;
+            buf.write(&bytes).context("Value::Double (buf::write)")?;
+        }
+
+        buf.write(b",")
+            .and_then(|_| buf.write(input.text().as_bytes()))
+            .and_then(|_| buf.write(DELIMITER))
             .context("Value::Double (buf::write)")?;
 
         buf.flush().context("Value::Double (buf::flush)")?;
@@ -251,10 +213,10 @@ impl TryFrom<&Parts> for Bytes {
     }
 }
 
-impl TryFrom<Parts> for Bytes {
+impl TryFrom<Double> for Bytes {
     type Error = anyhow::Error;
 
-    fn try_from(input: Parts) -> anyhow::Result<Self> {
+    fn try_from(input: Double) -> anyhow::Result<Self> {
         Bytes::try_from(&input)
     }
 }
@@ -2,50 +2,106 @@ pub mod prop;
 
 use super::*;
 
+fn parse(input: &[u8]) -> Double {
+    match Double::parse(input) {
+        Ok((b"", val)) => val,
+        result => panic!("unexpected result: {result:?}"),
+    }
+}
+
 #[test]
 fn test_positive_number() {
-    assert_eq!(
-        Double::parse(&b",1.234\r\n"[..]),
-        Ok((
-            &b""[..],
-            Double::from_parts(Parts {
-                sign: Sign::Plus,
-                int: 1,
-                frac: Some(234)
-            })
-            .unwrap()
-        ))
-    );
+    let val = parse(b",1.234\r\n");
+
+    assert_eq!(val.val(), 1.234);
+    assert_eq!(val.text(), "1.234");
 }
 
 #[test]
 fn test_negative_number() {
-    assert_eq!(
-        Double::parse(&b",-1.234\r\n"[..]),
-        Ok((
-            &b""[..],
-            Double::from_parts(Parts {
-                sign: Sign::Minus,
-                int: 1,
-                frac: Some(234)
-            })
-            .unwrap()
-        ))
-    );
+    let val = parse(b",-1.234\r\n");
+
+    assert_eq!(val.val(), -1.234);
+    assert_eq!(val.text(), "-1.234");
+}
+
+#[test]
+fn test_leading_zero_fraction() {
+    let val = parse(b",1.05\r\n");
+
+    assert_eq!(val.val(), 1.05);
+    assert_eq!(Bytes::try_from(val).unwrap(), &b",1.05\r\n"[..]);
+}
+
+#[test]
+fn test_exponent() {
+    assert_eq!(parse(b",1.5e10\r\n").val(), 1.5e10);
+    assert_eq!(parse(b",1E-3\r\n").val(), 1e-3);
+    assert_eq!(parse(b",+2.5e+2\r\n").val(), 250.0);
+
+    assert!(Double::parse(b",1.5e\r\n").is_err());
+    assert!(Double::parse(b",.5\r\n").is_err());
+}
+
+#[test]
+fn test_huge_integer() {
+    let text = "123456789012345678901234567890";
+    let val = parse(format!(",{text}\r\n").as_bytes());
+
+    assert_eq!(val.val(), 1.2345678901234568e29);
+    assert_eq!(val.text(), text);
 }
 
 #[test]
 fn test_positive_infinity() {
-    assert_eq!(
-        Double::parse(&b",inf\r\n"[..]),
-        Ok((&b""[..], Double::inf(Sign::Plus)))
-    );
+    assert_eq!(parse(b",inf\r\n"), Double::inf(Sign::Plus));
 }
 
 #[test]
 fn test_negative_infinity() {
-    assert_eq!(
-        Double::parse(&b",-inf\r\n"[..]),
-        Ok((&b""[..], Double::inf(Sign::Minus)))
-    );
+    assert_eq!(parse(b",-inf\r\n"), Double::inf(Sign::Minus));
+}
+
+#[test]
+fn test_nan() {
+    let val = parse(b",nan\r\n");
+
+    assert!(val.val().is_nan());
+    assert_eq!(val, Double::nan());
+    assert!(val > Double::inf(Sign::Plus));
+
+    // the sign of a NaN does not survive encoding, so it is dropped right away
+    let neg = Double::from(-f64::NAN);
+    let bytes = Bytes::try_from(&neg).unwrap();
+    assert_eq!(parse(&bytes), neg);
+    assert_eq!(neg, Double::nan());
+    assert!(neg > Double::inf(Sign::Plus));
+}
+
+#[test]
+fn test_from_f64() {
+    assert_eq!(Double::from(1.5).text(), "1.5");
+    assert_eq!(Double::from(3.0).text(), "3.0");
+    assert_eq!(Double::from(1e300).text(), "1e300");
+    assert_eq!(Double::from(0.1 + 0.2).text(), "0.30000000000000004");
+    assert_eq!(Double::from(f64::NEG_INFINITY).text(), "-inf");
+    assert_eq!(Double::from(f64::NAN).text(), "nan");
+}
+
+#[test]
+fn test_from_str() {
+    assert_eq!(Double::from_str("1.05").unwrap(), parse(b",1.05\r\n"));
+    assert!(Double::from_str("1.05\r\n,2").is_err());
+    assert!(Double::from_str("abc").is_err());
+}
+
+#[test]
+fn test_ord() {
+    let mut vals = ["nan", "2", "-inf", "1.50", "1.5", "inf"]
+        .map(|text| Double::from_str(text).unwrap())
+        .to_vec();
+    vals.sort();
+
+    let texts = vals.iter().map(Double::text).collect::<Vec<_>>();
+    assert_eq!(texts, ["-inf", "1.5", "1.50", "2", "inf", "nan"]);
 }
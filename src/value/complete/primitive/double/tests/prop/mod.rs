@@ -3,13 +3,15 @@ use proptest::prelude::{prop as p, *};
 use super::*;
 use crate::value::complete::special::attribute::tests::prop::value as attr_value;
 
-mod parts;
 mod sign;
+mod text;
 
 pub fn value() -> impl Strategy<Value = Double> {
     prop_oneof![
         sign::value().prop_map(Double::inf),
-        parts::value().prop_map(|parts| Double::from_parts(parts).unwrap())
+        Just(Double::nan()),
+        any::<f64>().prop_map(Double::from),
+        text::value().prop_map(|text| Double::from_str(&text).unwrap())
     ]
 }
 
@@ -31,4 +33,13 @@ proptest! {
         assert!(rest.is_empty());
         assert_eq!(parsed, v);
     }
+
+    #[test]
+    fn test_from_f64(v in any::<f64>()) {
+        let parsed = Double::from_str(Double::from(v).text()).unwrap();
+        assert_eq!(parsed.val().to_bits(), match v.is_nan() {
+            true => f64::NAN.to_bits(),
+            false => v.to_bits(),
+        });
+    }
 }
@@ -0,0 +1,9 @@
+use proptest::prelude::*;
+
+pub fn value() -> impl Strategy<Value = String> {
+    prop_oneof![
+        "[+-]?[0-9]{1,24}",
+        "[+-]?[0-9]{1,24}\\.[0-9]{1,24}",
+        "[+-]?[0-9]{1,24}(\\.[0-9]{1,24})?[eE][+-]?[0-9]{1,3}",
+    ]
+}
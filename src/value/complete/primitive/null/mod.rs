@@ -6,6 +6,7 @@ use std::io::Write;
 use anyhow::Context;
 use bytes::Bytes;
 use nom::{
+    branch::alt,
     bytes::complete::tag,
     combinator::opt,
     sequence::{pair, terminated},
@@ -26,6 +27,18 @@ impl From<Null> for Value {
 }
 
 impl Null {
+    pub fn attr(&self) -> Option<&Attribute> {
+        self.attr.as_ref()
+    }
+
+    pub fn attr_mut(&mut self) -> &mut Option<Attribute> {
+        &mut self.attr
+    }
+
+    pub fn take_attr(&mut self) -> Option<Attribute> {
+        self.attr.take()
+    }
+
     pub fn with_attr(mut self, attr: Attribute) -> Self {
         self.attr = Some(attr);
         self
@@ -34,7 +47,8 @@ impl Null {
 
 impl Null {
     pub fn parse(input: &[u8]) -> IResult<&[u8], Self> {
-        let parse_val = terminated(tag("_"), tag(DELIMITER));
+        // RESP2 null bulk strings and null arrays are accepted too
+        let parse_val = terminated(alt((tag("_"), tag("$-1"), tag("*-1"))), tag(DELIMITER));
         pair(opt(Attribute::parse), parse_val)
             .map(|(attr, _)| Null { attr })
             .parse(input)
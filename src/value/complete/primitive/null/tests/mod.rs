@@ -7,6 +7,18 @@ fn test_basic() {
     assert_eq!(Null::parse(&b"_\r\n"[..]), Ok((&b""[..], Null::default())));
 }
 
+#[test]
+fn test_resp2() {
+    assert_eq!(
+        Null::parse(&b"$-1\r\n"[..]),
+        Ok((&b""[..], Null::default()))
+    );
+    assert_eq!(
+        Null::parse(&b"*-1\r\n"[..]),
+        Ok((&b""[..], Null::default()))
+    );
+}
+
 #[test]
 fn test_codec() {
     assert_eq!(
@@ -39,6 +39,14 @@ impl Number {
         self.attr.as_ref()
     }
 
+    pub fn attr_mut(&mut self) -> &mut Option<Attribute> {
+        &mut self.attr
+    }
+
+    pub fn take_attr(&mut self) -> Option<Attribute> {
+        self.attr.take()
+    }
+
     pub fn with_attr(mut self, attr: Attribute) -> Self {
         self.attr = Some(attr);
         self
@@ -3,18 +3,15 @@ pub(crate) mod tests;
 
 use std::{
     io::Write,
+    slice,
     str::{self, FromStr},
+    vec,
 };
 
 use anyhow::Context;
 use bytes::Bytes;
 use nom::{
-    bytes::complete::tag,
-    character::complete::digit1,
-    combinator::map_res,
-    combinator::opt,
-    multi::many_m_n,
-    sequence::{delimited, pair},
+    bytes::complete::tag, character::complete::digit1, combinator::map_res, sequence::delimited,
     IResult, Parser,
 };
 
@@ -37,10 +34,49 @@ impl Array {
         &self.val
     }
 
+    pub fn val_mut(&mut self) -> &mut Vec<Value> {
+        &mut self.val
+    }
+
+    /// Drops the attribute.
+    pub fn into_inner(self) -> Vec<Value> {
+        self.val
+    }
+
+    pub fn len(&self) -> usize {
+        self.val.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.val.is_empty()
+    }
+
+    pub fn push(&mut self, value: Value) {
+        self.val.push(value);
+    }
+
+    /// Panics if `index > len`.
+    pub fn insert(&mut self, index: usize, value: Value) {
+        self.val.insert(index, value);
+    }
+
+    /// Panics if `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> Value {
+        self.val.remove(index)
+    }
+
     pub fn attr(&self) -> Option<&Attribute> {
         self.attr.as_ref()
     }
 
+    pub fn attr_mut(&mut self) -> &mut Option<Attribute> {
+        &mut self.attr
+    }
+
+    pub fn take_attr(&mut self) -> Option<Attribute> {
+        self.attr.take()
+    }
+
     pub fn with_attr(mut self, attr: Attribute) -> Self {
         self.attr = Some(attr);
         self
@@ -48,33 +84,69 @@ impl Array {
 }
 
 impl Array {
+    /// Parses just the header, e.g. `*3\r\n`, returning the number of items announced.
+    pub(crate) fn parse_len(input: &[u8]) -> IResult<&[u8], usize> {
+        let parser = delimited(tag("*"), digit1, tag(DELIMITER));
+
+        map_res(parser, |v: &[u8]| {
+            str::from_utf8(v)
+                .context("Value::Array (str::from_utf8)")
+                .and_then(|v| usize::from_str(v).context("Value::Array (usize::from_str)"))
+        })
+        .parse(input)
+    }
+
+    /// Parses a value with [`Value::parse`], failing if it is not an array.
     pub fn parse(input: &[u8]) -> IResult<&[u8], Self> {
-        let parse_len = {
-            let parser = delimited(tag("*"), digit1, tag(DELIMITER));
+        match Value::parse(input)? {
+            (rest, Value::Array(inner)) => Ok((rest, inner)),
+            _ => Err(nom::Err::Error(nom::error::Error::new(
+                input,
+                nom::error::ErrorKind::Verify,
+            ))),
+        }
+    }
+}
+
+impl From<Vec<Value>> for Array {
+    fn from(val: Vec<Value>) -> Self {
+        Self { val, attr: None }
+    }
+}
+
+impl<const N: usize> From<[Value; N]> for Array {
+    fn from(input: [Value; N]) -> Self {
+        Self::from(Vec::from(input))
+    }
+}
 
-            map_res(parser, |v: &[u8]| {
-                str::from_utf8(v)
-                    .context("Value::Array (str::from_utf8)")
-                    .and_then(|v| usize::from_str(v).context("Value::Array (usize::from_str)"))
-            })
-        };
+impl FromIterator<Value> for Array {
+    fn from_iter<I: IntoIterator<Item = Value>>(iter: I) -> Self {
+        Self::from(iter.into_iter().collect::<Vec<_>>())
+    }
+}
 
-        let parse_items = |len| many_m_n(len, len, Value::parse);
-        let parse_val = parse_len.flat_map(parse_items);
+impl Extend<Value> for Array {
+    fn extend<I: IntoIterator<Item = Value>>(&mut self, iter: I) {
+        self.val.extend(iter);
+    }
+}
+
+impl IntoIterator for Array {
+    type Item = Value;
+    type IntoIter = vec::IntoIter<Value>;
 
-        let parse_attr = opt(Attribute::parse);
-        pair(parse_attr, parse_val)
-            .map(|(attr, val)| Array { val, attr })
-            .parse(input)
+    fn into_iter(self) -> Self::IntoIter {
+        self.val.into_iter()
     }
 }
 
-impl<I: IntoIterator<Item = Value>> From<I> for Array {
-    fn from(input: I) -> Self {
-        Self {
-            val: input.into_iter().collect(),
-            attr: None,
-        }
+impl<'a> IntoIterator for &'a Array {
+    type Item = &'a Value;
+    type IntoIter = slice::Iter<'a, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.val.iter()
     }
 }
 
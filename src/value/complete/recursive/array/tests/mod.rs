@@ -34,14 +34,14 @@ fn test_heterogenous_simple() {
             &b""[..],
             Array::from([
                 Value::from(BigNumber::from(BigInt::from_str("12345").unwrap())),
-                Value::from(BlobError::new("ERR", b"reason".to_vec())),
+                Value::from(BlobError::new("ERR reason")),
                 Value::from(BlobString::from(b"test".to_vec())),
                 Value::from(Boolean::from(false)),
                 Value::from(Double::inf(double::Sign::Minus)),
                 Value::from(Null::default()),
                 Value::from(Number::from(1234)),
-                Value::from(SimpleError::new("ERR", "reason")),
-                Value::from(SimpleString::from("test")),
+                Value::from(SimpleError::new("ERR reason").unwrap()),
+                Value::from(SimpleString::new("test").unwrap()),
                 Value::from(VerbatimString::txt(b"test".to_vec())),
             ])
         ))
@@ -61,9 +61,9 @@ fn test_nested_array() {
         Ok((
             &b""[..],
             Array::from([
-                Value::Array(Array::from([Value::SimpleString(SimpleString::from(
-                    "test"
-                ))])),
+                Value::Array(Array::from([Value::SimpleString(
+                    SimpleString::new("test").unwrap()
+                )])),
                 Value::Array(Array::from([
                     Value::from(Boolean::from(false)),
                     Value::from(Number::from(-1))
@@ -72,3 +72,31 @@ fn test_nested_array() {
         ))
     );
 }
+
+#[test]
+fn test_mutation() {
+    let num = |n: i64| Value::from(Number::from(n));
+
+    let mut array = (1..=2).map(num).collect::<Array>();
+    array.push(num(4));
+    array.insert(2, num(3));
+    array.extend([num(5), num(6)]);
+    assert_eq!(array.remove(5), num(6));
+
+    assert_eq!(array.len(), 5);
+    assert!(!array.is_empty());
+    assert_eq!(array.val(), (1..=5).map(num).collect::<Vec<_>>());
+
+    array.val_mut().retain(|v| v != &num(1));
+    assert_eq!((&array).into_iter().next(), Some(&num(2)));
+
+    let array = array.with_attr(Attribute::new());
+    assert_eq!(
+        array.clone().into_inner(),
+        (2..=5).map(num).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        array.into_iter().collect::<Array>(),
+        Array::from(vec![num(2), num(3), num(4), num(5)])
+    );
+}
@@ -2,27 +2,26 @@
 pub(crate) mod tests;
 
 use std::{
-    collections::BTreeMap,
     io::Write,
+    slice,
     str::{self, FromStr},
+    vec,
 };
 
 use anyhow::Context;
 use bytes::Bytes;
 use nom::{
-    bytes::complete::tag,
-    character::complete::digit1,
-    combinator::{map_res, opt},
-    multi::many_m_n,
-    sequence::{delimited, pair},
+    bytes::complete::tag, character::complete::digit1, combinator::map_res, sequence::delimited,
     IResult, Parser,
 };
 
 use super::{Attribute, Value, DELIMITER};
 
+/// Entries are kept in wire order, duplicate keys included,
+/// see [`DecodeOptions`](crate::value::DecodeOptions) to drop or reject them.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Map {
-    val: BTreeMap<Value, Value>,
+    val: Vec<(Value, Value)>,
     attr: Option<Attribute>,
 }
 
@@ -33,15 +32,71 @@ impl From<Map> for Value {
 }
 
 impl Map {
-    #[allow(clippy::mutable_key_type)] // FIXME
-    pub fn val(&self) -> &BTreeMap<Value, Value> {
+    pub fn val(&self) -> &[(Value, Value)] {
         &self.val
     }
 
+    pub fn val_mut(&mut self) -> &mut Vec<(Value, Value)> {
+        &mut self.val
+    }
+
+    /// Drops the attribute.
+    pub fn into_inner(self) -> Vec<(Value, Value)> {
+        self.val
+    }
+
+    pub fn len(&self) -> usize {
+        self.val.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.val.is_empty()
+    }
+
+    /// Appends an entry even if the key is already present, like a duplicate on the wire.
+    pub fn push(&mut self, key: Value, value: Value) {
+        self.val.push((key, value));
+    }
+
+    /// Replaces the value of the first entry with this key, returning the old one,
+    /// or appends a new entry.
+    pub fn insert(&mut self, key: Value, value: Value) -> Option<Value> {
+        match self.val.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, v)) => Some(std::mem::replace(v, value)),
+            None => {
+                self.val.push((key, value));
+                None
+            }
+        }
+    }
+
+    /// Removes the first entry with this key, returning its value.
+    pub fn remove(&mut self, key: &Value) -> Option<Value> {
+        let i = self.val.iter().position(|(k, _)| k == key)?;
+        Some(self.val.remove(i).1)
+    }
+
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = (&Value, &Value)> {
+        self.val.iter().map(|(k, v)| (k, v))
+    }
+
+    /// The value of the first entry with this key.
+    pub fn get(&self, key: &Value) -> Option<&Value> {
+        self.iter().find(|(k, _)| *k == key).map(|(_, v)| v)
+    }
+
     pub fn attr(&self) -> Option<&Attribute> {
         self.attr.as_ref()
     }
 
+    pub fn attr_mut(&mut self) -> &mut Option<Attribute> {
+        &mut self.attr
+    }
+
+    pub fn take_attr(&mut self) -> Option<Attribute> {
+        self.attr.take()
+    }
+
     pub fn with_attr(mut self, attr: Attribute) -> Self {
         self.attr = Some(attr);
         self
@@ -49,37 +104,69 @@ impl Map {
 }
 
 impl Map {
+    /// Parses just the header, e.g. `%3\r\n`, returning the number of pairs announced.
+    pub(crate) fn parse_len(input: &[u8]) -> IResult<&[u8], usize> {
+        let parser = delimited(tag("%"), digit1, tag(DELIMITER));
+
+        map_res(parser, |v: &[u8]| {
+            str::from_utf8(v)
+                .context("Value::Map (str::from_utf8)")
+                .and_then(|v| usize::from_str(v).context("Value::Map (usize::from_str)"))
+        })
+        .parse(input)
+    }
+
+    /// Parses a value with [`Value::parse`], failing if it is not a map.
     pub fn parse(input: &[u8]) -> IResult<&[u8], Self> {
-        let parse_len = {
-            let parser = delimited(tag("%"), digit1, tag(DELIMITER));
+        match Value::parse(input)? {
+            (rest, Value::Map(inner)) => Ok((rest, inner)),
+            _ => Err(nom::Err::Error(nom::error::Error::new(
+                input,
+                nom::error::ErrorKind::Verify,
+            ))),
+        }
+    }
+}
+
+impl From<Vec<(Value, Value)>> for Map {
+    fn from(val: Vec<(Value, Value)>) -> Self {
+        Self { val, attr: None }
+    }
+}
 
-            map_res(parser, |v: &[u8]| {
-                str::from_utf8(v)
-                    .context("Value::Map (str::from_utf8)")
-                    .and_then(|v| usize::from_str(v).context("Value::Map (usize::from_str)"))
-            })
-        };
+impl<const N: usize> From<[(Value, Value); N]> for Map {
+    fn from(input: [(Value, Value); N]) -> Self {
+        Self::from(Vec::from(input))
+    }
+}
+
+impl FromIterator<(Value, Value)> for Map {
+    fn from_iter<I: IntoIterator<Item = (Value, Value)>>(iter: I) -> Self {
+        Self::from(iter.into_iter().collect::<Vec<_>>())
+    }
+}
 
-        let parse_items = |len| many_m_n(len, len, pair(Value::parse, Value::parse));
+impl Extend<(Value, Value)> for Map {
+    fn extend<I: IntoIterator<Item = (Value, Value)>>(&mut self, iter: I) {
+        self.val.extend(iter);
+    }
+}
 
-        let parse_attr = opt(Attribute::parse);
-        let parse_val = parse_len.flat_map(parse_items);
+impl IntoIterator for Map {
+    type Item = (Value, Value);
+    type IntoIter = vec::IntoIter<(Value, Value)>;
 
-        pair(parse_attr, parse_val)
-            .map(|(attr, val)| Map {
-                val: val.into_iter().collect(),
-                attr,
-            })
-            .parse(input)
+    fn into_iter(self) -> Self::IntoIter {
+        self.val.into_iter()
     }
 }
 
-impl<I: IntoIterator<Item = (Value, Value)>> From<I> for Map {
-    fn from(input: I) -> Self {
-        Self {
-            val: input.into_iter().collect(),
-            attr: None,
-        }
+impl<'a> IntoIterator for &'a Map {
+    type Item = &'a (Value, Value);
+    type IntoIter = slice::Iter<'a, (Value, Value)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.val.iter()
     }
 }
 
@@ -99,7 +186,7 @@ impl TryFrom<&Map> for Bytes {
             .and_then(|_| buf.write(DELIMITER))
             .context("Value::Map (buf::write)")?;
 
-        for (k, v) in input.val().iter() {
+        for (k, v) in input.iter() {
             let bytes = Bytes::try_from(k).context("Value::Map (Bytes::try_from)")?;
             buf.write(&bytes).context("Value::Map (buf::write)")?;
 
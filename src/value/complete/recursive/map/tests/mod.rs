@@ -37,7 +37,7 @@ fn test_heterogenous_keys_simple() {
                     Value::from(Null::default())
                 ),
                 (
-                    Value::from(BlobError::new("ERR", b"reason".to_vec())),
+                    Value::from(BlobError::new("ERR reason")),
                     Value::from(Null::default())
                 ),
                 (
@@ -58,11 +58,11 @@ fn test_heterogenous_keys_simple() {
                     Value::from(Null::default())
                 ),
                 (
-                    Value::from(SimpleError::new("ERR", "reason")),
+                    Value::from(SimpleError::new("ERR reason").unwrap()),
                     Value::from(Null::default())
                 ),
                 (
-                    Value::from(SimpleString::from("test")),
+                    Value::from(SimpleString::new("test").unwrap()),
                     Value::from(Null::default())
                 ),
                 (
@@ -101,7 +101,7 @@ fn test_heterogenous_value_simple() {
                 ),
                 (
                     Value::from(Number::from(1)),
-                    Value::from(BlobError::new("ERR", b"reason".to_vec()))
+                    Value::from(BlobError::new("ERR reason"))
                 ),
                 (
                     Value::from(Number::from(2)),
@@ -122,11 +122,11 @@ fn test_heterogenous_value_simple() {
                 ),
                 (
                     Value::from(Number::from(7)),
-                    Value::from(SimpleError::new("ERR", "reason"))
+                    Value::from(SimpleError::new("ERR reason").unwrap())
                 ),
                 (
                     Value::from(Number::from(8)),
-                    Value::from(SimpleString::from("test"))
+                    Value::from(SimpleString::new("test").unwrap())
                 ),
                 (
                     Value::from(Number::from(9)),
@@ -197,3 +197,62 @@ fn test_nested_map_value() {
         ))
     );
 }
+
+#[test]
+fn test_wire_order() {
+    let raw = b"%3\r\n+b\r\n:1\r\n+a\r\n:2\r\n+b\r\n:3\r\n";
+    let (_, map) = Map::parse(raw).unwrap();
+
+    let key = |k: &str| Value::from(SimpleString::new(k.to_string()).unwrap());
+    assert_eq!(
+        map.val(),
+        [
+            (key("b"), Value::from(Number::from(1))),
+            (key("a"), Value::from(Number::from(2))),
+            (key("b"), Value::from(Number::from(3))),
+        ]
+    );
+    assert_eq!(map.get(&key("b")), Some(&Value::from(Number::from(1))));
+    assert_eq!(map.get(&key("c")), None);
+
+    assert_eq!(Bytes::try_from(&map).unwrap(), &raw[..]);
+}
+
+#[test]
+fn test_mutation() {
+    let key = |k: &str| Value::from(SimpleString::new(k.to_string()).unwrap());
+    let num = |n: i64| Value::from(Number::from(n));
+
+    let mut map = Map::from([(key("a"), num(1))]);
+    assert_eq!(map.insert(key("b"), num(2)), None);
+    assert_eq!(map.insert(key("a"), num(3)), Some(num(1)));
+    map.push(key("a"), num(4));
+    map.extend([(key("c"), num(5))]);
+
+    assert_eq!(map.len(), 4);
+    assert_eq!(
+        map.val(),
+        [
+            (key("a"), num(3)),
+            (key("b"), num(2)),
+            (key("a"), num(4)),
+            (key("c"), num(5)),
+        ]
+    );
+
+    assert_eq!(map.remove(&key("a")), Some(num(3)));
+    assert_eq!(map.remove(&key("d")), None);
+    assert_eq!(map.get(&key("a")), Some(&num(4)));
+
+    let keys = (&map)
+        .into_iter()
+        .map(|(k, _)| k.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(keys, [key("b"), key("a"), key("c")]);
+
+    let map = map.into_iter().rev().collect::<Map>();
+    assert_eq!(
+        map.into_inner(),
+        [(key("c"), num(5)), (key("a"), num(4)), (key("b"), num(2))]
+    );
+}
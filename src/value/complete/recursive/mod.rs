@@ -1,9 +1,11 @@
 pub mod array;
 pub mod map;
+pub mod push;
 pub mod set;
 
 use super::*;
 
 pub use array::Array;
 pub use map::Map;
+pub use push::Push;
 pub use set::Set;
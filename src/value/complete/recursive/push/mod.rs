@@ -0,0 +1,194 @@
+#[cfg(test)]
+pub(crate) mod tests;
+
+use std::{
+    io::Write,
+    slice,
+    str::{self, FromStr},
+    vec,
+};
+
+use anyhow::Context;
+use bytes::Bytes;
+use nom::{
+    bytes::complete::tag, character::complete::digit1, combinator::map_res, sequence::delimited,
+    IResult, Parser,
+};
+
+use super::{Attribute, Value, DELIMITER};
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Push {
+    val: Vec<Value>,
+    attr: Option<Attribute>,
+}
+
+impl From<Push> for Value {
+    fn from(input: Push) -> Value {
+        Value::Push(input)
+    }
+}
+
+impl Push {
+    pub fn val(&self) -> &[Value] {
+        &self.val
+    }
+
+    pub fn val_mut(&mut self) -> &mut Vec<Value> {
+        &mut self.val
+    }
+
+    /// Drops the attribute.
+    pub fn into_inner(self) -> Vec<Value> {
+        self.val
+    }
+
+    pub fn len(&self) -> usize {
+        self.val.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.val.is_empty()
+    }
+
+    pub fn push(&mut self, value: Value) {
+        self.val.push(value);
+    }
+
+    /// Panics if `index > len`.
+    pub fn insert(&mut self, index: usize, value: Value) {
+        self.val.insert(index, value);
+    }
+
+    /// Panics if `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> Value {
+        self.val.remove(index)
+    }
+
+    /// The leading string naming the kind of the message (e.g. `message` or `invalidate`).
+    pub fn kind(&self) -> Option<&[u8]> {
+        match self.val.first()? {
+            Value::BlobString(inner) => Some(inner.val()),
+            Value::SimpleString(inner) => Some(inner.val()),
+            _ => None,
+        }
+    }
+
+    pub fn attr(&self) -> Option<&Attribute> {
+        self.attr.as_ref()
+    }
+
+    pub fn attr_mut(&mut self) -> &mut Option<Attribute> {
+        &mut self.attr
+    }
+
+    pub fn take_attr(&mut self) -> Option<Attribute> {
+        self.attr.take()
+    }
+
+    pub fn with_attr(mut self, attr: Attribute) -> Self {
+        self.attr = Some(attr);
+        self
+    }
+}
+
+impl Push {
+    /// Parses just the header, e.g. `>3\r\n`, returning the number of items announced.
+    pub(crate) fn parse_len(input: &[u8]) -> IResult<&[u8], usize> {
+        let parser = delimited(tag(">"), digit1, tag(DELIMITER));
+
+        map_res(parser, |v: &[u8]| {
+            str::from_utf8(v)
+                .context("Value::Push (str::from_utf8)")
+                .and_then(|v| usize::from_str(v).context("Value::Push (usize::from_str)"))
+        })
+        .parse(input)
+    }
+
+    /// Parses a value with [`Value::parse`], failing if it is not a push.
+    pub fn parse(input: &[u8]) -> IResult<&[u8], Self> {
+        match Value::parse(input)? {
+            (rest, Value::Push(inner)) => Ok((rest, inner)),
+            _ => Err(nom::Err::Error(nom::error::Error::new(
+                input,
+                nom::error::ErrorKind::Verify,
+            ))),
+        }
+    }
+}
+
+impl From<Vec<Value>> for Push {
+    fn from(val: Vec<Value>) -> Self {
+        Self { val, attr: None }
+    }
+}
+
+impl<const N: usize> From<[Value; N]> for Push {
+    fn from(input: [Value; N]) -> Self {
+        Self::from(Vec::from(input))
+    }
+}
+
+impl FromIterator<Value> for Push {
+    fn from_iter<I: IntoIterator<Item = Value>>(iter: I) -> Self {
+        Self::from(iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl Extend<Value> for Push {
+    fn extend<I: IntoIterator<Item = Value>>(&mut self, iter: I) {
+        self.val.extend(iter);
+    }
+}
+
+impl IntoIterator for Push {
+    type Item = Value;
+    type IntoIter = vec::IntoIter<Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.val.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Push {
+    type Item = &'a Value;
+    type IntoIter = slice::Iter<'a, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.val.iter()
+    }
+}
+
+impl TryFrom<&Push> for Bytes {
+    type Error = anyhow::Error;
+
+    fn try_from(input: &Push) -> anyhow::Result<Bytes> {
+        let mut buf = vec![];
+
+        if let Some(attr) = input.attr.as_ref() {
+            let bytes = Bytes::try_from(attr).context("Value::Push (Bytes::from)")?;
+            buf.write(&bytes).context("Value::Push (buf::write)")?;
+        }
+
+        buf.write(b">")
+            .and_then(|_| buf.write(input.val().len().to_string().as_bytes()))
+            .and_then(|_| buf.write(DELIMITER))
+            .context("Value::Push (buf::write)")?;
+
+        for value in input.val().iter() {
+            let bytes = Bytes::try_from(value).context("Value::Push (Bytes::try_from)")?;
+            buf.write(&bytes).context("Value::Push (buf::write)")?;
+        }
+
+        buf.flush().context("Value::Push (buf::flush)")?;
+        Ok(Bytes::from(buf))
+    }
+}
+
+impl TryFrom<Push> for Bytes {
+    type Error = anyhow::Error;
+
+    fn try_from(input: Push) -> anyhow::Result<Bytes> {
+        Bytes::try_from(&input)
+    }
+}
@@ -0,0 +1,32 @@
+pub mod prop;
+
+use super::*;
+use crate::value::*;
+
+#[test]
+fn test_empty() {
+    assert_eq!(Push::parse(&b">0\r\n"[..]), Ok((&b""[..], Push::from([]))));
+}
+
+#[test]
+fn test_message() {
+    let raw = "\
+                   >3\r\n\
+                   $7\r\nmessage\r\n\
+                   $7\r\nchannel\r\n\
+                   $5\r\nhello\r\n\
+                   ";
+
+    let (rest, push) = Push::parse(raw.as_bytes()).unwrap();
+
+    assert!(rest.is_empty());
+    assert_eq!(push.kind(), Some(&b"message"[..]));
+    assert_eq!(
+        push,
+        Push::from([
+            Value::from(BlobString::from("message")),
+            Value::from(BlobString::from("channel")),
+            Value::from(BlobString::from("hello")),
+        ])
+    );
+}
@@ -0,0 +1,29 @@
+use bytes::Bytes;
+use proptest::prelude::*;
+
+use super::Push;
+use crate::value::complete::special::attribute::tests::prop::value as attr_value;
+
+pub fn value() -> impl Strategy<Value = Push> {
+    prop::collection::vec(crate::value::tests::prop::value(), 0..=10).prop_map(Push::from)
+}
+
+prop_compose! {
+    pub fn value_with_attr()(
+        val in value(),
+        attr in prop::option::of(attr_value())
+    ) -> Push {
+        attr.map(|attr| val.clone().with_attr(attr)).unwrap_or(val)
+    }
+}
+
+proptest! {
+    #[test]
+    fn test_basic(v in value_with_attr()) {
+        let bytes = Bytes::try_from(v.clone()).unwrap();
+        let (rest, parsed) = Push::parse(&bytes).unwrap();
+
+        assert!(rest.is_empty());
+        assert_eq!(parsed, v);
+    }
+}
@@ -2,28 +2,26 @@
 pub(crate) mod tests;
 
 use std::{
-    collections::BTreeSet,
     io::Write,
+    slice,
     str::{self, FromStr},
+    vec,
 };
 
 use anyhow::Context;
 use bytes::Bytes;
 use nom::{
-    bytes::complete::tag,
-    character::complete::digit1,
-    combinator::map_res,
-    combinator::opt,
-    multi::many_m_n,
-    sequence::{delimited, pair},
+    bytes::complete::tag, character::complete::digit1, combinator::map_res, sequence::delimited,
     IResult, Parser,
 };
 
 use super::{Attribute, Value, DELIMITER};
 
+/// Items are kept in wire order, duplicates included,
+/// see [`DecodeOptions`](crate::value::DecodeOptions) to drop or reject them.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Set {
-    val: BTreeSet<Value>,
+    val: Vec<Value>,
     attr: Option<Attribute>,
 }
 
@@ -34,15 +32,70 @@ impl From<Set> for Value {
 }
 
 impl Set {
-    #[allow(clippy::mutable_key_type)] // FIXME
-    pub fn val(&self) -> &BTreeSet<Value> {
+    pub fn val(&self) -> &[Value] {
         &self.val
     }
 
+    pub fn val_mut(&mut self) -> &mut Vec<Value> {
+        &mut self.val
+    }
+
+    /// Drops the attribute.
+    pub fn into_inner(self) -> Vec<Value> {
+        self.val
+    }
+
+    pub fn len(&self) -> usize {
+        self.val.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.val.is_empty()
+    }
+
+    pub fn contains(&self, value: &Value) -> bool {
+        self.val.contains(value)
+    }
+
+    /// Appends `value` even if it is already present, like a duplicate on the wire.
+    pub fn push(&mut self, value: Value) {
+        self.val.push(value);
+    }
+
+    /// Appends `value` unless it is already present, returning whether it was added.
+    pub fn insert(&mut self, value: Value) -> bool {
+        match self.contains(&value) {
+            true => false,
+            false => {
+                self.val.push(value);
+                true
+            }
+        }
+    }
+
+    /// Removes the first item equal to `value`, returning whether there was one.
+    pub fn remove(&mut self, value: &Value) -> bool {
+        match self.val.iter().position(|v| v == value) {
+            Some(i) => {
+                self.val.remove(i);
+                true
+            }
+            None => false,
+        }
+    }
+
     pub fn attr(&self) -> Option<&Attribute> {
         self.attr.as_ref()
     }
 
+    pub fn attr_mut(&mut self) -> &mut Option<Attribute> {
+        &mut self.attr
+    }
+
+    pub fn take_attr(&mut self) -> Option<Attribute> {
+        self.attr.take()
+    }
+
     pub fn with_attr(mut self, attr: Attribute) -> Self {
         self.attr = Some(attr);
         self
@@ -50,37 +103,69 @@ impl Set {
 }
 
 impl Set {
+    /// Parses just the header, e.g. `~3\r\n`, returning the number of items announced.
+    pub(crate) fn parse_len(input: &[u8]) -> IResult<&[u8], usize> {
+        let parser = delimited(tag("~"), digit1, tag(DELIMITER));
+
+        map_res(parser, |v: &[u8]| {
+            str::from_utf8(v)
+                .context("Value::Set (str::from_utf8)")
+                .and_then(|v| usize::from_str(v).context("Value::Set (usize::from_str)"))
+        })
+        .parse(input)
+    }
+
+    /// Parses a value with [`Value::parse`], failing if it is not a set.
     pub fn parse(input: &[u8]) -> IResult<&[u8], Self> {
-        let parse_len = {
-            let parser = delimited(tag("~"), digit1, tag(DELIMITER));
+        match Value::parse(input)? {
+            (rest, Value::Set(inner)) => Ok((rest, inner)),
+            _ => Err(nom::Err::Error(nom::error::Error::new(
+                input,
+                nom::error::ErrorKind::Verify,
+            ))),
+        }
+    }
+}
 
-            map_res(parser, |v: &[u8]| {
-                str::from_utf8(v)
-                    .context("Value::Set (str::from_utf8)")
-                    .and_then(|v| usize::from_str(v).context("Value::Set (usize::from_str)"))
-            })
-        };
+impl From<Vec<Value>> for Set {
+    fn from(val: Vec<Value>) -> Self {
+        Self { val, attr: None }
+    }
+}
 
-        let parse_items = |len| many_m_n(len, len, Value::parse);
+impl<const N: usize> From<[Value; N]> for Set {
+    fn from(input: [Value; N]) -> Self {
+        Self::from(Vec::from(input))
+    }
+}
 
-        let parse_attr = opt(Attribute::parse);
-        let parse_val = parse_len.flat_map(parse_items);
+impl FromIterator<Value> for Set {
+    fn from_iter<I: IntoIterator<Item = Value>>(iter: I) -> Self {
+        Self::from(iter.into_iter().collect::<Vec<_>>())
+    }
+}
 
-        pair(parse_attr, parse_val)
-            .map(|(attr, val)| Set {
-                val: val.into_iter().collect(),
-                attr,
-            })
-            .parse(input)
+impl Extend<Value> for Set {
+    fn extend<I: IntoIterator<Item = Value>>(&mut self, iter: I) {
+        self.val.extend(iter);
     }
 }
 
-impl<I: IntoIterator<Item = Value>> From<I> for Set {
-    fn from(input: I) -> Self {
-        Self {
-            val: input.into_iter().collect(),
-            attr: None,
-        }
+impl IntoIterator for Set {
+    type Item = Value;
+    type IntoIter = vec::IntoIter<Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.val.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Set {
+    type Item = &'a Value;
+    type IntoIter = slice::Iter<'a, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.val.iter()
     }
 }
 
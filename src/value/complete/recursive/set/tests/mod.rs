@@ -33,14 +33,14 @@ fn test_heterogenous_simple() {
             &b""[..],
             Set::from([
                 Value::from(BigNumber::from(BigInt::from_str("12345").unwrap())),
-                Value::from(BlobError::new("ERR", b"reason".to_vec())),
+                Value::from(BlobError::new("ERR reason")),
                 Value::from(BlobString::from(b"test".to_vec())),
                 Value::from(Boolean::from(false)),
                 Value::from(Double::inf(double::Sign::Minus)),
                 Value::from(Null::default()),
                 Value::from(Number::from(1234)),
-                Value::from(SimpleError::new("ERR", "reason")),
-                Value::from(SimpleString::from("test")),
+                Value::from(SimpleError::new("ERR reason").unwrap()),
+                Value::from(SimpleString::new("test").unwrap()),
                 Value::from(VerbatimString::txt(b"test".to_vec())),
             ])
         ))
@@ -60,7 +60,9 @@ fn test_nested_array() {
         Ok((
             &b""[..],
             Set::from([
-                Value::Set(Set::from([Value::SimpleString(SimpleString::from("test"))])),
+                Value::Set(Set::from([Value::SimpleString(
+                    SimpleString::new("test").unwrap()
+                )])),
                 Value::Set(Set::from([
                     Value::from(Boolean::from(false)),
                     Value::from(Number::from(-1))
@@ -69,3 +71,44 @@ fn test_nested_array() {
         ))
     );
 }
+
+#[test]
+fn test_wire_order() {
+    let raw = b"~3\r\n:2\r\n:1\r\n:2\r\n";
+    let (_, set) = Set::parse(raw).unwrap();
+
+    assert_eq!(
+        set.val(),
+        [
+            Value::from(Number::from(2)),
+            Value::from(Number::from(1)),
+            Value::from(Number::from(2)),
+        ]
+    );
+
+    assert_eq!(Bytes::try_from(&set).unwrap(), &raw[..]);
+}
+
+#[test]
+fn test_mutation() {
+    let num = |n: i64| Value::from(Number::from(n));
+
+    let mut set = Set::from([num(1)]);
+    assert!(set.insert(num(2)));
+    assert!(!set.insert(num(1)));
+    set.push(num(1));
+    set.extend([num(3)]);
+
+    assert_eq!(set.len(), 4);
+    assert!(set.contains(&num(3)));
+
+    assert!(set.remove(&num(1)));
+    assert!(!set.remove(&num(4)));
+    assert_eq!(set.val(), [num(2), num(1), num(3)]);
+
+    assert_eq!((&set).into_iter().count(), 3);
+    assert_eq!(
+        set.into_iter().collect::<Vec<_>>(),
+        [num(2), num(1), num(3)]
+    );
+}
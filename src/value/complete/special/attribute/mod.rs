@@ -2,9 +2,10 @@
 pub(crate) mod tests;
 
 use std::{
-    collections::BTreeMap,
     io::Write,
+    slice,
     str::{self, FromStr},
+    vec,
 };
 
 use anyhow::Context;
@@ -20,32 +21,182 @@ use nom::{
 
 use super::{Value, DELIMITER};
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct Attribute(BTreeMap<Value, Value>);
+/// Entries are kept in wire order, duplicate keys included, like those of a
+/// [`Map`](crate::value::Map), see [`DecodeOptions`](crate::value::DecodeOptions)
+/// to drop or reject them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Attribute(Vec<(Value, Value)>);
 
 impl Attribute {
-    pub fn parse(input: &[u8]) -> IResult<&[u8], Self> {
-        let parse_len = {
-            let parser = delimited(tag("|"), digit1, tag(DELIMITER));
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn val(&self) -> &[(Value, Value)] {
+        &self.0
+    }
+
+    pub fn val_mut(&mut self) -> &mut Vec<(Value, Value)> {
+        &mut self.0
+    }
+
+    pub fn into_inner(self) -> Vec<(Value, Value)> {
+        self.0
+    }
 
-            map_res(parser, |v: &[u8]| {
-                str::from_utf8(v)
-                    .context("Value::Map (str::from_utf8)")
-                    .and_then(|v| usize::from_str(v).context("Value::Map (usize::from_str)"))
+    /// The value of the first entry with this key.
+    pub fn get(&self, key: &Value) -> Option<&Value> {
+        self.iter().find(|(k, _)| *k == key).map(|(_, v)| v)
+    }
+
+    /// Looks a value up by a blob or simple string key, e.g. `ttl`.
+    pub fn find(&self, name: impl AsRef<[u8]>) -> Option<&Value> {
+        let name = name.as_ref();
+
+        self.iter()
+            .find(|(k, _)| match k {
+                Value::BlobString(k) => k.val() == name,
+                Value::SimpleString(k) => k.val() == name,
+                _ => false,
             })
-        };
+            .map(|(_, v)| v)
+    }
+
+    pub fn get_i64(&self, name: impl AsRef<[u8]>) -> Option<i64> {
+        match self.find(name)? {
+            Value::Number(v) => Some(v.val()),
+            _ => None,
+        }
+    }
+
+    pub fn get_f64(&self, name: impl AsRef<[u8]>) -> Option<f64> {
+        match self.find(name)? {
+            Value::Double(v) => Some(v.val()),
+            Value::Number(v) => Some(v.val() as f64),
+            _ => None,
+        }
+    }
+
+    pub fn get_bool(&self, name: impl AsRef<[u8]>) -> Option<bool> {
+        match self.find(name)? {
+            Value::Boolean(v) => Some(v.val()),
+            _ => None,
+        }
+    }
+
+    /// The contents of a blob, simple or verbatim string.
+    pub fn get_bytes(&self, name: impl AsRef<[u8]>) -> Option<&[u8]> {
+        match self.find(name)? {
+            Value::BlobString(v) => Some(v.val()),
+            Value::SimpleString(v) => Some(v.val()),
+            Value::VerbatimString(v) => Some(v.val()),
+            _ => None,
+        }
+    }
+
+    /// Like [`Attribute::get_bytes`], `None` if the contents are not valid UTF-8.
+    pub fn get_str(&self, name: impl AsRef<[u8]>) -> Option<&str> {
+        self.get_bytes(name)
+            .and_then(|bytes| str::from_utf8(bytes).ok())
+    }
+
+    /// Appends an entry even if the key is already present, like a duplicate on the wire.
+    pub fn push(&mut self, key: Value, value: Value) {
+        self.0.push((key, value));
+    }
+
+    /// Replaces the value of the first entry with this key, returning the old one,
+    /// or appends a new entry.
+    pub fn insert(&mut self, key: Value, value: Value) -> Option<Value> {
+        match self.0.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, v)) => Some(std::mem::replace(v, value)),
+            None => {
+                self.0.push((key, value));
+                None
+            }
+        }
+    }
 
+    /// Removes the first entry with this key, returning its value.
+    pub fn remove(&mut self, key: &Value) -> Option<Value> {
+        let i = self.0.iter().position(|(k, _)| k == key)?;
+        Some(self.0.remove(i).1)
+    }
+
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = (&Value, &Value)> {
+        self.0.iter().map(|(k, v)| (k, v))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Attribute {
+    /// Parses just the header, e.g. `|1\r\n`, returning the number of pairs announced.
+    pub(crate) fn parse_len(input: &[u8]) -> IResult<&[u8], usize> {
+        let parser = delimited(tag("|"), digit1, tag(DELIMITER));
+
+        map_res(parser, |v: &[u8]| {
+            str::from_utf8(v)
+                .context("Value::Attribute (str::from_utf8)")
+                .and_then(|v| usize::from_str(v).context("Value::Attribute (usize::from_str)"))
+        })
+        .parse(input)
+    }
+
+    pub fn parse(input: &[u8]) -> IResult<&[u8], Self> {
         let parse_val = |len| many_m_n(len, len, pair(Value::parse, Value::parse));
-        parse_len
+        Self::parse_len
             .flat_map(parse_val)
             .map(Attribute::from)
             .parse(input)
     }
 }
 
-impl<I: IntoIterator<Item = (Value, Value)>> From<I> for Attribute {
-    fn from(input: I) -> Self {
-        Self(input.into_iter().collect())
+impl From<Vec<(Value, Value)>> for Attribute {
+    fn from(input: Vec<(Value, Value)>) -> Self {
+        Self(input)
+    }
+}
+
+impl<const N: usize> From<[(Value, Value); N]> for Attribute {
+    fn from(input: [(Value, Value); N]) -> Self {
+        Self::from(Vec::from(input))
+    }
+}
+
+impl FromIterator<(Value, Value)> for Attribute {
+    fn from_iter<I: IntoIterator<Item = (Value, Value)>>(iter: I) -> Self {
+        Self::from(iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl Extend<(Value, Value)> for Attribute {
+    fn extend<I: IntoIterator<Item = (Value, Value)>>(&mut self, iter: I) {
+        self.0.extend(iter);
+    }
+}
+
+impl IntoIterator for Attribute {
+    type Item = (Value, Value);
+    type IntoIter = vec::IntoIter<(Value, Value)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Attribute {
+    type Item = &'a (Value, Value);
+    type IntoIter = slice::Iter<'a, (Value, Value)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
     }
 }
 
@@ -56,11 +207,11 @@ impl TryFrom<&Attribute> for Bytes {
         let mut buf = vec![];
 
         buf.write(b"|")
-            .and_then(|_| buf.write(input.0.len().to_string().as_bytes()))
+            .and_then(|_| buf.write(input.len().to_string().as_bytes()))
             .and_then(|_| buf.write(DELIMITER))
             .context("Value::Attribute (buf::write)")?;
 
-        for (k, v) in input.0.iter() {
+        for (k, v) in input.iter() {
             let bytes = Bytes::try_from(k).context("Value::Attribute (Bytes::try_from)")?;
             buf.write(&bytes).context("Value::Attribute (buf::write)")?;
 
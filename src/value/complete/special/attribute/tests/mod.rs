@@ -40,7 +40,7 @@ fn test_heterogenous_keys_simple() {
                     Value::from(Null::default())
                 ),
                 (
-                    Value::from(BlobError::new("ERR", b"reason".to_vec())),
+                    Value::from(BlobError::new("ERR reason")),
                     Value::from(Null::default())
                 ),
                 (
@@ -61,11 +61,11 @@ fn test_heterogenous_keys_simple() {
                     Value::from(Null::default())
                 ),
                 (
-                    Value::from(SimpleError::new("ERR", "reason")),
+                    Value::from(SimpleError::new("ERR reason").unwrap()),
                     Value::from(Null::default())
                 ),
                 (
-                    Value::from(SimpleString::from("test")),
+                    Value::from(SimpleString::new("test").unwrap()),
                     Value::from(Null::default())
                 ),
                 (
@@ -76,3 +76,102 @@ fn test_heterogenous_keys_simple() {
         ))
     );
 }
+
+#[test]
+fn test_typed_getters() {
+    let raw = b"\
+               |6\r\n\
+               +ttl\r\n:3600\r\n\
+               $5\r\nratio\r\n,0.5\r\n\
+               +hot\r\n#t\r\n\
+               +key\r\n$3\r\nfoo\r\n\
+               +bin\r\n$1\r\n\xff\r\n\
+               :1\r\n+numeric key\r\n\
+               ";
+    let (_, attr) = Attribute::parse(raw).unwrap();
+
+    assert_eq!(attr.len(), 6);
+    assert_eq!(attr.get_i64("ttl"), Some(3600));
+    assert_eq!(attr.get_f64("ttl"), Some(3600.0));
+    assert_eq!(attr.get_f64(b"ratio"), Some(0.5));
+    assert_eq!(attr.get_bool("hot"), Some(true));
+    assert_eq!(attr.get_str("key"), Some("foo"));
+    assert_eq!(attr.get_bytes("bin"), Some(&b"\xff"[..]));
+    assert_eq!(attr.get_str("bin"), None);
+    assert_eq!(attr.get_i64("key"), None);
+    assert_eq!(attr.get_i64("missing"), None);
+    assert_eq!(
+        attr.get(&Value::from(Number::from(1))),
+        Some(&Value::from(SimpleString::new("numeric key").unwrap()))
+    );
+}
+
+#[test]
+fn test_insert_remove() {
+    let key = Value::from(SimpleString::new("ttl").unwrap());
+
+    let mut attr = Attribute::new();
+    assert!(attr.is_empty());
+
+    assert_eq!(attr.insert(key.clone(), Value::from(Number::from(1))), None);
+    assert_eq!(
+        attr.insert(key.clone(), Value::from(Number::from(2))),
+        Some(Value::from(Number::from(1)))
+    );
+    assert_eq!(attr.len(), 1);
+    assert_eq!(attr.get_i64("ttl"), Some(2));
+
+    assert_eq!(attr.remove(&key), Some(Value::from(Number::from(2))));
+    assert!(attr.is_empty());
+}
+
+#[test]
+fn test_take_attr() {
+    let (_, mut value) = Value::parse(&b"|1\r\n+ttl\r\n:1\r\n:5\r\n"[..]).unwrap();
+
+    if let Some(attr) = value.attr_mut() {
+        attr.insert(
+            Value::from(SimpleString::new("hot").unwrap()),
+            Value::from(Boolean::from(true)),
+        );
+    }
+
+    let attr = value.take_attr().unwrap();
+    assert_eq!(attr.get_i64("ttl"), Some(1));
+    assert_eq!(attr.get_bool("hot"), Some(true));
+
+    assert_eq!(value, Value::from(Number::from(5)));
+    assert_eq!(value.take_attr(), None);
+}
+
+#[test]
+fn test_ownership() {
+    let key = |k: &str| Value::from(SimpleString::new(k.to_string()).unwrap());
+
+    let mut attr = [(key("b"), Value::from(Number::from(2)))]
+        .into_iter()
+        .collect::<Attribute>();
+    attr.extend([(key("a"), Value::from(Number::from(1)))]);
+    attr.val_mut()
+        .retain(|(_, v)| v != &Value::from(Number::from(2)));
+
+    assert_eq!((&attr).into_iter().count(), 1);
+    assert_eq!(
+        attr.into_iter().collect::<Vec<_>>(),
+        [(key("a"), Value::from(Number::from(1)))]
+    );
+}
+
+#[test]
+fn test_wire_order() {
+    let raw = &b"|2\r\n+b\r\n:1\r\n+a\r\n:2\r\n:5\r\n"[..];
+    let (_, value) = Value::parse(raw).unwrap();
+    assert_eq!(Bytes::try_from(&value).unwrap(), raw);
+
+    // duplicate keys survive too, lookups see the first one
+    let raw = &b"|2\r\n+a\r\n:1\r\n+a\r\n:2\r\n:5\r\n"[..];
+    let (_, value) = Value::parse(raw).unwrap();
+    assert_eq!(Bytes::try_from(&value).unwrap(), raw);
+    assert_eq!(value.attr().unwrap().len(), 2);
+    assert_eq!(value.attr().unwrap().get_i64("a"), Some(1));
+}
@@ -2,11 +2,12 @@
 pub(crate) mod tests;
 
 use std::{
+    borrow::Cow,
     io::Write,
     str::{self, FromStr},
 };
 
-use super::{Attribute, Value, DELIMITER};
+use super::{simple_error::split_code, Attribute, Value, DELIMITER};
 
 use anyhow::Context;
 use bytes::Bytes;
@@ -15,20 +16,15 @@ use nom::{
     character::complete::digit1,
     combinator::map_res,
     combinator::opt,
-    sequence::{delimited, terminated},
+    sequence::{delimited, pair, terminated},
     IResult, Parser,
 };
-use nom_regex::bytes::re_find;
-use once_cell::sync::Lazy;
-use regex::bytes::Regex;
-
-static CODE: Lazy<Regex> = Lazy::new(|| Regex::new(r"[A-Z]+").unwrap());
 
+/// A binary-safe error reply, see [`SimpleError`](super::SimpleError) for the conventions.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct BlobError {
-    pub code: String,
-    pub msg: Bytes,
-    pub attr: Option<Attribute>,
+    val: Bytes,
+    attr: Option<Attribute>,
 }
 
 impl From<BlobError> for Value {
@@ -38,10 +34,9 @@ impl From<BlobError> for Value {
 }
 
 impl BlobError {
-    pub fn new(code: impl Into<String>, msg: impl Into<Bytes>) -> Self {
+    pub fn new(val: impl Into<Bytes>) -> Self {
         Self {
-            code: code.into(),
-            msg: msg.into(),
+            val: val.into(),
             attr: None,
         }
     }
@@ -51,22 +46,46 @@ impl BlobError {
         self
     }
 
-    pub fn code(&self) -> &str {
-        &self.code
+    /// The whole payload, code included.
+    pub fn val(&self) -> &Bytes {
+        &self.val
+    }
+
+    /// The leading error code, if the payload starts with one.
+    pub fn code(&self) -> Option<&str> {
+        split_code(&self.val).0
+    }
+
+    /// The payload without the error code.
+    pub fn msg(&self) -> &[u8] {
+        split_code(&self.val).1
+    }
+
+    /// `None` if the payload is not valid UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        str::from_utf8(&self.val).ok()
     }
 
-    pub fn msg(&self) -> &Bytes {
-        &self.msg
+    pub fn to_string_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.val)
     }
 
     pub fn attr(&self) -> Option<&Attribute> {
         self.attr.as_ref()
     }
+
+    pub fn attr_mut(&mut self) -> &mut Option<Attribute> {
+        &mut self.attr
+    }
+
+    pub fn take_attr(&mut self) -> Option<Attribute> {
+        self.attr.take()
+    }
 }
 
 impl BlobError {
     pub fn parse(input: &[u8]) -> IResult<&[u8], Self> {
-        let mut parse_len = {
+        let parse_len = {
             let parser = delimited(tag("!"), digit1, tag(DELIMITER));
 
             map_res(parser, |v: &[u8]| {
@@ -76,22 +95,15 @@ impl BlobError {
             })
         };
 
-        let (input, attr) = opt(Attribute::parse).parse(input)?;
-        let (input, len) = parse_len.parse(input)?;
-        let (input, msg) = terminated(take(len), tag(DELIMITER)).parse(input)?;
-
-        let code = Lazy::force(&CODE).to_owned();
-        let (msg, code) = map_res(terminated(re_find(code), tag(" ")), |code: &[u8]| {
-            str::from_utf8(code).context("Value::BlobError (str::from_utf8)")
-        })
-        .parse(msg)?;
+        let parse_attr = opt(Attribute::parse);
+        let parse_val = |len| terminated(take(len), tag(DELIMITER));
 
-        let mut value = BlobError::new(code, msg.to_vec());
-        if let Some(attr) = attr {
-            value = value.with_attr(attr);
-        }
-
-        Ok((input, value))
+        pair(parse_attr, parse_len.flat_map(parse_val))
+            .map(|(attr, val)| BlobError {
+                val: Bytes::copy_from_slice(val),
+                attr,
+            })
+            .parse(input)
     }
 }
 
@@ -106,15 +118,10 @@ impl TryFrom<&BlobError> for Bytes {
             buf.write(&bytes).context("Value::BlobError (buf::write)")?;
         }
 
-        buf.write("!".as_bytes())
-            .and_then(|_| {
-                let len = input.code.as_bytes().len() + input.msg.len() + 1;
-                buf.write(len.to_string().as_bytes())
-            })
+        buf.write(b"!")
+            .and_then(|_| buf.write(input.val().len().to_string().as_bytes()))
             .and_then(|_| buf.write(DELIMITER))
-            .and_then(|_| buf.write(input.code.to_string().as_bytes()))
-            .and_then(|_| buf.write(b" "))
-            .and_then(|_| buf.write(&input.msg))
+            .and_then(|_| buf.write(input.val()))
             .and_then(|_| buf.write(DELIMITER))
             .context("Value::BlobError (buf::write)")?;
 
@@ -6,6 +6,17 @@ use super::*;
 fn test_basic() {
     assert_eq!(
         BlobError::parse(&b"!10\r\nERR reason\r\n"[..]),
-        Ok((&b""[..], BlobError::new("ERR", b"reason".to_vec())))
+        Ok((&b""[..], BlobError::new("ERR reason")))
     );
 }
+
+#[test]
+fn test_code() {
+    let (_, parsed) = BlobError::parse(&b"!22\r\nSYNTAX invalid\r\nsyntax\r\n"[..]).unwrap();
+
+    assert_eq!(parsed.code(), Some("SYNTAX"));
+    assert_eq!(parsed.msg(), &b"invalid\r\nsyntax"[..]);
+
+    let (_, parsed) = BlobError::parse(&b"!0\r\n\r\n"[..]).unwrap();
+    assert_eq!((parsed.code(), parsed.msg()), (None, &b""[..]));
+}
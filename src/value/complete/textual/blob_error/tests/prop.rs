@@ -4,13 +4,8 @@ use proptest::prelude::*;
 use super::BlobError;
 use crate::value::complete::special::attribute::tests::prop::value as attr_value;
 
-prop_compose! {
-    pub fn value()(
-        code in "[A-Z]+",
-        msg in any::<Vec<u8>>()
-    ) -> BlobError {
-        BlobError::new(code, msg)
-    }
+pub fn value() -> impl Strategy<Value = BlobError> {
+    any::<Vec<u8>>().prop_map(BlobError::new)
 }
 
 prop_compose! {
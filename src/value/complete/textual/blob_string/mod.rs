@@ -40,6 +40,14 @@ impl BlobString {
         self.attr.as_ref()
     }
 
+    pub fn attr_mut(&mut self) -> &mut Option<Attribute> {
+        &mut self.attr
+    }
+
+    pub fn take_attr(&mut self) -> Option<Attribute> {
+        self.attr.take()
+    }
+
     pub fn with_attr(mut self, attr: Attribute) -> Self {
         self.attr = Some(attr);
         self
@@ -47,7 +55,8 @@ impl BlobString {
 }
 
 impl BlobString {
-    pub fn parse(input: &[u8]) -> IResult<&[u8], Self> {
+    /// Parses a blob without an attribute, borrowing its contents from `input`.
+    pub(crate) fn parse_val(input: &[u8]) -> IResult<&[u8], &[u8]> {
         let parse_len = {
             let parser = delimited(tag("$"), digit1, tag(DELIMITER));
 
@@ -58,10 +67,14 @@ impl BlobString {
             })
         };
 
-        let parse_attr = opt(Attribute::parse);
         let parse_val = |len| terminated(take(len), tag(DELIMITER));
+        parse_len.flat_map(parse_val).parse(input)
+    }
+
+    pub fn parse(input: &[u8]) -> IResult<&[u8], Self> {
+        let parse_attr = opt(Attribute::parse);
 
-        pair(parse_attr, parse_len.flat_map(parse_val))
+        pair(parse_attr, Self::parse_val)
             .map(|(attr, val)| BlobString {
                 val: Bytes::from(val.to_vec()),
                 attr,
@@ -10,4 +10,4 @@ pub use blob_error::BlobError;
 pub use blob_string::BlobString;
 pub use simple_error::SimpleError;
 pub use simple_string::SimpleString;
-pub use verbatim_string::VerbatimString;
+pub use verbatim_string::{VerbatimFormat, VerbatimString};
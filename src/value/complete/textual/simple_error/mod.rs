@@ -1,30 +1,24 @@
 #[cfg(test)]
 pub(crate) mod tests;
 
-use std::{io::Write, str};
+use std::{borrow::Cow, io::Write, str};
 
-use anyhow::Context;
+use anyhow::{bail, Context};
 use bytes::Bytes;
 use nom::{
-    bytes::complete::tag,
-    combinator::map_res,
+    bytes::complete::{tag, take_till},
     combinator::opt,
-    sequence::{pair, preceded, separated_pair, terminated},
+    sequence::{delimited, pair},
     IResult, Parser,
 };
-use nom_regex::bytes::re_find;
-use once_cell::sync::Lazy;
-use regex::bytes::Regex;
 
-static MSG: Lazy<Regex> = Lazy::new(|| Regex::new(r"[^\r\n]+").unwrap());
-static CODE: Lazy<Regex> = Lazy::new(|| Regex::new(r"[A-Z]+").unwrap());
-
-use super::{Attribute, Value, DELIMITER};
+use super::{simple_string::is_line_break, Attribute, Value, DELIMITER};
 
+/// An error reply. The payload is kept whole, by convention it starts with an error code
+/// (`ERR`, `WRONGTYPE`, `MOVED`...) but nothing besides CR and LF is forbidden.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct SimpleError {
-    code: String,
-    msg: String,
+    val: Bytes,
     attr: Option<Attribute>,
 }
 
@@ -35,12 +29,12 @@ impl From<SimpleError> for Value {
 }
 
 impl SimpleError {
-    pub fn new(code: impl Into<String>, msg: impl Into<String>) -> Self {
-        Self {
-            code: code.into(),
-            msg: msg.into(),
-            attr: None,
-        }
+    /// Fails if `val` contains CR or LF.
+    pub fn new(val: impl Into<Bytes>) -> anyhow::Result<Self> {
+        let val = val.into();
+        check(&val)?;
+
+        Ok(Self { val, attr: None })
     }
 
     pub fn with_attr(mut self, attr: Attribute) -> Self {
@@ -48,43 +42,54 @@ impl SimpleError {
         self
     }
 
-    pub fn code(&self) -> &str {
-        &self.code
+    /// The whole payload, code included.
+    pub fn val(&self) -> &Bytes {
+        &self.val
+    }
+
+    /// The leading error code, if the payload starts with one.
+    pub fn code(&self) -> Option<&str> {
+        split_code(&self.val).0
+    }
+
+    /// The payload without the error code.
+    pub fn msg(&self) -> &[u8] {
+        split_code(&self.val).1
     }
 
-    pub fn msg(&self) -> &str {
-        &self.msg
+    /// `None` if the payload is not valid UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        str::from_utf8(&self.val).ok()
+    }
+
+    pub fn to_string_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.val)
     }
 
     pub fn attr(&self) -> Option<&Attribute> {
         self.attr.as_ref()
     }
+
+    pub fn attr_mut(&mut self) -> &mut Option<Attribute> {
+        &mut self.attr
+    }
+
+    pub fn take_attr(&mut self) -> Option<Attribute> {
+        self.attr.take()
+    }
 }
 
 impl SimpleError {
     pub fn parse(input: &[u8]) -> IResult<&[u8], Self> {
-        let msg = Lazy::force(&MSG).to_owned();
-        let code = Lazy::force(&CODE).to_owned();
-
-        let parse_code = preceded(tag("-"), re_find(code));
-        let parse_msg = terminated(re_find(msg), tag(DELIMITER));
+        let parse_val = delimited(tag("-"), take_till(is_line_break), tag(DELIMITER));
 
         let parse_attr = opt(Attribute::parse);
-        let parse_val = separated_pair(parse_code, tag(" "), parse_msg);
-
-        let mut wrapper = map_res(pair(parse_attr, parse_val), |(attr, (code, msg))| {
-            let code = str::from_utf8(code).context("Value::SimpleError (str::from_utf8")?;
-            let msg = str::from_utf8(msg).context("Value::SimpleError (str::from_utf8")?;
-
-            let mut value = SimpleError::new(code, msg);
-            if let Some(attr) = attr {
-                value = value.with_attr(attr);
-            }
-
-            Ok::<_, anyhow::Error>(value)
-        });
-
-        wrapper.parse(input)
+        pair(parse_attr, parse_val)
+            .map(|(attr, val)| SimpleError {
+                val: Bytes::copy_from_slice(val),
+                attr,
+            })
+            .parse(input)
     }
 }
 
@@ -100,10 +105,10 @@ impl TryFrom<&SimpleError> for Bytes {
                 .context("Value::SimpleError (buf::write)")?;
         }
 
+        check(input.val()).context("Value::SimpleError (invalid contents)")?;
+
         buf.write(b"-")
-            .and_then(|_| buf.write(input.code.as_bytes()))
-            .and_then(|_| buf.write(b" "))
-            .and_then(|_| buf.write(input.msg.as_bytes()))
+            .and_then(|_| buf.write(input.val()))
             .and_then(|_| buf.write(DELIMITER))
             .context("Value::SimpleError (buf::write)")?;
 
@@ -119,3 +124,31 @@ impl TryFrom<SimpleError> for Bytes {
         Bytes::try_from(&input)
     }
 }
+
+fn check(val: &[u8]) -> anyhow::Result<()> {
+    if val.iter().copied().any(is_line_break) {
+        bail!(
+            "Value::SimpleError (CR or LF in {:?})",
+            String::from_utf8_lossy(val)
+        );
+    }
+
+    Ok(())
+}
+
+/// Splits an error payload into its code (the first word, if made of capital letters,
+/// digits and underscores) and the rest of the message.
+pub(crate) fn split_code(val: &[u8]) -> (Option<&str>, &[u8]) {
+    let end = val.iter().position(|b| *b == b' ').unwrap_or(val.len());
+    let (code, rest) = val.split_at(end);
+
+    let is_code = code.first().is_some_and(u8::is_ascii_uppercase)
+        && code
+            .iter()
+            .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit() || *b == b'_');
+
+    match (is_code, str::from_utf8(code)) {
+        (true, Ok(code)) => (Some(code), rest.strip_prefix(b" ").unwrap_or(rest)),
+        _ => (None, val),
+    }
+}
@@ -7,7 +7,7 @@ use nom::error::{Error, ErrorKind};
 fn test_basic() {
     assert_eq!(
         SimpleError::parse(&b"-ERR reason\r\n"[..]),
-        Ok((&b""[..], SimpleError::new("ERR", "reason")))
+        Ok((&b""[..], SimpleError::new("ERR reason").unwrap()))
     );
 }
 
@@ -29,3 +29,44 @@ fn test_invalid_characters() {
         )))
     );
 }
+
+#[test]
+fn test_binary() {
+    let (_, parsed) = SimpleError::parse(&b"-ERR caf\xe9\r\n"[..]).unwrap();
+
+    assert_eq!(parsed.val(), &b"ERR caf\xe9"[..]);
+    assert_eq!(parsed.as_str(), None);
+    assert_eq!(parsed.to_string_lossy(), "ERR caf\u{fffd}");
+}
+
+#[test]
+fn test_code() {
+    let parse = |input: &'static [u8]| SimpleError::parse(input).unwrap().1;
+
+    let err = parse(b"-ERR unknown command\r\n");
+    assert_eq!(
+        (err.code(), err.msg()),
+        (Some("ERR"), &b"unknown command"[..])
+    );
+
+    let err = parse(b"-NOPERM_2 denied\r\n");
+    assert_eq!((err.code(), err.msg()), (Some("NOPERM_2"), &b"denied"[..]));
+
+    let err = parse(b"-LOADING\r\n");
+    assert_eq!((err.code(), err.msg()), (Some("LOADING"), &b""[..]));
+
+    let err = parse(b"-something went wrong\r\n");
+    assert_eq!(
+        (err.code(), err.msg()),
+        (None, &b"something went wrong"[..])
+    );
+
+    let err = parse(b"-\r\n");
+    assert_eq!((err.code(), err.msg()), (None, &b""[..]));
+}
+
+#[test]
+fn test_reject_invalid() {
+    assert!(SimpleError::new("ERR a\r\nb").is_err());
+    assert!(SimpleError::new("ERR a\nb").is_err());
+}
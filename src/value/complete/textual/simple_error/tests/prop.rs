@@ -4,13 +4,9 @@ use proptest::prelude::*;
 use super::SimpleError;
 use crate::value::complete::special::attribute::tests::prop::value as attr_value;
 
-prop_compose! {
-    pub fn value()(
-        code in "[A-Z]+",
-        msg in "[^\r\n]+"
-    ) -> SimpleError {
-        SimpleError::new(code, msg)
-    }
+pub fn value() -> impl Strategy<Value = SimpleError> {
+    let byte = any::<u8>().prop_filter("CR or LF", |b| !matches!(b, b'\r' | b'\n'));
+    prop::collection::vec(byte, 0..64).prop_map(|val| SimpleError::new(val).unwrap())
 }
 
 prop_compose! {
@@ -1,27 +1,23 @@
 #[cfg(test)]
 pub(crate) mod tests;
 
-use std::{io::Write, str};
+use std::{borrow::Cow, io::Write, str};
 
-use anyhow::Context;
+use anyhow::{bail, Context};
 use bytes::Bytes;
 use nom::{
-    bytes::complete::tag,
-    combinator::{map_res, opt},
+    bytes::complete::{tag, take_till},
+    combinator::opt,
     sequence::{delimited, pair},
     IResult, Parser,
 };
-use nom_regex::bytes::re_find;
-use once_cell::sync::Lazy;
-use regex::bytes::Regex;
-
-static RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"[^\r\n]+").unwrap());
 
 use super::{Attribute, Value, DELIMITER};
 
+/// A status reply. Any bytes but CR and LF are allowed, UTF-8 is not required.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct SimpleString {
-    val: String,
+    val: Bytes,
     attr: Option<Attribute>,
 }
 
@@ -32,47 +28,59 @@ impl From<SimpleString> for Value {
 }
 
 impl SimpleString {
+    /// Fails if `val` contains CR or LF.
+    pub fn new(val: impl Into<Bytes>) -> anyhow::Result<Self> {
+        let val = val.into();
+        check(&val)?;
+
+        Ok(Self { val, attr: None })
+    }
+
     pub fn with_attr(mut self, attr: Attribute) -> Self {
         self.attr = Some(attr);
         self
     }
 
-    pub fn val(&self) -> &str {
+    pub fn val(&self) -> &Bytes {
         &self.val
     }
 
+    /// `None` if the contents are not valid UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        str::from_utf8(&self.val).ok()
+    }
+
+    pub fn to_string_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.val)
+    }
+
     pub fn attr(&self) -> Option<&Attribute> {
         self.attr.as_ref()
     }
+
+    pub fn attr_mut(&mut self) -> &mut Option<Attribute> {
+        &mut self.attr
+    }
+
+    pub fn take_attr(&mut self) -> Option<Attribute> {
+        self.attr.take()
+    }
 }
 
 impl SimpleString {
     pub fn parse(input: &[u8]) -> IResult<&[u8], Self> {
-        let re = Lazy::force(&RE).to_owned();
-
-        let parse_val = delimited(tag("+"), re_find(re), tag(DELIMITER));
-        let parse_val = map_res(parse_val, |v: &[u8]| {
-            str::from_utf8(v)
-                .map(ToString::to_string)
-                .context("Value::SimpleString (str::from_utf8)")
-        });
+        let parse_val = delimited(tag("+"), take_till(is_line_break), tag(DELIMITER));
 
         let parse_attr = opt(Attribute::parse);
         pair(parse_attr, parse_val)
-            .map(|(attr, val)| SimpleString { val, attr })
+            .map(|(attr, val)| SimpleString {
+                val: Bytes::copy_from_slice(val),
+                attr,
+            })
             .parse(input)
     }
 }
 
-impl<S: Into<String>> From<S> for SimpleString {
-    fn from(val: S) -> Self {
-        Self {
-            val: val.into(),
-            attr: None,
-        }
-    }
-}
-
 impl TryFrom<&SimpleString> for Bytes {
     type Error = anyhow::Error;
 
@@ -85,8 +93,10 @@ impl TryFrom<&SimpleString> for Bytes {
                 .context("Value::SimpleString (buf::write)")?;
         }
 
+        check(input.val()).context("Value::SimpleString (invalid contents)")?;
+
         buf.write(b"+")
-            .and_then(|_| buf.write(input.val().as_bytes()))
+            .and_then(|_| buf.write(input.val()))
             .and_then(|_| buf.write(DELIMITER))
             .context("Value::SimpleString (buf::write)")?;
 
@@ -102,3 +112,18 @@ impl TryFrom<SimpleString> for Bytes {
         Bytes::try_from(&input)
     }
 }
+
+fn check(val: &[u8]) -> anyhow::Result<()> {
+    if val.iter().copied().any(is_line_break) {
+        bail!(
+            "Value::SimpleString (CR or LF in {:?})",
+            String::from_utf8_lossy(val)
+        );
+    }
+
+    Ok(())
+}
+
+pub(crate) fn is_line_break(b: u8) -> bool {
+    b == b'\r' || b == b'\n'
+}
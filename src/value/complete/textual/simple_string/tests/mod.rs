@@ -7,7 +7,7 @@ use nom::error::{Error, ErrorKind};
 fn test_basic() {
     assert_eq!(
         SimpleString::parse(&b"+hello world\r\n"[..]),
-        Ok((&b""[..], SimpleString::from("hello world")))
+        Ok((&b""[..], SimpleString::new("hello world").unwrap()))
     );
 }
 
@@ -29,3 +29,26 @@ fn test_invalid_characters() {
         )))
     );
 }
+
+#[test]
+fn test_binary() {
+    let (_, parsed) = SimpleString::parse(&b"+caf\xe9\r\n"[..]).unwrap();
+
+    assert_eq!(parsed.val(), &b"caf\xe9"[..]);
+    assert_eq!(parsed.as_str(), None);
+    assert_eq!(parsed.to_string_lossy(), "caf\u{fffd}");
+}
+
+#[test]
+fn test_empty() {
+    assert_eq!(
+        SimpleString::parse(&b"+\r\n"[..]),
+        Ok((&b""[..], SimpleString::new("").unwrap()))
+    );
+}
+
+#[test]
+fn test_reject_line_breaks() {
+    assert!(SimpleString::new("a\r\nb").is_err());
+    assert!(SimpleString::new("a\nb").is_err());
+}
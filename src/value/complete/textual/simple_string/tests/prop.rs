@@ -5,7 +5,8 @@ use super::SimpleString;
 use crate::value::complete::special::attribute::tests::prop::value as attr_value;
 
 pub fn value() -> impl Strategy<Value = SimpleString> {
-    "[^\r\n]+".prop_map(SimpleString::from)
+    let byte = any::<u8>().prop_filter("CR or LF", |b| !matches!(b, b'\r' | b'\n'));
+    prop::collection::vec(byte, 0..64).prop_map(|val| SimpleString::new(val).unwrap())
 }
 
 prop_compose! {
@@ -2,27 +2,86 @@
 pub(crate) mod tests;
 
 use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
     io::Write,
     str::{self, FromStr},
 };
 
-use anyhow::Context;
+use anyhow::{bail, Context};
 use bytes::Bytes;
 use nom::{
-    branch::alt,
     bytes::complete::{tag, take},
     character::complete::digit1,
     combinator::{map_res, opt},
-    sequence::{delimited, pair, separated_pair, terminated},
+    sequence::{delimited, pair, terminated},
     IResult, Parser,
 };
 
-use super::{Attribute, Value, DELIMITER};
+use super::{Attribute, BlobString, Value, DELIMITER};
+
+/// The three bytes before the `:` of a verbatim string.
+///
+/// Formats compare by their bytes, `Other(*b"txt")` is `Txt`; [`VerbatimFormat::from`]
+/// picks the named variant.
+#[derive(Debug, Clone, Copy)]
+pub enum VerbatimFormat {
+    Txt,
+    Mkd,
+    Other([u8; 3]),
+}
+
+impl VerbatimFormat {
+    pub fn as_bytes(&self) -> &[u8; 3] {
+        match self {
+            Self::Txt => b"txt",
+            Self::Mkd => b"mkd",
+            Self::Other(format) => format,
+        }
+    }
+}
+
+impl PartialEq for VerbatimFormat {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl Eq for VerbatimFormat {}
+
+impl PartialOrd for VerbatimFormat {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for VerbatimFormat {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_bytes().cmp(other.as_bytes())
+    }
+}
+
+impl Hash for VerbatimFormat {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_bytes().hash(state)
+    }
+}
+
+impl From<[u8; 3]> for VerbatimFormat {
+    fn from(input: [u8; 3]) -> Self {
+        match &input {
+            b"txt" => Self::Txt,
+            b"mkd" => Self::Mkd,
+            _ => Self::Other(input),
+        }
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub enum VerbatimString {
-    Txt { val: Bytes, attr: Option<Attribute> },
-    Mkd { val: Bytes, attr: Option<Attribute> },
+pub struct VerbatimString {
+    format: VerbatimFormat,
+    val: Bytes,
+    attr: Option<Attribute>,
 }
 
 impl From<VerbatimString> for Value {
@@ -32,62 +91,69 @@ impl From<VerbatimString> for Value {
 }
 
 impl VerbatimString {
-    pub fn txt(bytes: impl Into<Bytes>) -> Self {
-        Self::Txt {
-            val: bytes.into(),
+    pub fn new(format: VerbatimFormat, val: impl Into<Bytes>) -> Self {
+        Self {
+            format: VerbatimFormat::from(*format.as_bytes()),
+            val: val.into(),
             attr: None,
         }
     }
 
-    pub fn mkd(bytes: impl Into<Bytes>) -> Self {
-        Self::Mkd {
-            val: bytes.into(),
-            attr: None,
-        }
+    pub fn txt(bytes: impl Into<Bytes>) -> Self {
+        Self::new(VerbatimFormat::Txt, bytes)
     }
 
-    pub fn with_attr(self, attr: Attribute) -> Self {
-        match self {
-            Self::Mkd { val, .. } => Self::Mkd {
-                val,
-                attr: Some(attr),
-            },
-            Self::Txt { val, .. } => Self::Txt {
-                val,
-                attr: Some(attr),
-            },
-        }
+    pub fn mkd(bytes: impl Into<Bytes>) -> Self {
+        Self::new(VerbatimFormat::Mkd, bytes)
     }
 
-    fn tag(&self) -> &'static str {
-        match self {
-            Self::Txt { .. } => "txt",
-            Self::Mkd { .. } => "mkd",
-        }
+    pub fn with_attr(mut self, attr: Attribute) -> Self {
+        self.attr = Some(attr);
+        self
     }
 
-    fn len(&self) -> usize {
-        match self {
-            Self::Txt { val, .. } => val.len(),
-            Self::Mkd { val, .. } => val.len(),
-        }
+    pub fn format(&self) -> VerbatimFormat {
+        self.format
     }
 
     pub fn val(&self) -> &Bytes {
-        match self {
-            Self::Txt { val, .. } => val,
-            Self::Mkd { val, .. } => val,
-        }
+        &self.val
+    }
+
+    /// `None` if the text is not valid UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        str::from_utf8(&self.val).ok()
     }
 
     pub fn attr(&self) -> Option<&Attribute> {
-        match self {
-            Self::Txt { attr, .. } => attr.as_ref(),
-            Self::Mkd { attr, .. } => attr.as_ref(),
+        self.attr.as_ref()
+    }
+
+    pub fn attr_mut(&mut self) -> &mut Option<Attribute> {
+        &mut self.attr
+    }
+
+    pub fn take_attr(&mut self) -> Option<Attribute> {
+        self.attr.take()
+    }
+
+    /// Drops the format, keeping the text and the attribute.
+    pub fn into_blob_string(self) -> BlobString {
+        let val = BlobString::from(self.val);
+
+        match self.attr {
+            Some(attr) => val.with_attr(attr),
+            None => val,
         }
     }
 }
 
+impl From<VerbatimString> for BlobString {
+    fn from(input: VerbatimString) -> Self {
+        input.into_blob_string()
+    }
+}
+
 impl VerbatimString {
     pub fn parse(input: &[u8]) -> IResult<&[u8], Self> {
         let parse_len = {
@@ -95,15 +161,18 @@ impl VerbatimString {
 
             map_res(parser, |v: &[u8]| {
                 str::from_utf8(v)
-                    .context("Value::Array (str::from_utf8)")
-                    .and_then(|v| u64::from_str(v).context("Value::Array (u64::from_str)"))
+                    .context("Value::VerbatimString (str::from_utf8)")
+                    .and_then(|v| u64::from_str(v).context("Value::VerbatimString (u64::from_str)"))
             })
         };
 
-        let parse_msg = |len: u64| {
-            terminated(
-                separated_pair(alt((tag("txt"), tag("mkd"))), tag(":"), take(len - 4)),
-                tag(DELIMITER),
+        let parse_msg = |len| {
+            map_res(
+                terminated(take(len), tag(DELIMITER)),
+                |msg: &[u8]| match msg.get(..4) {
+                    Some([a, b, c, b':']) => Ok((VerbatimFormat::from([*a, *b, *c]), &msg[4..])),
+                    _ => bail!("Value::VerbatimString (missing format)"),
+                },
             )
         };
 
@@ -111,18 +180,10 @@ impl VerbatimString {
         let parse_val = parse_len.flat_map(parse_msg);
 
         pair(parse_attr, parse_val)
-            .map(|(attr, (ty, msg))| {
-                let mut value = match ty {
-                    b"txt" => VerbatimString::txt(msg.to_vec()),
-                    b"mkd" => VerbatimString::mkd(msg.to_vec()),
-                    _ => unreachable!(),
-                };
-
-                if let Some(attr) = attr {
-                    value = value.with_attr(attr);
-                }
-
-                value
+            .map(|(attr, (format, msg))| VerbatimString {
+                format,
+                val: Bytes::copy_from_slice(msg),
+                attr,
             })
             .parse(input)
     }
@@ -141,9 +202,9 @@ impl TryFrom<&VerbatimString> for Bytes {
         }
 
         buf.write(b"=")
-            .and_then(|_| buf.write((input.len() + 4).to_string().as_bytes()))
+            .and_then(|_| buf.write((input.val().len() + 4).to_string().as_bytes()))
             .and_then(|_| buf.write(DELIMITER))
-            .and_then(|_| buf.write(input.tag().as_bytes()))
+            .and_then(|_| buf.write(input.format().as_bytes()))
             .and_then(|_| buf.write(b":"))
             .and_then(|_| buf.write(input.val()))
             .and_then(|_| buf.write(DELIMITER))
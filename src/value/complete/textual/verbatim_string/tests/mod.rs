@@ -1,5 +1,7 @@
 pub mod prop;
 
+use std::collections::HashSet;
+
 use super::*;
 
 #[test]
@@ -14,3 +16,60 @@ fn test_basic() {
         Ok((&b""[..], VerbatimString::mkd(b"hello world".to_vec())))
     );
 }
+
+#[test]
+fn test_other_format() {
+    let (_, parsed) = VerbatimString::parse(&b"=8\r\nhtm:<b/>\r\n"[..]).unwrap();
+
+    assert_eq!(parsed.format(), VerbatimFormat::Other(*b"htm"));
+    assert_eq!(parsed.as_str(), Some("<b/>"));
+    assert_eq!(Bytes::try_from(parsed).unwrap(), &b"=8\r\nhtm:<b/>\r\n"[..]);
+
+    assert_eq!(
+        VerbatimString::new(VerbatimFormat::Other(*b"txt"), "a"),
+        VerbatimString::txt("a")
+    );
+}
+
+#[test]
+fn test_format_normalization() {
+    assert!(matches!(VerbatimFormat::from(*b"txt"), VerbatimFormat::Txt));
+    assert!(matches!(VerbatimFormat::from(*b"mkd"), VerbatimFormat::Mkd));
+    assert!(matches!(
+        VerbatimString::new(VerbatimFormat::Other(*b"mkd"), "a").format(),
+        VerbatimFormat::Mkd
+    ));
+
+    assert_eq!(VerbatimFormat::Other(*b"txt"), VerbatimFormat::Txt);
+    assert_ne!(VerbatimFormat::Other(*b"htm"), VerbatimFormat::Txt);
+    assert!(VerbatimFormat::Mkd < VerbatimFormat::Other(*b"txt"));
+
+    let formats = HashSet::from([VerbatimFormat::Txt, VerbatimFormat::Other(*b"txt")]);
+    assert_eq!(formats.len(), 1);
+}
+
+#[test]
+fn test_invalid_length() {
+    assert!(VerbatimString::parse(&b"=2\r\nab\r\n"[..]).is_err());
+    assert!(VerbatimString::parse(&b"=0\r\n\r\n"[..]).is_err());
+    assert!(VerbatimString::parse(&b"=5\r\ntxt-a\r\n"[..]).is_err());
+
+    assert_eq!(
+        VerbatimString::parse(&b"=4\r\ntxt:\r\n"[..]),
+        Ok((&b""[..], VerbatimString::txt("")))
+    );
+}
+
+#[test]
+fn test_into_blob_string() {
+    let attr = Attribute::from([(
+        Value::from(BlobString::from("k")),
+        Value::from(BlobString::from("v")),
+    )]);
+    let val = VerbatimString::mkd("# title").with_attr(attr.clone());
+
+    assert_eq!(
+        BlobString::from(val),
+        BlobString::from("# title").with_attr(attr)
+    );
+}
@@ -1,13 +1,15 @@
 use bytes::Bytes;
 use proptest::prelude::*;
 
-use super::VerbatimString;
+use super::{VerbatimFormat, VerbatimString};
 use crate::value::complete::special::attribute::tests::prop::value as attr_value;
 
 pub fn value() -> impl Strategy<Value = VerbatimString> {
     prop_oneof![
         any::<Vec<u8>>().prop_map(VerbatimString::txt),
         any::<Vec<u8>>().prop_map(VerbatimString::mkd),
+        (any::<[u8; 3]>(), any::<Vec<u8>>())
+            .prop_map(|(format, val)| VerbatimString::new(VerbatimFormat::from(format), val)),
     ]
 }
 
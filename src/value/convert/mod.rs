@@ -0,0 +1,141 @@
+#[cfg(test)]
+pub(crate) mod tests;
+
+use anyhow::{bail, Context};
+use bytes::Bytes;
+
+use super::Value;
+
+/// Conversion of a reply into a Rust type. Error replies fail the conversion
+/// with the [`ServerError`](crate::error::ServerError) they carry.
+pub trait FromValue: Sized {
+    fn from_value(value: Value) -> anyhow::Result<Self>;
+}
+
+impl FromValue for Value {
+    fn from_value(value: Value) -> anyhow::Result<Self> {
+        Ok(value)
+    }
+}
+
+/// Accepts any reply but an error, e.g. `+OK`.
+impl FromValue for () {
+    fn from_value(value: Value) -> anyhow::Result<Self> {
+        checked(value).map(|_| ())
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: Value) -> anyhow::Result<Self> {
+        match checked(value)? {
+            Value::Boolean(inner) => Ok(inner.val()),
+            Value::Number(inner) if matches!(inner.val(), 0 | 1) => Ok(inner.val() == 1),
+            value => bail!("FromValue (expected a boolean, got {value:?})"),
+        }
+    }
+}
+
+impl FromValue for i64 {
+    fn from_value(value: Value) -> anyhow::Result<Self> {
+        match checked(value)? {
+            Value::Number(inner) => Ok(inner.val()),
+            value => parse(value, "an integer"),
+        }
+    }
+}
+
+impl FromValue for f64 {
+    fn from_value(value: Value) -> anyhow::Result<Self> {
+        match checked(value)? {
+            Value::Double(inner) => Ok(inner.val()),
+            Value::Number(inner) => Ok(inner.val() as f64),
+            value => parse(value, "a double"),
+        }
+    }
+}
+
+impl FromValue for Bytes {
+    fn from_value(value: Value) -> anyhow::Result<Self> {
+        match checked(value)? {
+            Value::BlobString(inner) => Ok(inner.val().clone()),
+            Value::SimpleString(inner) => Ok(inner.val().clone()),
+            Value::VerbatimString(inner) => Ok(inner.val().clone()),
+            value => bail!("FromValue (expected a string, got {value:?})"),
+        }
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: Value) -> anyhow::Result<Self> {
+        let bytes = Bytes::from_value(value)?;
+
+        String::from_utf8(bytes.to_vec()).context("FromValue (String::from_utf8)")
+    }
+}
+
+/// `None` for a null reply.
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: Value) -> anyhow::Result<Self> {
+        match value {
+            Value::Null(_) => Ok(None),
+            value => T::from_value(value).map(Some),
+        }
+    }
+}
+
+impl<T: FromValue> FromValue for Vec<T> {
+    fn from_value(value: Value) -> anyhow::Result<Self> {
+        elements(value)?.into_iter().map(T::from_value).collect()
+    }
+}
+
+macro_rules! tuple {
+    ($len:literal: $($name:ident),+) => {
+        /// Elements of an array of exactly this length.
+        impl<$($name: FromValue),+> FromValue for ($($name,)+) {
+            fn from_value(value: Value) -> anyhow::Result<Self> {
+                let elements = elements(value)?;
+                if elements.len() != $len {
+                    bail!("FromValue (expected {} elements, got {})", $len, elements.len());
+                }
+
+                let mut elements = elements.into_iter();
+                Ok(($(
+                    $name::from_value(elements.next().expect("length was just checked"))?,
+                )+))
+            }
+        }
+    };
+}
+
+tuple!(1: A);
+tuple!(2: A, B);
+tuple!(3: A, B, C);
+tuple!(4: A, B, C, D);
+tuple!(5: A, B, C, D, E);
+tuple!(6: A, B, C, D, E, F);
+tuple!(7: A, B, C, D, E, F, G);
+tuple!(8: A, B, C, D, E, F, G, H);
+
+fn checked(value: Value) -> anyhow::Result<Value> {
+    value.into_result().map_err(anyhow::Error::from)
+}
+
+fn elements(value: Value) -> anyhow::Result<Vec<Value>> {
+    match checked(value)? {
+        Value::Array(inner) => Ok(inner.into_inner()),
+        Value::Set(inner) => Ok(inner.into_inner()),
+        Value::Push(inner) => Ok(inner.into_inner()),
+        value => bail!("FromValue (expected an array, got {value:?})"),
+    }
+}
+
+// Numbers sent as strings, e.g. `INCRBYFLOAT` or RESP2 doubles.
+fn parse<T: std::str::FromStr>(value: Value, what: &str) -> anyhow::Result<T> {
+    let bytes = Bytes::from_value(value).with_context(|| format!("FromValue (expected {what})"))?;
+
+    std::str::from_utf8(&bytes)
+        .ok()
+        .and_then(|text| text.parse().ok())
+        .with_context(|| format!("FromValue (expected {what}, got {bytes:?})"))
+}
@@ -0,0 +1,44 @@
+use super::*;
+use crate::{error::ErrorKind, error::ServerError, resp};
+
+#[test]
+fn test_scalars() {
+    assert_eq!(i64::from_value(resp!(42)).unwrap(), 42);
+    assert_eq!(i64::from_value(resp!("-7")).unwrap(), -7);
+    assert_eq!(f64::from_value(resp!("1.5")).unwrap(), 1.5);
+    assert_eq!(f64::from_value(resp!(2)).unwrap(), 2.0);
+    assert!(bool::from_value(resp!(true)).unwrap());
+    assert!(!bool::from_value(resp!(0)).unwrap());
+    assert_eq!(String::from_value(resp!(+"OK")).unwrap(), "OK");
+    assert_eq!(Bytes::from_value(resp!("val")).unwrap(), "val");
+    assert_eq!(<()>::from_value(resp!(+"OK")).unwrap(), ());
+
+    assert!(i64::from_value(resp!("x")).is_err());
+    assert!(bool::from_value(resp!(2)).is_err());
+    assert!(String::from_value(resp!(1)).is_err());
+}
+
+#[test]
+fn test_nested() {
+    let value = resp!([1, nil, ["a", "b"]]);
+    let (n, missing, list) = <(i64, Option<String>, Vec<String>)>::from_value(value).unwrap();
+
+    assert_eq!(
+        (n, missing, list),
+        (1, None, vec!["a".to_string(), "b".to_string()])
+    );
+    assert_eq!(Option::<i64>::from_value(resp!(3)).unwrap(), Some(3));
+    assert!(<(i64, i64)>::from_value(resp!([1])).is_err());
+    assert!(Vec::<i64>::from_value(resp!(nil)).is_err());
+}
+
+#[test]
+fn test_errors() {
+    let err = i64::from_value(resp!(-"WRONGTYPE Operation against a key")).unwrap_err();
+    let err = err.downcast::<ServerError>().unwrap();
+    assert_eq!(err.kind(), &ErrorKind::WrongType);
+
+    assert!(<()>::from_value(resp!(-"ERR nope")).is_err());
+    assert!(Option::<i64>::from_value(resp!(-"ERR nope")).is_err());
+    assert!(Value::from_value(resp!(-"ERR nope")).is_ok());
+}
@@ -0,0 +1,192 @@
+#[cfg(test)]
+pub(crate) mod tests;
+
+use std::fmt::{self, Display, Write};
+
+use super::{Attribute, Value};
+
+/// Formats a value the way `redis-cli --raw` does:
+/// strings are printed verbatim, aggregates are flattened one element per line.
+#[derive(Debug, Clone, Copy)]
+pub struct Raw<'a>(&'a Value);
+
+impl Value {
+    pub fn raw(&self) -> Raw<'_> {
+        Raw(self)
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        human(self, "", &mut out)?;
+
+        f.write_str(out.strip_suffix('\n').unwrap_or(&out))
+    }
+}
+
+impl Display for Raw<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        raw(self.0, &mut out)?;
+
+        f.write_str(&out)
+    }
+}
+
+fn human(value: &Value, prefix: &str, out: &mut String) -> fmt::Result {
+    if let Some(attr) = value.attr().filter(|attr| !attr.is_empty()) {
+        human_attr(attr, prefix, out)?;
+        out.push_str(prefix);
+    }
+
+    match value {
+        Value::Array(inner) => human_seq(inner.val().iter(), ')', "array", prefix, out),
+        Value::Set(inner) => human_seq(inner.val().iter(), '~', "set", prefix, out),
+        Value::Push(inner) => human_seq(inner.val().iter(), ')', "push", prefix, out),
+        Value::Map(inner) => human_map(inner.iter(), '#', "hash", prefix, out),
+        Value::BigNumber(inner) => writeln!(out, "(big number) {}", inner.val()),
+        Value::BlobError(inner) => writeln!(out, "(error) {}", inner.to_string_lossy()),
+        Value::BlobString(inner) => {
+            quote(inner.val(), out)?;
+            out.write_char('\n')
+        }
+        Value::Boolean(inner) => writeln!(out, "({})", inner.val()),
+        Value::Double(inner) => writeln!(out, "(double) {}", inner.text()),
+        Value::Null(_) => writeln!(out, "(nil)"),
+        Value::Number(inner) => writeln!(out, "(integer) {}", inner.val()),
+        Value::SimpleError(inner) => writeln!(out, "(error) {}", inner.to_string_lossy()),
+        Value::SimpleString(inner) => writeln!(out, "{}", inner.to_string_lossy()),
+        Value::VerbatimString(inner) => {
+            writeln!(out, "{}", String::from_utf8_lossy(inner.val()))
+        }
+    }
+}
+
+fn human_attr(attr: &Attribute, prefix: &str, out: &mut String) -> fmt::Result {
+    human_entries(attr.iter(), "|", ')', prefix, out)
+}
+
+fn human_seq<'a>(
+    values: impl ExactSizeIterator<Item = &'a Value>,
+    sep: char,
+    kind: &str,
+    prefix: &str,
+    out: &mut String,
+) -> fmt::Result {
+    if values.len() == 0 {
+        return writeln!(out, "(empty {kind})");
+    }
+
+    let (width, nested) = layout(values.len(), "", prefix);
+
+    for (i, value) in values.enumerate() {
+        if i > 0 {
+            out.push_str(prefix);
+        }
+
+        write!(out, "{:>width$}{sep} ", i + 1)?;
+        human(value, &nested, out)?;
+    }
+
+    Ok(())
+}
+
+fn human_map<'a>(
+    entries: impl ExactSizeIterator<Item = (&'a Value, &'a Value)>,
+    sep: char,
+    kind: &str,
+    prefix: &str,
+    out: &mut String,
+) -> fmt::Result {
+    if entries.len() == 0 {
+        return writeln!(out, "(empty {kind})");
+    }
+
+    human_entries(entries, "", sep, prefix, out)
+}
+
+fn human_entries<'a>(
+    entries: impl ExactSizeIterator<Item = (&'a Value, &'a Value)>,
+    marker: &str,
+    sep: char,
+    prefix: &str,
+    out: &mut String,
+) -> fmt::Result {
+    let (width, nested) = layout(entries.len(), marker, prefix);
+
+    for (i, (k, v)) in entries.enumerate() {
+        if i > 0 {
+            out.push_str(prefix);
+        }
+
+        write!(out, "{marker}{:>width$}{sep} ", i + 1)?;
+        human(k, &nested, out)?;
+        out.pop();
+        out.push_str(" => ");
+        human(v, &nested, out)?;
+    }
+
+    Ok(())
+}
+
+// Nested elements are indented past the marker, the widest index and its separator.
+fn layout(len: usize, marker: &str, prefix: &str) -> (usize, String) {
+    let width = len.to_string().len();
+    let nested = format!("{prefix}{}", " ".repeat(marker.len() + width + 2));
+
+    (width, nested)
+}
+
+fn raw(value: &Value, out: &mut String) -> fmt::Result {
+    match value {
+        Value::Array(inner) => raw_seq(inner.val().iter(), out),
+        Value::Set(inner) => raw_seq(inner.val().iter(), out),
+        Value::Push(inner) => raw_seq(inner.val().iter(), out),
+        Value::Map(inner) => raw_seq(inner.iter().flat_map(|(k, v)| [k, v]), out),
+        Value::BigNumber(inner) => write!(out, "{}", inner.val()),
+        Value::BlobError(inner) => write!(out, "{}", inner.to_string_lossy()),
+        Value::BlobString(inner) => write!(out, "{}", String::from_utf8_lossy(inner.val())),
+        Value::Boolean(inner) => write!(out, "({})", inner.val()),
+        Value::Double(inner) => write!(out, "{}", inner.text()),
+        Value::Null(_) => Ok(()),
+        Value::Number(inner) => write!(out, "{}", inner.val()),
+        Value::SimpleError(inner) => write!(out, "{}", inner.to_string_lossy()),
+        Value::SimpleString(inner) => write!(out, "{}", inner.to_string_lossy()),
+        Value::VerbatimString(inner) => write!(out, "{}", String::from_utf8_lossy(inner.val())),
+    }
+}
+
+fn raw_seq<'a>(values: impl Iterator<Item = &'a Value>, out: &mut String) -> fmt::Result {
+    for (i, value) in values.enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+
+        raw(value, out)?;
+    }
+
+    Ok(())
+}
+
+// Mirrors `sdscatrepr` used by redis-cli to quote binary strings.
+fn quote(bytes: &[u8], out: &mut String) -> fmt::Result {
+    out.push('"');
+
+    for &byte in bytes {
+        match byte {
+            b'\\' => out.push_str("\\\\"),
+            b'"' => out.push_str("\\\""),
+            b'\n' => out.push_str("\\n"),
+            b'\r' => out.push_str("\\r"),
+            b'\t' => out.push_str("\\t"),
+            0x07 => out.push_str("\\a"),
+            0x08 => out.push_str("\\b"),
+            0x20..=0x7e => out.push(byte as char),
+            _ => write!(out, "\\x{byte:02x}")?,
+        }
+    }
+
+    out.push('"');
+    Ok(())
+}
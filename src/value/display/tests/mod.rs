@@ -0,0 +1,153 @@
+use std::str::FromStr;
+
+use num_bigint::BigInt;
+
+use crate::value::{complete::primitive::double, *};
+
+#[test]
+fn test_primitive() {
+    assert_eq!(Value::from(Number::from(5)).to_string(), "(integer) 5");
+    assert_eq!(Value::from(Boolean::from(true)).to_string(), "(true)");
+    assert_eq!(Value::from(Null::default()).to_string(), "(nil)");
+    assert_eq!(
+        Value::from(Double::inf(double::Sign::Minus)).to_string(),
+        "(double) -inf"
+    );
+    assert_eq!(
+        Value::from(BigNumber::from(BigInt::from_str("12345").unwrap())).to_string(),
+        "(big number) 12345"
+    );
+}
+
+#[test]
+fn test_textual() {
+    assert_eq!(
+        Value::from(SimpleString::new("OK").unwrap()).to_string(),
+        "OK"
+    );
+    assert_eq!(
+        Value::from(SimpleError::new("ERR unknown command").unwrap()).to_string(),
+        "(error) ERR unknown command"
+    );
+    assert_eq!(
+        Value::from(BlobError::new("ERR reason")).to_string(),
+        "(error) ERR reason"
+    );
+    assert_eq!(
+        Value::from(BlobString::from(b"a\"b\r\n\x00".to_vec())).to_string(),
+        r#""a\"b\r\n\x00""#
+    );
+    assert_eq!(
+        Value::from(VerbatimString::txt(b"hello".to_vec())).to_string(),
+        "hello"
+    );
+}
+
+#[test]
+fn test_empty_aggregates() {
+    assert_eq!(Value::from(Array::from([])).to_string(), "(empty array)");
+    assert_eq!(Value::from(Set::from([])).to_string(), "(empty set)");
+    assert_eq!(Value::from(Map::from([])).to_string(), "(empty hash)");
+}
+
+#[test]
+fn test_nested_array() {
+    let value = Value::from(Array::from([
+        Value::from(BlobString::from("foo")),
+        Value::from(Array::from([
+            Value::from(Number::from(1)),
+            Value::from(Array::from([Value::from(Null::default())])),
+        ])),
+        Value::from(Set::from([Value::from(Boolean::from(false))])),
+    ]));
+
+    assert_eq!(
+        value.to_string(),
+        "\
+1) \"foo\"
+2) 1) (integer) 1
+   2) 1) (nil)
+3) 1~ (false)"
+    );
+}
+
+#[test]
+fn test_index_width() {
+    let value = Value::from(
+        (0..10)
+            .map(|i| Value::from(Array::from([Value::from(Number::from(i))])))
+            .collect::<Array>(),
+    );
+
+    let expected = (1..=10)
+        .map(|i| format!("{i:>2}) 1) (integer) {}", i - 1))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    assert_eq!(value.to_string(), expected);
+}
+
+#[test]
+fn test_map() {
+    let value = Value::from(Map::from([
+        (
+            Value::from(BlobString::from("key")),
+            Value::from(BlobString::from("val")),
+        ),
+        (
+            Value::from(BlobString::from("nested")),
+            Value::from(Array::from([
+                Value::from(Number::from(1)),
+                Value::from(Number::from(2)),
+            ])),
+        ),
+    ]));
+
+    assert_eq!(
+        value.to_string(),
+        "\
+1# \"key\" => \"val\"
+2# \"nested\" => 1) (integer) 1
+   2) (integer) 2"
+    );
+}
+
+#[test]
+fn test_attribute() {
+    let attr = Attribute::from([(
+        Value::from(BlobString::from("key-popularity")),
+        Value::from(Number::from(10)),
+    )]);
+
+    let value = Value::from(Array::from([
+        Value::from(Number::from(1)),
+        Value::from(Number::from(2).with_attr(attr.clone())),
+    ]))
+    .to_string();
+
+    assert_eq!(
+        value,
+        "\
+1) (integer) 1
+2) |1) \"key-popularity\" => (integer) 10
+   (integer) 2"
+    );
+
+    let value = Value::from(Null::default().with_attr(attr)).to_string();
+    assert_eq!(value, "|1) \"key-popularity\" => (integer) 10\n(nil)");
+}
+
+#[test]
+fn test_raw() {
+    let value = Value::from(Array::from([
+        Value::from(BlobString::from("foo")),
+        Value::from(Number::from(5)),
+        Value::from(Null::default()),
+        Value::from(Map::from([(
+            Value::from(SimpleString::new("k").unwrap()),
+            Value::from(Double::inf(double::Sign::Plus)),
+        )])),
+    ]));
+
+    assert_eq!(value.raw().to_string(), "foo\n5\n\nk\ninf");
+}
@@ -0,0 +1,272 @@
+#[cfg(test)]
+pub(crate) mod tests;
+
+use std::{
+    collections::BTreeMap,
+    mem,
+    str::{self, FromStr},
+};
+
+use anyhow::{anyhow, bail, Context};
+
+use super::{PullParser, Value, DELIMITER};
+
+/// What to do with repeated keys in maps and attributes, and repeated items in sets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Duplicates {
+    /// Fail the decoding.
+    Reject,
+    /// Keep every entry as it appears on the wire, so the value re-encodes byte for byte.
+    #[default]
+    Keep,
+    /// Keep one entry per key, at the position of the first one, with the last value.
+    LastWins,
+}
+
+/// Parameters of [`Value::decode_with`] and [`PullParser::next_value`](super::PullParser::next_value).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct DecodeOptions {
+    pub duplicates: Duplicates,
+}
+
+impl Value {
+    /// Returns the length of the first frame in `input` without building it,
+    /// `None` if more bytes are needed, or an error if the frame is malformed.
+    pub fn frame_len(input: &[u8]) -> anyhow::Result<Option<usize>> {
+        FrameScanner::new().scan(input)
+    }
+
+    /// Decodes the first frame in `input` along with the number of bytes it took.
+    ///
+    /// Unlike [`Value::parse`] this tells an incomplete frame (`Ok(None)`)
+    /// apart from a malformed one (`Err`), which is what stream readers need.
+    pub fn decode(input: &[u8]) -> anyhow::Result<Option<(Value, usize)>> {
+        Value::decode_with(input, &DecodeOptions::default())
+    }
+
+    /// Like [`Value::decode`], applying `options` to every map, set and attribute in the reply.
+    pub fn decode_with(
+        input: &[u8],
+        options: &DecodeOptions,
+    ) -> anyhow::Result<Option<(Value, usize)>> {
+        let len = match Value::frame_len(input)? {
+            Some(len) => len,
+            None => return Ok(None),
+        };
+
+        let mut parser = PullParser::new(&input[..len]).with_options(*options);
+        let value = parser
+            .next_value()
+            .context("Value::decode (PullParser::next_value)")?
+            .context("Value::decode (empty frame)")?;
+
+        if parser.offset() != len {
+            bail!("Value::decode (trailing bytes in frame)");
+        }
+
+        Ok(Some((value, len)))
+    }
+}
+
+/// Finds the end of the first frame in a buffer filled a chunk at a time, without
+/// building it. Each call resumes where the last one stopped instead of starting over,
+/// so a large frame arriving in many chunks is only scanned once.
+///
+/// Pass the same buffer every time, with the new bytes appended. Once a length is
+/// returned the scanner starts over, expecting the buffer to begin with the next frame.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FrameScanner {
+    // where the next element starts
+    pos: usize,
+    // elements still expected by each aggregate being scanned, innermost last
+    remaining: Vec<usize>,
+}
+
+impl FrameScanner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The length of the first frame in `input`, `None` if more bytes are needed,
+    /// or an error if the frame is malformed.
+    pub fn scan(&mut self, input: &[u8]) -> anyhow::Result<Option<usize>> {
+        loop {
+            let (end, items) = match element(input, self.pos)? {
+                Some(element) => element,
+                None => return Ok(None),
+            };
+            self.pos = end;
+
+            if items > 0 {
+                self.remaining.push(items);
+                continue;
+            }
+
+            // the element may complete the aggregates it is in, and the frame
+            loop {
+                match self.remaining.last_mut() {
+                    None => {
+                        let len = self.pos;
+                        *self = Self::new();
+                        return Ok(Some(len));
+                    }
+                    Some(remaining) if *remaining > 1 => {
+                        *remaining -= 1;
+                        break;
+                    }
+                    Some(_) => drop(self.remaining.pop()),
+                }
+            }
+        }
+    }
+}
+
+// The end of the element starting at `pos` (of its header for aggregates), along with
+// the number of elements the aggregate holds.
+fn element(input: &[u8], pos: usize) -> anyhow::Result<Option<(usize, usize)>> {
+    let ty = match input.get(pos) {
+        Some(ty) => *ty,
+        None => return Ok(None),
+    };
+
+    let (line, next) = match line(input, pos + 1)? {
+        Some(line) => line,
+        None => return Ok(None),
+    };
+
+    match ty {
+        b'+' | b'-' | b':' | b',' | b'(' | b'_' | b'#' => Ok(Some((next, 0))),
+        // RESP2 null bulk string and null array
+        b'$' | b'*' if line == b"-1" => Ok(Some((next, 0))),
+        b'$' | b'!' | b'=' => {
+            let end = len(line)?
+                .checked_add(next + DELIMITER.len())
+                .ok_or_else(|| anyhow!("Value::frame_len (length overflow)"))?;
+            if input.len() < end {
+                return Ok(None);
+            }
+
+            if &input[end - DELIMITER.len()..end] != DELIMITER {
+                bail!(
+                    "Value::frame_len (missing delimiter at {})",
+                    end - DELIMITER.len()
+                );
+            }
+
+            Ok(Some((end, 0)))
+        }
+        b'*' | b'~' | b'>' | b'%' | b'|' => {
+            let count = match ty {
+                b'%' => len(line)?.checked_mul(2),
+                // attributes are always followed by the value they describe
+                b'|' => len(line)?.checked_mul(2).and_then(|n| n.checked_add(1)),
+                _ => Some(len(line)?),
+            }
+            .ok_or_else(|| anyhow!("Value::frame_len (length overflow)"))?;
+
+            Ok(Some((next, count)))
+        }
+        ty => bail!(
+            "Value::frame_len (unexpected type byte {:?} at {pos})",
+            ty as char
+        ),
+    }
+}
+
+fn line(input: &[u8], start: usize) -> anyhow::Result<Option<(&[u8], usize)>> {
+    let rest = &input[start.min(input.len())..];
+
+    match rest.iter().position(|b| *b == b'\r' || *b == b'\n') {
+        Some(i) => match (rest[i], rest.get(i + 1)) {
+            (b'\r', Some(b'\n')) => Ok(Some((&rest[..i], start + i + DELIMITER.len()))),
+            (b'\r', None) => Ok(None),
+            _ => bail!("Value::frame_len (unexpected line break at {})", start + i),
+        },
+        None => Ok(None),
+    }
+}
+
+fn len(line: &[u8]) -> anyhow::Result<usize> {
+    str::from_utf8(line)
+        .context("Value::frame_len (str::from_utf8)")
+        .and_then(|v| usize::from_str(v).context("Value::frame_len (usize::from_str)"))
+}
+
+pub(super) fn dedup(value: &mut Value, duplicates: Duplicates) -> anyhow::Result<()> {
+    if let Some(attr) = value.attr_mut() {
+        attr.val_mut()
+            .iter_mut()
+            .try_for_each(|(k, v)| dedup(k, duplicates).and_then(|_| dedup(v, duplicates)))?;
+
+        let entries = mem::take(attr.val_mut());
+        *attr.val_mut() = dedup_by(entries, |(k, _)| k, duplicates).context("Value::Attribute")?;
+    }
+
+    match value {
+        Value::Array(inner) => inner
+            .val_mut()
+            .iter_mut()
+            .try_for_each(|v| dedup(v, duplicates)),
+        Value::Push(inner) => inner
+            .val_mut()
+            .iter_mut()
+            .try_for_each(|v| dedup(v, duplicates)),
+        Value::Set(inner) => {
+            inner
+                .val_mut()
+                .iter_mut()
+                .try_for_each(|v| dedup(v, duplicates))?;
+
+            let items = mem::take(inner.val_mut());
+            *inner.val_mut() = dedup_by(items, |v| v, duplicates).context("Value::Set")?;
+            Ok(())
+        }
+        Value::Map(inner) => {
+            inner
+                .val_mut()
+                .iter_mut()
+                .try_for_each(|(k, v)| dedup(k, duplicates).and_then(|_| dedup(v, duplicates)))?;
+
+            let entries = mem::take(inner.val_mut());
+            *inner.val_mut() = dedup_by(entries, |(k, _)| k, duplicates).context("Value::Map")?;
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn dedup_by<T>(
+    items: Vec<T>,
+    key: fn(&T) -> &Value,
+    duplicates: Duplicates,
+) -> anyhow::Result<Vec<T>> {
+    // the position of the first item with the same key, for every item
+    let firsts = {
+        let mut seen = BTreeMap::new();
+
+        items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| *seen.entry(key(item)).or_insert(i))
+            .collect::<Vec<_>>()
+    };
+
+    let mut out = Vec::with_capacity(items.len());
+    let mut slots = vec![0; items.len()];
+
+    for (i, (item, first)) in items.into_iter().zip(firsts).enumerate() {
+        if i == first {
+            slots[i] = out.len();
+            out.push(item);
+            continue;
+        }
+
+        match duplicates {
+            Duplicates::Reject => bail!("Value::decode (duplicate at {i}, first seen at {first})"),
+            Duplicates::Keep => out.push(item),
+            Duplicates::LastWins => out[slots[first]] = item,
+        }
+    }
+
+    Ok(out)
+}
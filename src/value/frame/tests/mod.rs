@@ -0,0 +1,118 @@
+pub mod prop;
+
+use bytes::Bytes;
+
+use crate::value::*;
+
+#[test]
+fn test_complete() {
+    assert_eq!(Value::frame_len(b"+OK\r\n:1\r\n").unwrap(), Some(5));
+    assert_eq!(Value::frame_len(b"$5\r\na\r\nbc\r\n").unwrap(), Some(11));
+    assert_eq!(
+        Value::frame_len(b"%1\r\n+k\r\n*1\r\n_\r\n").unwrap(),
+        Some(15)
+    );
+    assert_eq!(
+        Value::frame_len(b"|1\r\n+k\r\n+v\r\n#t\r\n").unwrap(),
+        Some(16)
+    );
+}
+
+#[test]
+fn test_incomplete() {
+    assert_eq!(Value::frame_len(b"").unwrap(), None);
+    assert_eq!(Value::frame_len(b"+OK\r").unwrap(), None);
+    assert_eq!(Value::frame_len(b"$5\r\nab").unwrap(), None);
+    assert_eq!(Value::frame_len(b"*2\r\n:1\r\n").unwrap(), None);
+    assert_eq!(Value::frame_len(b"|1\r\n+k\r\n+v\r\n").unwrap(), None);
+}
+
+#[test]
+fn test_malformed() {
+    assert!(Value::frame_len(b"?\r\n").is_err());
+    assert!(Value::frame_len(b"+O\nK\r\n").is_err());
+    assert!(Value::frame_len(b"$2\r\nabc\r\n").is_err());
+    assert!(Value::frame_len(b"*x\r\n").is_err());
+}
+
+#[test]
+fn test_scanner() {
+    let input = b"*2\r\n$5\r\nhello\r\n|1\r\n+k\r\n+v\r\n:1\r\n+next\r\n";
+    let mut scanner = FrameScanner::new();
+
+    // resumes within the blob, the attribute and the value it describes
+    for len in [0, 3, 9, 15, 21, 27, 30] {
+        assert_eq!(scanner.scan(&input[..len]).unwrap(), None, "{len}");
+    }
+    assert_eq!(scanner.scan(input).unwrap(), Some(31));
+
+    // and starts over with the next frame
+    assert_eq!(scanner.scan(&input[31..]).unwrap(), Some(7));
+    assert!(scanner.scan(b"*1\r\n?\r\n").is_err());
+}
+
+#[test]
+fn test_decode() {
+    assert_eq!(
+        Value::decode(b"+OK\r\n+rest").unwrap(),
+        Some((Value::from(SimpleString::new("OK").unwrap()), 5))
+    );
+
+    assert_eq!(Value::decode(b"+OK").unwrap(), None);
+    assert!(Value::decode(b":abc\r\n").is_err());
+}
+
+#[test]
+fn test_decode_attribute_duplicates() {
+    let raw = b"|2\r\n+a\r\n:1\r\n+a\r\n%2\r\n+k\r\n:1\r\n+k\r\n:2\r\n:5\r\n";
+
+    let decode = |duplicates| {
+        Value::decode_with(raw, &DecodeOptions { duplicates }).map(|v| v.map(|(v, _)| v))
+    };
+
+    let value = decode(Duplicates::Keep).unwrap().unwrap();
+    assert_eq!(Bytes::try_from(&value).unwrap(), &raw[..]);
+
+    let key = |k: &str| Value::from(SimpleString::new(k.to_string()).unwrap());
+    let num = |n: i64| Value::from(Number::from(n));
+    assert_eq!(
+        decode(Duplicates::LastWins).unwrap(),
+        Some(num(5).with_attr(Attribute::from([(
+            key("a"),
+            Value::from(Map::from([(key("k"), num(2))]))
+        )])))
+    );
+    assert!(decode(Duplicates::Reject).is_err());
+}
+
+#[test]
+fn test_decode_duplicates() {
+    let raw = b"*2\r\n%3\r\n+b\r\n:1\r\n+a\r\n:2\r\n+b\r\n:3\r\n~3\r\n:2\r\n:1\r\n:2\r\n";
+
+    let key = |k: &str| Value::from(SimpleString::new(k.to_string()).unwrap());
+    let num = |n: i64| Value::from(Number::from(n));
+
+    let decode = |duplicates| {
+        Value::decode_with(raw, &DecodeOptions { duplicates }).map(|v| v.map(|(v, _)| v))
+    };
+
+    assert_eq!(
+        decode(Duplicates::Keep).unwrap(),
+        Some(Value::decode(raw).unwrap().unwrap().0)
+    );
+    assert_eq!(
+        decode(Duplicates::LastWins).unwrap(),
+        Some(Value::from(Array::from([
+            Value::from(Map::from([(key("b"), num(3)), (key("a"), num(2))])),
+            Value::from(Set::from([num(2), num(1)])),
+        ])))
+    );
+    assert!(decode(Duplicates::Reject).is_err());
+    assert!(Value::decode_with(
+        b"%2\r\n+a\r\n:1\r\n+b\r\n:1\r\n",
+        &DecodeOptions {
+            duplicates: Duplicates::Reject
+        }
+    )
+    .is_ok());
+}
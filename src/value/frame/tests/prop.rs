@@ -0,0 +1,21 @@
+use bytes::Bytes;
+use proptest::prelude::*;
+
+use crate::value::{FrameScanner, Value};
+
+proptest! {
+    #[test]
+    fn test_basic(v in crate::value::tests::prop::value()) {
+        let bytes = Bytes::try_from(v.clone()).unwrap();
+
+        assert_eq!(Value::frame_len(&bytes).unwrap(), Some(bytes.len()));
+        assert_eq!(Value::decode(&bytes).unwrap(), Some((v, bytes.len())));
+
+        let mut scanner = FrameScanner::new();
+        for len in 0..bytes.len() {
+            assert_eq!(Value::frame_len(&bytes[..len]).unwrap(), None);
+            assert_eq!(scanner.scan(&bytes[..len]).unwrap(), None);
+        }
+        assert_eq!(scanner.scan(&bytes).unwrap(), Some(bytes.len()));
+    }
+}
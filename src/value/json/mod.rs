@@ -0,0 +1,318 @@
+#[cfg(test)]
+pub(crate) mod tests;
+
+use std::str::{self, FromStr};
+
+use anyhow::{anyhow, bail, Context};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bytes::Bytes;
+use num_bigint::BigInt;
+use serde_json::{json, Map as Object, Number as JsonNumber, Value as Json};
+
+use super::{
+    Array, Attribute, BigNumber, BlobError, BlobString, Boolean, Double, Map, Null, Number, Push,
+    Set, SimpleError, SimpleString, Value, VerbatimFormat, VerbatimString,
+};
+
+impl Value {
+    /// Converts into the JSON a human would expect:
+    /// maps become objects (non-string keys are stringified), arrays and sets become arrays,
+    /// big numbers become strings and attributes are dropped.
+    pub fn to_json(&self) -> Json {
+        match self {
+            Value::Array(inner) => Json::Array(inner.val().iter().map(Value::to_json).collect()),
+            Value::Set(inner) => Json::Array(inner.val().iter().map(Value::to_json).collect()),
+            Value::Push(inner) => Json::Array(inner.val().iter().map(Value::to_json).collect()),
+            Value::Map(inner) => Json::Object(
+                inner
+                    .iter()
+                    .map(|(k, v)| (json_key(k), v.to_json()))
+                    .collect(),
+            ),
+            Value::BigNumber(inner) => Json::String(inner.val().to_string()),
+            Value::BlobError(inner) => json!({
+                "error": inner.to_string_lossy()
+            }),
+            Value::BlobString(inner) => Json::String(lossy(inner.val())),
+            Value::Boolean(inner) => Json::Bool(inner.val()),
+            Value::Double(inner) => JsonNumber::from_f64(inner.val())
+                .map(Json::Number)
+                .unwrap_or_else(|| Json::String(inner.text().to_string())),
+            Value::Null(_) => Json::Null,
+            Value::Number(inner) => Json::from(inner.val()),
+            Value::SimpleError(inner) => json!({
+                "error": inner.to_string_lossy()
+            }),
+            Value::SimpleString(inner) => Json::String(inner.to_string_lossy().into_owned()),
+            Value::VerbatimString(inner) => Json::String(lossy(inner.val())),
+        }
+    }
+
+    /// Builds a value from arbitrary JSON:
+    /// objects become maps with blob string keys, strings become blob strings.
+    pub fn from_json(input: &Json) -> anyhow::Result<Self> {
+        let value = match input {
+            Json::Null => Value::from(Null::default()),
+            Json::Bool(val) => Value::from(Boolean::from(*val)),
+            Json::Number(val) => match val.as_i64() {
+                Some(val) => Value::from(Number::from(val)),
+                None => {
+                    Value::from(Double::from_str(&val.to_string()).context("Value::from_json")?)
+                }
+            },
+            Json::String(val) => Value::from(BlobString::from(val.clone())),
+            Json::Array(values) => Value::from(Array::from(
+                values
+                    .iter()
+                    .map(Value::from_json)
+                    .collect::<anyhow::Result<Vec<_>>>()?,
+            )),
+            Json::Object(entries) => Value::from(Map::from(
+                entries
+                    .iter()
+                    .map(|(k, v)| {
+                        let k = Value::from(BlobString::from(k.clone()));
+                        Value::from_json(v).map(|v| (k, v))
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?,
+            )),
+        };
+
+        Ok(value)
+    }
+
+    /// Converts into a lossless tagged form which keeps every type,
+    /// every attribute and every non UTF-8 payload (as base64).
+    pub fn to_tagged_json(&self) -> Json {
+        let mut object = match self {
+            Value::Array(inner) => tagged("array", tagged_seq(inner.val().iter())),
+            Value::Set(inner) => tagged("set", tagged_seq(inner.val().iter())),
+            Value::Push(inner) => tagged("push", tagged_seq(inner.val().iter())),
+            Value::Map(inner) => tagged("map", tagged_entries(inner.iter())),
+            Value::BigNumber(inner) => tagged("big_number", Json::String(inner.val().to_string())),
+            Value::BlobError(inner) => tagged_bytes("blob_error", inner.val()),
+            Value::BlobString(inner) => tagged_bytes("blob_string", inner.val()),
+            Value::Boolean(inner) => tagged("boolean", Json::Bool(inner.val())),
+            Value::Double(inner) => tagged("double", Json::String(inner.text().to_string())),
+            Value::Null(_) => tagged("null", Json::Null),
+            Value::Number(inner) => tagged("number", Json::from(inner.val())),
+            Value::SimpleError(inner) => tagged_bytes("simple_error", inner.val()),
+            Value::SimpleString(inner) => tagged_bytes("simple_string", inner.val()),
+            Value::VerbatimString(inner) => {
+                let mut object = tagged_bytes("verbatim_string", inner.val());
+
+                match str::from_utf8(inner.format().as_bytes()) {
+                    Ok(format) => object.insert("format".into(), Json::String(format.to_string())),
+                    Err(_) => object.insert(
+                        "format_base64".into(),
+                        Json::String(STANDARD.encode(inner.format().as_bytes())),
+                    ),
+                };
+
+                object
+            }
+        };
+
+        if let Some(attr) = self.attr() {
+            object.insert("attr".into(), tagged_entries(attr.iter()));
+        }
+
+        Json::Object(object)
+    }
+
+    /// Inverse of [`Value::to_tagged_json`].
+    pub fn from_tagged_json(input: &Json) -> anyhow::Result<Self> {
+        let object = input
+            .as_object()
+            .ok_or_else(|| anyhow!("Value::from_tagged_json (expected object)"))?;
+
+        let ty = object
+            .get("type")
+            .and_then(Json::as_str)
+            .ok_or_else(|| anyhow!("Value::from_tagged_json (missing type)"))?;
+
+        let attr = object
+            .get("attr")
+            .map(|attr| untagged_entries(attr).map(Attribute::from))
+            .transpose()
+            .context("Value::from_tagged_json (attr)")?;
+
+        let value = match ty {
+            "array" => {
+                let val = Array::from(untagged_seq(field(object, "value")?)?);
+                Value::from(with_attr(val, attr, Array::with_attr))
+            }
+            "set" => {
+                let val = Set::from(untagged_seq(field(object, "value")?)?);
+                Value::from(with_attr(val, attr, Set::with_attr))
+            }
+            "push" => {
+                let val = Push::from(untagged_seq(field(object, "value")?)?);
+                Value::from(with_attr(val, attr, Push::with_attr))
+            }
+            "map" => {
+                let val = Map::from(untagged_entries(field(object, "value")?)?);
+                Value::from(with_attr(val, attr, Map::with_attr))
+            }
+            "big_number" => {
+                let val = BigInt::from_str(str_field(object, "value")?)
+                    .context("Value::from_tagged_json (BigInt::from_str)")?;
+                Value::from(with_attr(BigNumber::from(val), attr, BigNumber::with_attr))
+            }
+            "blob_error" => {
+                let val = BlobError::new(untagged_bytes(object)?);
+                Value::from(with_attr(val, attr, BlobError::with_attr))
+            }
+            "blob_string" => {
+                let val = BlobString::from(untagged_bytes(object)?);
+                Value::from(with_attr(val, attr, BlobString::with_attr))
+            }
+            "boolean" => {
+                let val = field(object, "value")?
+                    .as_bool()
+                    .ok_or_else(|| anyhow!("Value::from_tagged_json (expected bool)"))?;
+                Value::from(with_attr(Boolean::from(val), attr, Boolean::with_attr))
+            }
+            "double" => {
+                let val = Double::from_str(str_field(object, "value")?)
+                    .context("Value::from_tagged_json")?;
+                Value::from(with_attr(val, attr, Double::with_attr))
+            }
+            "null" => Value::from(with_attr(Null::default(), attr, Null::with_attr)),
+            "number" => {
+                let val = field(object, "value")?
+                    .as_i64()
+                    .ok_or_else(|| anyhow!("Value::from_tagged_json (expected i64)"))?;
+                Value::from(with_attr(Number::from(val), attr, Number::with_attr))
+            }
+            "simple_error" => {
+                let val =
+                    SimpleError::new(untagged_bytes(object)?).context("Value::from_tagged_json")?;
+                Value::from(with_attr(val, attr, SimpleError::with_attr))
+            }
+            "simple_string" => {
+                let val = SimpleString::new(untagged_bytes(object)?)
+                    .context("Value::from_tagged_json")?;
+                Value::from(with_attr(val, attr, SimpleString::with_attr))
+            }
+            "verbatim_string" => {
+                let format = match object.get("format_base64") {
+                    Some(_) => STANDARD
+                        .decode(str_field(object, "format_base64")?)
+                        .context("Value::from_tagged_json (base64::decode)")?,
+                    None => str_field(object, "format")?.as_bytes().to_vec(),
+                };
+                let format = <[u8; 3]>::try_from(format.as_slice())
+                    .map_err(|_| anyhow!("Value::from_tagged_json (invalid format {format:?})"))?;
+
+                let val =
+                    VerbatimString::new(VerbatimFormat::from(format), untagged_bytes(object)?);
+                Value::from(with_attr(val, attr, VerbatimString::with_attr))
+            }
+            ty => bail!("Value::from_tagged_json (unknown type {ty:?})"),
+        };
+
+        Ok(value)
+    }
+}
+
+fn json_key(key: &Value) -> String {
+    match key.to_json() {
+        Json::String(key) => key,
+        key => key.to_string(),
+    }
+}
+
+fn lossy(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+fn with_attr<T>(val: T, attr: Option<Attribute>, f: fn(T, Attribute) -> T) -> T {
+    match attr {
+        Some(attr) => f(val, attr),
+        None => val,
+    }
+}
+
+fn tagged(ty: &str, value: Json) -> Object<String, Json> {
+    let mut object = Object::new();
+    object.insert("type".into(), Json::String(ty.to_string()));
+
+    if !value.is_null() {
+        object.insert("value".into(), value);
+    }
+
+    object
+}
+
+fn tagged_bytes(ty: &str, bytes: &[u8]) -> Object<String, Json> {
+    match str::from_utf8(bytes) {
+        Ok(text) => tagged(ty, Json::String(text.to_string())),
+        Err(_) => {
+            let mut object = tagged(ty, Json::Null);
+            object.insert("base64".into(), Json::String(STANDARD.encode(bytes)));
+            object
+        }
+    }
+}
+
+fn tagged_seq<'a>(values: impl Iterator<Item = &'a Value>) -> Json {
+    Json::Array(values.map(Value::to_tagged_json).collect())
+}
+
+fn tagged_entries<'a>(entries: impl Iterator<Item = (&'a Value, &'a Value)>) -> Json {
+    Json::Array(
+        entries
+            .map(|(k, v)| Json::Array(vec![k.to_tagged_json(), v.to_tagged_json()]))
+            .collect(),
+    )
+}
+
+fn field<'a>(object: &'a Object<String, Json>, name: &str) -> anyhow::Result<&'a Json> {
+    object
+        .get(name)
+        .ok_or_else(|| anyhow!("Value::from_tagged_json (missing {name})"))
+}
+
+fn str_field<'a>(object: &'a Object<String, Json>, name: &str) -> anyhow::Result<&'a str> {
+    field(object, name)?
+        .as_str()
+        .ok_or_else(|| anyhow!("Value::from_tagged_json (expected string {name})"))
+}
+
+fn untagged_bytes(object: &Object<String, Json>) -> anyhow::Result<Bytes> {
+    match object.get("base64") {
+        Some(encoded) => {
+            let encoded = encoded
+                .as_str()
+                .ok_or_else(|| anyhow!("Value::from_tagged_json (expected string base64)"))?;
+
+            STANDARD
+                .decode(encoded)
+                .map(Bytes::from)
+                .context("Value::from_tagged_json (base64::decode)")
+        }
+        None => str_field(object, "value").map(|text| Bytes::from(text.to_string())),
+    }
+}
+
+fn untagged_seq(input: &Json) -> anyhow::Result<Vec<Value>> {
+    input
+        .as_array()
+        .ok_or_else(|| anyhow!("Value::from_tagged_json (expected array)"))?
+        .iter()
+        .map(Value::from_tagged_json)
+        .collect()
+}
+
+fn untagged_entries(input: &Json) -> anyhow::Result<Vec<(Value, Value)>> {
+    input
+        .as_array()
+        .ok_or_else(|| anyhow!("Value::from_tagged_json (expected array)"))?
+        .iter()
+        .map(|entry| match entry.as_array().map(Vec::as_slice) {
+            Some([k, v]) => Ok((Value::from_tagged_json(k)?, Value::from_tagged_json(v)?)),
+            _ => bail!("Value::from_tagged_json (expected [key, value] pair)"),
+        })
+        .collect()
+}
@@ -0,0 +1,103 @@
+pub mod prop;
+
+use serde_json::json;
+
+use super::*;
+use crate::value::complete::primitive::double::Sign;
+
+#[test]
+fn test_natural() {
+    let value = Value::from(Map::from([
+        (
+            Value::from(BlobString::from("name")),
+            Value::from(SimpleString::new("redis").unwrap()),
+        ),
+        (
+            Value::from(Number::from(1)),
+            Value::from(Array::from([
+                Value::from(Null::default()),
+                Value::from(Boolean::from(true)),
+                Value::from(BigNumber::from(BigInt::from(12345))),
+            ])),
+        ),
+        (
+            Value::from(BlobString::from("set")),
+            Value::from(Set::from([Value::from(Double::inf(Sign::Minus))])),
+        ),
+        (
+            Value::from(BlobString::from("err")),
+            Value::from(SimpleError::new("ERR reason").unwrap()),
+        ),
+    ]));
+
+    assert_eq!(
+        value.to_json(),
+        json!({
+            "name": "redis",
+            "1": [null, true, "12345"],
+            "set": ["-inf"],
+            "err": {"error": "ERR reason"},
+        })
+    );
+}
+
+#[test]
+fn test_from_natural() {
+    let value = Value::from_json(&json!({"a": [1, 1.5, "x", null, false]})).unwrap();
+
+    assert_eq!(
+        value,
+        Value::from(Map::from([(
+            Value::from(BlobString::from("a")),
+            Value::from(Array::from([
+                Value::from(Number::from(1)),
+                Value::from(Double::from(1.5)),
+                Value::from(BlobString::from("x")),
+                Value::from(Null::default()),
+                Value::from(Boolean::from(false)),
+            ])),
+        )]))
+    );
+}
+
+#[test]
+fn test_tagged() {
+    let attr = Attribute::from([(
+        Value::from(SimpleString::new("ttl").unwrap()),
+        Value::from(Number::from(3600)),
+    )]);
+
+    let value = Value::from(Array::from([
+        Value::from(BlobString::from(b"\xff\x00".to_vec())),
+        Value::from(VerbatimString::mkd("# title").with_attr(attr)),
+    ]));
+
+    let tagged = value.to_tagged_json();
+    assert_eq!(
+        tagged,
+        json!({
+            "type": "array",
+            "value": [
+                {"type": "blob_string", "base64": "/wA="},
+                {
+                    "type": "verbatim_string",
+                    "format": "mkd",
+                    "value": "# title",
+                    "attr": [[
+                        {"type": "simple_string", "value": "ttl"},
+                        {"type": "number", "value": 3600},
+                    ]],
+                },
+            ],
+        })
+    );
+
+    assert_eq!(Value::from_tagged_json(&tagged).unwrap(), value);
+}
+
+#[test]
+fn test_tagged_invalid() {
+    assert!(Value::from_tagged_json(&json!({"type": "unknown"})).is_err());
+    assert!(Value::from_tagged_json(&json!({"type": "number", "value": "1"})).is_err());
+    assert!(Value::from_tagged_json(&json!({"type": "map", "value": [[]]})).is_err());
+}
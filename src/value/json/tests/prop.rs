@@ -0,0 +1,18 @@
+use proptest::prelude::*;
+
+use crate::value::{complete::recursive::array::tests::prop::value_with_attr, Value};
+
+proptest! {
+    #[test]
+    fn test_tagged(v in crate::value::tests::prop::value()) {
+        let json = v.to_tagged_json();
+        assert_eq!(Value::from_tagged_json(&json).unwrap(), v);
+    }
+
+    #[test]
+    fn test_tagged_with_attr(v in value_with_attr()) {
+        let v = Value::from(v);
+        let json = v.to_tagged_json();
+        assert_eq!(Value::from_tagged_json(&json).unwrap(), v);
+    }
+}
@@ -2,16 +2,33 @@
 pub(crate) mod tests;
 
 mod complete;
+mod convert;
+mod display;
+mod frame;
+#[cfg(feature = "json")]
+mod json;
+mod pull;
+mod walk;
+
+pub use convert::FromValue;
+pub use display::Raw;
+pub use frame::{DecodeOptions, Duplicates, FrameScanner};
+pub use pull::{PullEvent, PullParser};
+pub use walk::PathSegment;
 
 pub use complete::{
     primitive::{BigNumber, Boolean, Double, Null, Number},
-    recursive::{Array, Map, Set},
+    recursive::{Array, Map, Push, Set},
     special::Attribute,
-    textual::{BlobError, BlobString, SimpleError, SimpleString, VerbatimString},
+    textual::{BlobError, BlobString, SimpleError, SimpleString, VerbatimFormat, VerbatimString},
 };
 
+pub(crate) use complete::textual::simple_error::split_code;
+
 use bytes::Bytes;
-use nom::{branch::alt, IResult, Parser};
+use nom::IResult;
+
+use crate::error::ServerError;
 
 static DELIMITER: &[u8] = b"\r\n";
 
@@ -26,6 +43,7 @@ pub enum Value {
     Map(Map),
     Null(Null),
     Number(Number),
+    Push(Push),
     Set(Set),
     SimpleError(SimpleError),
     SimpleString(SimpleString),
@@ -33,23 +51,112 @@ pub enum Value {
 }
 
 impl Value {
+    /// Parses the first value in `input` out of [`PullParser`] events, so that both
+    /// follow the same grammar. Fails where the event which could not be parsed starts.
     pub fn parse(input: &[u8]) -> IResult<&[u8], Self> {
-        alt((
-            Array::parse.map(Value::from),
-            BigNumber::parse.map(Value::from),
-            BlobError::parse.map(Value::from),
-            BlobString::parse.map(Value::from),
-            Boolean::parse.map(Value::from),
-            Double::parse.map(Value::from),
-            Map::parse.map(Value::from),
-            Null::parse.map(Value::from),
-            Number::parse.map(Value::from),
-            Set::parse.map(Value::from),
-            SimpleError::parse.map(Value::from),
-            SimpleString::parse.map(Value::from),
-            VerbatimString::parse.map(Value::from),
-        ))
-        .parse(input)
+        let mut parser = PullParser::new(input);
+
+        match parser.next_value() {
+            Ok(Some(value)) => Ok((&input[parser.offset()..], value)),
+            _ => Err(nom::Err::Error(nom::error::Error::new(
+                &input[parser.offset()..],
+                nom::error::ErrorKind::Verify,
+            ))),
+        }
+    }
+
+    pub fn attr(&self) -> Option<&Attribute> {
+        match self {
+            Value::Array(inner) => inner.attr(),
+            Value::BigNumber(inner) => inner.attr(),
+            Value::BlobError(inner) => inner.attr(),
+            Value::BlobString(inner) => inner.attr(),
+            Value::Boolean(inner) => inner.attr(),
+            Value::Double(inner) => inner.attr(),
+            Value::Map(inner) => inner.attr(),
+            Value::Null(inner) => inner.attr(),
+            Value::Number(inner) => inner.attr(),
+            Value::Push(inner) => inner.attr(),
+            Value::Set(inner) => inner.attr(),
+            Value::SimpleError(inner) => inner.attr(),
+            Value::SimpleString(inner) => inner.attr(),
+            Value::VerbatimString(inner) => inner.attr(),
+        }
+    }
+
+    pub fn attr_mut(&mut self) -> &mut Option<Attribute> {
+        match self {
+            Value::Array(inner) => inner.attr_mut(),
+            Value::BigNumber(inner) => inner.attr_mut(),
+            Value::BlobError(inner) => inner.attr_mut(),
+            Value::BlobString(inner) => inner.attr_mut(),
+            Value::Boolean(inner) => inner.attr_mut(),
+            Value::Double(inner) => inner.attr_mut(),
+            Value::Map(inner) => inner.attr_mut(),
+            Value::Null(inner) => inner.attr_mut(),
+            Value::Number(inner) => inner.attr_mut(),
+            Value::Push(inner) => inner.attr_mut(),
+            Value::Set(inner) => inner.attr_mut(),
+            Value::SimpleError(inner) => inner.attr_mut(),
+            Value::SimpleString(inner) => inner.attr_mut(),
+            Value::VerbatimString(inner) => inner.attr_mut(),
+        }
+    }
+
+    pub fn take_attr(&mut self) -> Option<Attribute> {
+        match self {
+            Value::Array(inner) => inner.take_attr(),
+            Value::BigNumber(inner) => inner.take_attr(),
+            Value::BlobError(inner) => inner.take_attr(),
+            Value::BlobString(inner) => inner.take_attr(),
+            Value::Boolean(inner) => inner.take_attr(),
+            Value::Double(inner) => inner.take_attr(),
+            Value::Map(inner) => inner.take_attr(),
+            Value::Null(inner) => inner.take_attr(),
+            Value::Number(inner) => inner.take_attr(),
+            Value::Push(inner) => inner.take_attr(),
+            Value::Set(inner) => inner.take_attr(),
+            Value::SimpleError(inner) => inner.take_attr(),
+            Value::SimpleString(inner) => inner.take_attr(),
+            Value::VerbatimString(inner) => inner.take_attr(),
+        }
+    }
+
+    /// The error of an error reply, `None` for any other value.
+    pub fn server_error(&self) -> Option<ServerError> {
+        match self {
+            Value::BlobError(inner) => Some(ServerError::new(inner.val().clone())),
+            Value::SimpleError(inner) => Some(ServerError::new(inner.val().clone())),
+            _ => None,
+        }
+    }
+
+    /// Turns error replies into `Err`, any other value is returned as is.
+    pub fn into_result(self) -> Result<Value, ServerError> {
+        match self {
+            Value::BlobError(inner) => Err(ServerError::from(inner)),
+            Value::SimpleError(inner) => Err(ServerError::from(inner)),
+            value => Ok(value),
+        }
+    }
+
+    pub fn with_attr(self, attr: Attribute) -> Self {
+        match self {
+            Value::Array(inner) => Value::from(inner.with_attr(attr)),
+            Value::BigNumber(inner) => Value::from(inner.with_attr(attr)),
+            Value::BlobError(inner) => Value::from(inner.with_attr(attr)),
+            Value::BlobString(inner) => Value::from(inner.with_attr(attr)),
+            Value::Boolean(inner) => Value::from(inner.with_attr(attr)),
+            Value::Double(inner) => Value::from(inner.with_attr(attr)),
+            Value::Map(inner) => Value::from(inner.with_attr(attr)),
+            Value::Null(inner) => Value::from(inner.with_attr(attr)),
+            Value::Number(inner) => Value::from(inner.with_attr(attr)),
+            Value::Push(inner) => Value::from(inner.with_attr(attr)),
+            Value::Set(inner) => Value::from(inner.with_attr(attr)),
+            Value::SimpleError(inner) => Value::from(inner.with_attr(attr)),
+            Value::SimpleString(inner) => Value::from(inner.with_attr(attr)),
+            Value::VerbatimString(inner) => Value::from(inner.with_attr(attr)),
+        }
     }
 }
 
@@ -67,6 +174,7 @@ impl TryFrom<&Value> for Bytes {
             Value::Map(inner) => Bytes::try_from(inner),
             Value::Null(inner) => Bytes::try_from(inner),
             Value::Number(inner) => Bytes::try_from(inner),
+            Value::Push(inner) => Bytes::try_from(inner),
             Value::Set(inner) => Bytes::try_from(inner),
             Value::SimpleError(inner) => Bytes::try_from(inner),
             Value::SimpleString(inner) => Bytes::try_from(inner),
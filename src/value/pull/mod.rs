@@ -0,0 +1,291 @@
+#[cfg(test)]
+pub(crate) mod tests;
+
+use anyhow::{bail, Context};
+use nom::{branch::alt, combinator::recognize, IResult, Parser};
+use num_bigint::BigInt;
+
+use super::{
+    frame::dedup, Array, Attribute, BigNumber, BlobError, BlobString, Boolean, DecodeOptions,
+    Double, Duplicates, Map, Null, Number, Push, Set, SimpleError, SimpleString, Value,
+    VerbatimString,
+};
+
+/// A single step of a [`PullParser`].
+///
+/// Aggregates are reported as a start event carrying the announced length
+/// (in pairs for maps and attributes), their items and a matching [`PullEvent::End`].
+/// An attribute is followed by the value it describes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PullEvent<'a> {
+    ArrayStart(usize),
+    AttributeStart(usize),
+    MapStart(usize),
+    PushStart(usize),
+    SetStart(usize),
+    End,
+    BigNumber(BigInt),
+    Blob(&'a [u8]),
+    BlobError(BlobError),
+    Boolean(bool),
+    Double(f64),
+    Null,
+    Number(i64),
+    SimpleError(SimpleError),
+    SimpleString(&'a [u8]),
+    Verbatim(VerbatimString),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Level {
+    attribute: bool,
+    remaining: usize,
+}
+
+/// Walks through RESP frames one event at a time without building a [`Value`] tree.
+///
+/// The input has to hold complete frames, use [`Value::frame_len`] to find their boundaries.
+/// Blobs and simple strings are borrowed from the input.
+#[derive(Debug, Clone)]
+pub struct PullParser<'a> {
+    input: &'a [u8],
+    offset: usize,
+    raw: &'a [u8],
+    stack: Vec<Level>,
+    described: bool,
+    failed: bool,
+    options: DecodeOptions,
+}
+
+impl<'a> PullParser<'a> {
+    pub fn new(input: &'a [u8]) -> Self {
+        Self {
+            input,
+            offset: 0,
+            raw: &input[..0],
+            stack: vec![],
+            described: false,
+            failed: false,
+            options: DecodeOptions::default(),
+        }
+    }
+
+    pub fn with_options(mut self, options: DecodeOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Number of bytes consumed so far.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Number of aggregates (attributes included) the parser is currently inside of.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// The bytes the last event was parsed from (empty for [`PullEvent::End`]).
+    pub fn raw(&self) -> &'a [u8] {
+        self.raw
+    }
+
+    /// Returns `None` once the input is exhausted on a value boundary.
+    pub fn next_event(&mut self) -> anyhow::Result<Option<PullEvent<'a>>> {
+        let result = self.advance();
+        self.failed = result.is_err();
+
+        result
+    }
+
+    /// Builds the next value (e.g. the next item of the current aggregate) out of events,
+    /// handling repeated map keys and set items as the options say.
+    pub fn next_value(&mut self) -> anyhow::Result<Option<Value>> {
+        let mut value = match self.next_event()? {
+            Some(event) => self.build(event)?,
+            None => return Ok(None),
+        };
+
+        if self.options.duplicates != Duplicates::Keep {
+            dedup(&mut value, self.options.duplicates)?;
+        }
+
+        Ok(Some(value))
+    }
+
+    fn advance(&mut self) -> anyhow::Result<Option<PullEvent<'a>>> {
+        if let Some(level) = self.stack.pop_if(|level| level.remaining == 0) {
+            match level.attribute {
+                true => self.described = true,
+                false => self.complete(),
+            }
+
+            self.raw = &self.input[self.offset..self.offset];
+
+            return Ok(Some(PullEvent::End));
+        }
+
+        let rest = &self.input[self.offset..];
+        if rest.is_empty() {
+            match self.stack.is_empty() && !self.described {
+                true => return Ok(None),
+                false => bail!("PullParser (unexpected end of input at {})", self.offset),
+            }
+        }
+
+        let (tail, event) = match event(rest) {
+            Ok(ok) => ok,
+            Err(nom::Err::Error(err) | nom::Err::Failure(err)) => bail!(
+                "PullParser ({:?} at {})",
+                err.code,
+                self.input.len() - err.input.len()
+            ),
+            Err(nom::Err::Incomplete(_)) => {
+                bail!("PullParser (incomplete frame at {})", self.offset)
+            }
+        };
+
+        if self.described && matches!(event, PullEvent::AttributeStart(_)) {
+            bail!(
+                "PullParser (attribute describing an attribute at {})",
+                self.offset
+            );
+        }
+
+        let len = rest.len() - tail.len();
+        self.raw = &rest[..len];
+        self.offset += len;
+        self.described = false;
+
+        let level = |attribute, remaining: Option<usize>| {
+            remaining
+                .map(|remaining| Level {
+                    attribute,
+                    remaining,
+                })
+                .context("PullParser (length overflow)")
+        };
+
+        match event {
+            PullEvent::AttributeStart(len) => self.stack.push(level(true, len.checked_mul(2))?),
+            PullEvent::MapStart(len) => self.stack.push(level(false, len.checked_mul(2))?),
+            PullEvent::ArrayStart(len) | PullEvent::PushStart(len) | PullEvent::SetStart(len) => {
+                self.stack.push(level(false, Some(len))?)
+            }
+            _ => self.complete(),
+        }
+
+        Ok(Some(event))
+    }
+
+    fn complete(&mut self) {
+        if let Some(level) = self.stack.last_mut() {
+            level.remaining -= 1;
+        }
+    }
+
+    fn build(&mut self, event: PullEvent<'a>) -> anyhow::Result<Value> {
+        let value = match event {
+            PullEvent::ArrayStart(len) => Value::from(Array::from(self.items(len)?)),
+            PullEvent::AttributeStart(len) => {
+                let attr = Attribute::from(self.pairs(len)?);
+                let value = self.required()?;
+
+                return self.build(value).map(|value| value.with_attr(attr));
+            }
+            PullEvent::MapStart(len) => Value::from(Map::from(self.pairs(len)?)),
+            PullEvent::PushStart(len) => Value::from(Push::from(self.items(len)?)),
+            PullEvent::SetStart(len) => Value::from(Set::from(self.items(len)?)),
+            PullEvent::End => bail!("PullParser (unexpected end of aggregate)"),
+            PullEvent::BigNumber(val) => Value::from(BigNumber::from(val)),
+            PullEvent::Blob(val) => Value::from(BlobString::from(val.to_vec())),
+            PullEvent::BlobError(val) => Value::from(val),
+            PullEvent::Boolean(val) => Value::from(Boolean::from(val)),
+            // the event only carries the f64, the exact textual form comes from the frame
+            PullEvent::Double(_) => match Double::parse(self.raw) {
+                Ok((_, val)) => Value::from(val),
+                Err(_) => bail!("PullParser (Double::parse)"),
+            },
+            PullEvent::Null => Value::from(Null::default()),
+            PullEvent::Number(val) => Value::from(Number::from(val)),
+            PullEvent::SimpleError(val) => Value::from(val),
+            PullEvent::SimpleString(val) => {
+                Value::from(SimpleString::new(val.to_vec()).context("PullParser")?)
+            }
+            PullEvent::Verbatim(val) => Value::from(val),
+        };
+
+        Ok(value)
+    }
+
+    fn required(&mut self) -> anyhow::Result<PullEvent<'a>> {
+        self.next_event()?
+            .context("PullParser (unexpected end of input)")
+    }
+
+    // the announced length is not trusted for preallocation
+    fn items(&mut self, len: usize) -> anyhow::Result<Vec<Value>> {
+        let mut items = vec![];
+
+        for _ in 0..len {
+            let event = self.required()?;
+            items.push(self.build(event)?);
+        }
+
+        self.end().map(|_| items)
+    }
+
+    fn pairs(&mut self, len: usize) -> anyhow::Result<Vec<(Value, Value)>> {
+        let mut pairs = vec![];
+
+        for _ in 0..len {
+            let event = self.required()?;
+            let k = self.build(event)?;
+
+            let event = self.required()?;
+            pairs.push((k, self.build(event)?));
+        }
+
+        self.end().map(|_| pairs)
+    }
+
+    fn end(&mut self) -> anyhow::Result<()> {
+        match self.required()? {
+            PullEvent::End => Ok(()),
+            event => bail!("PullParser (expected end of aggregate, got {event:?})"),
+        }
+    }
+}
+
+impl<'a> Iterator for PullParser<'a> {
+    type Item = anyhow::Result<PullEvent<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.failed {
+            true => None,
+            false => self.next_event().transpose(),
+        }
+    }
+}
+
+// Attributes go first: every scalar parser would swallow one as its own prefix.
+fn event(input: &[u8]) -> IResult<&[u8], PullEvent<'_>> {
+    alt((
+        Attribute::parse_len.map(PullEvent::AttributeStart),
+        Array::parse_len.map(PullEvent::ArrayStart),
+        BigNumber::parse.map(|v| PullEvent::BigNumber(v.val().clone())),
+        BlobError::parse.map(PullEvent::BlobError),
+        BlobString::parse_val.map(PullEvent::Blob),
+        Boolean::parse.map(|v| PullEvent::Boolean(v.val())),
+        Double::parse.map(|v| PullEvent::Double(v.val())),
+        Map::parse_len.map(PullEvent::MapStart),
+        Null::parse.map(|_| PullEvent::Null),
+        Number::parse.map(|v| PullEvent::Number(v.val())),
+        Push::parse_len.map(PullEvent::PushStart),
+        Set::parse_len.map(PullEvent::SetStart),
+        SimpleError::parse.map(PullEvent::SimpleError),
+        recognize(SimpleString::parse).map(|v: &[u8]| PullEvent::SimpleString(&v[1..v.len() - 2])),
+        VerbatimString::parse.map(PullEvent::Verbatim),
+    ))
+    .parse(input)
+}
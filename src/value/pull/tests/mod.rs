@@ -0,0 +1,157 @@
+pub mod prop;
+
+use crate::value::*;
+
+fn events(input: &[u8]) -> anyhow::Result<Vec<PullEvent<'_>>> {
+    PullParser::new(input).collect()
+}
+
+#[test]
+fn test_scalars() {
+    assert_eq!(
+        events(b"+OK\r\n$3\r\nabc\r\n:-1\r\n,1.5\r\n#t\r\n_\r\n$-1\r\n").unwrap(),
+        vec![
+            PullEvent::SimpleString(b"OK"),
+            PullEvent::Blob(b"abc"),
+            PullEvent::Number(-1),
+            PullEvent::Double(1.5),
+            PullEvent::Boolean(true),
+            PullEvent::Null,
+            PullEvent::Null,
+        ]
+    );
+}
+
+#[test]
+fn test_aggregates() {
+    assert_eq!(
+        events(b"%2\r\n+a\r\n*0\r\n+b\r\n~1\r\n:1\r\n>1\r\n+c\r\n").unwrap(),
+        vec![
+            PullEvent::MapStart(2),
+            PullEvent::SimpleString(b"a"),
+            PullEvent::ArrayStart(0),
+            PullEvent::End,
+            PullEvent::SimpleString(b"b"),
+            PullEvent::SetStart(1),
+            PullEvent::Number(1),
+            PullEvent::End,
+            PullEvent::End,
+            PullEvent::PushStart(1),
+            PullEvent::SimpleString(b"c"),
+            PullEvent::End,
+        ]
+    );
+}
+
+#[test]
+fn test_attribute() {
+    assert_eq!(
+        events(b"*2\r\n|1\r\n+ttl\r\n:3\r\n$1\r\nv\r\n:2\r\n").unwrap(),
+        vec![
+            PullEvent::ArrayStart(2),
+            PullEvent::AttributeStart(1),
+            PullEvent::SimpleString(b"ttl"),
+            PullEvent::Number(3),
+            PullEvent::End,
+            PullEvent::Blob(b"v"),
+            PullEvent::Number(2),
+            PullEvent::End,
+        ]
+    );
+
+    assert!(events(b"|1\r\n+a\r\n+b\r\n").is_err());
+    assert!(events(b"|1\r\n+a\r\n+b\r\n|1\r\n+a\r\n+b\r\n:1\r\n").is_err());
+}
+
+#[test]
+fn test_depth_and_raw() {
+    let mut parser = PullParser::new(b"*1\r\n$2\r\nab\r\n");
+
+    assert_eq!(parser.next_event().unwrap(), Some(PullEvent::ArrayStart(1)));
+    assert_eq!((parser.depth(), parser.raw()), (1, &b"*1\r\n"[..]));
+
+    assert_eq!(parser.next_event().unwrap(), Some(PullEvent::Blob(b"ab")));
+    assert_eq!(parser.raw(), b"$2\r\nab\r\n");
+
+    assert_eq!(parser.next_event().unwrap(), Some(PullEvent::End));
+    assert_eq!((parser.depth(), parser.offset()), (0, 12));
+
+    assert_eq!(parser.next_event().unwrap(), None);
+}
+
+#[test]
+fn test_next_value() {
+    let mut parser = PullParser::new(b"~3\r\n+a\r\n*1\r\n:1\r\n+c\r\n");
+
+    assert_eq!(parser.next_event().unwrap(), Some(PullEvent::SetStart(3)));
+
+    let members = (0..3)
+        .map(|_| parser.next_value().unwrap().unwrap())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        members,
+        vec![
+            Value::from(SimpleString::new("a").unwrap()),
+            Value::from(Array::from([Value::from(Number::from(1))])),
+            Value::from(SimpleString::new("c").unwrap()),
+        ]
+    );
+
+    assert_eq!(parser.next_event().unwrap(), Some(PullEvent::End));
+    assert_eq!(parser.next_value().unwrap(), None);
+}
+
+#[test]
+fn test_next_value_duplicates() {
+    let raw = b"*2\r\n%3\r\n+b\r\n:1\r\n+a\r\n:2\r\n+b\r\n:3\r\n~3\r\n:2\r\n:1\r\n:2\r\n";
+
+    let next_value = |duplicates| {
+        PullParser::new(raw)
+            .with_options(DecodeOptions { duplicates })
+            .next_value()
+    };
+
+    // the same as decoding the frame at once
+    for duplicates in [Duplicates::Keep, Duplicates::LastWins] {
+        assert_eq!(
+            next_value(duplicates).unwrap(),
+            Value::decode_with(raw, &DecodeOptions { duplicates })
+                .unwrap()
+                .map(|(value, _)| value)
+        );
+    }
+    assert_ne!(
+        next_value(Duplicates::Keep).unwrap(),
+        next_value(Duplicates::LastWins).unwrap()
+    );
+    assert!(next_value(Duplicates::Reject).is_err());
+}
+
+#[test]
+fn test_malformed() {
+    let mut parser = PullParser::new(b"*2\r\n:1\r\n?\r\n");
+
+    assert!(matches!(parser.next(), Some(Ok(PullEvent::ArrayStart(2)))));
+    assert!(matches!(parser.next(), Some(Ok(PullEvent::Number(1)))));
+    assert!(matches!(parser.next(), Some(Err(_))));
+    assert!(parser.next().is_none());
+
+    assert!(events(b"*2\r\n:1\r\n").is_err());
+}
+
+#[test]
+fn test_value_parse() {
+    // the complete parser is built on the pull parser and stops where it does
+    let raw = &b"*2\r\n:1\r\n|1\r\n+ttl\r\n:5\r\n+a\r\n:2\r\n"[..];
+    let (rest, value) = Value::parse(raw).unwrap();
+
+    let mut parser = PullParser::new(raw);
+    assert_eq!(parser.next_value().unwrap(), Some(value));
+    assert_eq!(rest, &raw[parser.offset()..]);
+    assert_eq!(rest, b":2\r\n");
+
+    assert!(Value::parse(b"*2\r\n:1\r\n?\r\n").is_err());
+    assert!(Value::parse(b"*2\r\n:1\r\n").is_err());
+    assert!(Array::parse(b":1\r\n").is_err());
+}
@@ -0,0 +1,28 @@
+use bytes::Bytes;
+use proptest::prelude::*;
+
+use crate::value::{
+    complete::special::attribute::tests::prop::value as attr_value, PullParser, Value,
+};
+
+proptest! {
+    #[test]
+    fn test_basic(v in crate::value::tests::prop::value()) {
+        let bytes = Bytes::try_from(v.clone()).unwrap();
+        let (_, parsed) = Value::parse(&bytes).unwrap();
+
+        let mut parser = PullParser::new(&bytes);
+        assert_eq!(parser.next_value().unwrap(), Some(parsed));
+        assert_eq!(parser.next_value().unwrap(), None);
+        assert_eq!(parser.offset(), bytes.len());
+    }
+
+    #[test]
+    fn test_with_attr(v in crate::value::tests::prop::value(), attr in attr_value()) {
+        let v = v.with_attr(attr);
+        let bytes = Bytes::try_from(v.clone()).unwrap();
+        let (_, parsed) = Value::parse(&bytes).unwrap();
+
+        assert_eq!(PullParser::new(&bytes).next_value().unwrap(), Some(parsed));
+    }
+}
@@ -13,9 +13,9 @@ fn test_basic_array() {
         Value::parse(&b"*1\r\n+test\r\n"[..]),
         Ok((
             &b""[..],
-            Value::Array(Array::from([Value::SimpleString(SimpleString::from(
-                "test"
-            ))]))
+            Value::Array(Array::from([Value::SimpleString(
+                SimpleString::new("test").unwrap()
+            )]))
         ))
     );
 }
@@ -45,10 +45,7 @@ fn test_basic_big_number() {
 fn test_basic_blob_error() {
     assert_eq!(
         Value::parse(&b"!10\r\nERR reason\r\n"[..]),
-        Ok((
-            &b""[..],
-            Value::BlobError(BlobError::new("ERR", b"reason".to_vec()))
-        ))
+        Ok((&b""[..], Value::BlobError(BlobError::new("ERR reason"))))
     );
 }
 
@@ -93,7 +90,7 @@ fn test_basic_simple_error() {
         Value::parse(&b"-ERR reason\r\n"[..]),
         Ok((
             &b""[..],
-            Value::SimpleError(SimpleError::new("ERR", "reason"))
+            Value::SimpleError(SimpleError::new("ERR reason").unwrap())
         ))
     );
 }
@@ -102,7 +99,10 @@ fn test_basic_simple_error() {
 fn test_basic_simple_string() {
     assert_eq!(
         Value::parse(&b"+test\r\n"[..]),
-        Ok((&b""[..], Value::SimpleString(SimpleString::from("test"))))
+        Ok((
+            &b""[..],
+            Value::SimpleString(SimpleString::new("test").unwrap())
+        ))
     );
 }
 
@@ -21,6 +21,7 @@ pub fn value() -> impl Strategy<Value = Value> {
             p::collection::vec(e.clone(), 0..16)
                 .prop_map(|values| Value::from(Array::from(values))),
             p::collection::vec(e.clone(), 0..16).prop_map(|values| Value::from(Set::from(values))),
+            p::collection::vec(e.clone(), 0..16).prop_map(|values| Value::from(Push::from(values))),
             p::collection::vec((e.clone(), e.clone()), 0..16)
                 .prop_map(|values| Value::from(Map::from(values))),
         ]
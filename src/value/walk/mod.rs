@@ -0,0 +1,66 @@
+#[cfg(test)]
+pub(crate) mod tests;
+
+use super::{Attribute, Value};
+
+/// One step from a value down to one of its children.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PathSegment<'a> {
+    /// An element of an array, a set or a push, by position.
+    Index(usize),
+    /// A map key itself.
+    Key(&'a Value),
+    /// The value stored under a map key.
+    Entry(&'a Value),
+}
+
+impl Value {
+    /// Collects every attribute in the reply, depth first, along with the path leading
+    /// to the value it describes. The root's attribute has an empty path.
+    pub fn attributes(&self) -> Vec<(Vec<PathSegment<'_>>, &Attribute)> {
+        let mut path = vec![];
+        let mut out = vec![];
+        walk(self, &mut path, &mut out);
+
+        out
+    }
+}
+
+fn walk<'a>(
+    value: &'a Value,
+    path: &mut Vec<PathSegment<'a>>,
+    out: &mut Vec<(Vec<PathSegment<'a>>, &'a Attribute)>,
+) {
+    if let Some(attr) = value.attr() {
+        out.push((path.clone(), attr));
+    }
+
+    let mut descend = |segment, child| {
+        path.push(segment);
+        walk(child, path, out);
+        path.pop();
+    };
+
+    match value {
+        Value::Array(inner) => inner
+            .val()
+            .iter()
+            .enumerate()
+            .for_each(|(i, v)| descend(PathSegment::Index(i), v)),
+        Value::Set(inner) => inner
+            .val()
+            .iter()
+            .enumerate()
+            .for_each(|(i, v)| descend(PathSegment::Index(i), v)),
+        Value::Push(inner) => inner
+            .val()
+            .iter()
+            .enumerate()
+            .for_each(|(i, v)| descend(PathSegment::Index(i), v)),
+        Value::Map(inner) => inner.iter().for_each(|(k, v)| {
+            descend(PathSegment::Key(k), k);
+            descend(PathSegment::Entry(k), v);
+        }),
+        _ => {}
+    }
+}
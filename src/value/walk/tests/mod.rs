@@ -0,0 +1,75 @@
+use super::*;
+use crate::value::*;
+
+fn attr(name: &str, val: i64) -> Attribute {
+    Attribute::from([(
+        Value::from(BlobString::from(name.to_string())),
+        Value::from(Number::from(val)),
+    )])
+}
+
+#[test]
+fn test_no_attributes() {
+    let value = Value::from(Array::from([Value::from(Number::from(1))]));
+
+    assert!(value.attributes().is_empty());
+}
+
+#[test]
+fn test_root() {
+    let value = Value::from(Number::from(1).with_attr(attr("ttl", 3600)));
+
+    assert_eq!(value.attributes(), vec![(vec![], &attr("ttl", 3600))]);
+}
+
+#[test]
+fn test_nested() {
+    let raw = "\
+               *2\r\n\
+               :1\r\n\
+               %1\r\n\
+               |1\r\n+key\r\n:1\r\n\
+               +a\r\n\
+               |1\r\n+val\r\n:2\r\n\
+               *1\r\n\
+               |1\r\n+deep\r\n:3\r\n\
+               _\r\n\
+               ";
+    let (_, value) = Value::parse(raw.as_bytes()).unwrap();
+
+    let key = Value::from(SimpleString::new("a").unwrap().with_attr(Attribute::from([(
+        Value::from(SimpleString::new("key").unwrap()),
+        Value::from(Number::from(1)),
+    )])));
+
+    let found = value.attributes();
+    let paths = found
+        .iter()
+        .map(|(path, _)| path.clone())
+        .collect::<Vec<_>>();
+    let ttls = found
+        .iter()
+        .map(|(_, attr)| attr.iter().next().map(|(_, v)| v.clone()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        paths,
+        vec![
+            vec![PathSegment::Index(1), PathSegment::Key(&key)],
+            vec![PathSegment::Index(1), PathSegment::Entry(&key)],
+            vec![
+                PathSegment::Index(1),
+                PathSegment::Entry(&key),
+                PathSegment::Index(0)
+            ],
+        ]
+    );
+    assert_eq!(
+        ttls,
+        vec![
+            Some(Value::from(Number::from(1))),
+            Some(Value::from(Number::from(2))),
+            Some(Value::from(Number::from(3))),
+        ]
+    );
+}